@@ -2,10 +2,24 @@
 
 use std::{
     cell::RefCell,
-    sync::{Arc, Mutex},
+    future::Future,
+    ops::{Deref, DerefMut},
+    pin::Pin,
+    result,
+    sync::{
+        atomic::{AtomicBool, Ordering::SeqCst},
+        mpsc, Arc, Condvar, Mutex,
+    },
+    time,
 };
 
-use crate::{endpoints::Endpoints, Config, Error, Info, Random, Result};
+use futures::future::{AbortHandle, AbortRegistration, Abortable, Aborted};
+
+use crate::{
+    endpoints::{ClientState, Endpoints},
+    Config, EndpointConfig, Error, GroupInfo, Info, Random, Result, Timed, VerifiedRandom,
+    VerifyFailure,
+};
 
 /// List of available endpoints.
 #[derive(Clone)]
@@ -18,35 +32,98 @@ pub enum Endpoint {
     HttpDrandApi3,
     /// https://drand.cloudflare.com
     HttpCloudflare,
+    /// Self-hosted relay or mirror, `base_url` shall be an absolute
+    /// http/https url like `https://drand.example.com`. Plain http, like
+    /// `http://127.0.0.1:8080` for a local node, skips TLS, refer
+    /// [Config::https_only].
+    HttpCustom { base_url: String },
+    /// drand node's gRPC api, `addr` shall be an absolute http/https url
+    /// like `https://drand.example.com:4444`. Requires `grpc` feature.
+    /// gRPC api can't address a chain by its hash, the node's default
+    /// chain is served and a pinned [Config::chain_hash] is only checked
+    /// against it while booting.
+    #[cfg(feature = "grpc")]
+    Grpc { addr: String },
 }
 
 /// Type to interface with league-of-entropy.
+///
+/// Clones of a client share its endpoints, state and
+/// [Config::max_inflight_ops], and can be moved to other threads to
+/// share the client. Operations across clones are serialized.
+#[derive(Clone)]
 pub struct Client {
     name: String,
     inner: Arc<Mutex<RefCell<InnerClient>>>,
+    inflight: Inflight,
 }
 
 struct InnerClient {
     _config: Config,
     endpoints: Option<Endpoints>,
+    // clients booted via boot_chains(), keyed by chain-hash.
+    chains: Vec<(Vec<u8>, Client)>,
 }
 
 impl Client {
     /// Create a new client from `config` value, all clients are named.
     /// Caller can choose a meaningful name.
     pub fn from_config(name: &str, config: Config) -> Client {
+        let inflight = Inflight::new(config.max_inflight_ops);
         let inner = InnerClient {
             _config: config.clone(),
             endpoints: Some(Endpoints::from_config(name, config)),
+            chains: Vec::default(),
         };
         Client {
             name: name.to_string(),
             inner: Arc::new(Mutex::new(RefCell::new(inner))),
+            inflight,
         }
     }
 
+    /// Restore a client from a [ClientState] previously obtained via
+    /// [export_state], skipping the [boot] process. Endpoints are to be
+    /// added after restoring, their latency history is carried over from
+    /// the snapshot. `check_point` in `config` is ignored in favor of the
+    /// snapshot's check_point.
+    ///
+    /// Refer [ClientState] for what is trusted and what is re-validated.
+    pub fn from_state(config: Config, state: ClientState) -> Result<Client> {
+        let endpoints = Endpoints::from_state(config.clone(), state)?;
+        let name = endpoints.to_name();
+        let inflight = Inflight::new(config.max_inflight_ops);
+        let inner = InnerClient {
+            _config: config,
+            endpoints: Some(endpoints),
+            chains: Vec::default(),
+        };
+        let val = Client {
+            name,
+            inner: Arc::new(Mutex::new(RefCell::new(inner))),
+            inflight,
+        };
+        Ok(val)
+    }
+
+    /// Export a snapshot of this client's state, that can be persisted
+    /// and later restored using [from_state].
+    pub fn export_state(&self) -> Result<ClientState> {
+        let inner = err_at!(PoisonedLock, self.inner.lock())?;
+        let state = inner.borrow().endpoints.as_ref().unwrap().to_client_state();
+        Ok(state)
+    }
+
     /// Add an endpoint to the client. Typically, endpoints are added to
     /// the [Client] instance before called after its [boot] method.
+    /// An endpoint resolving to an already added host is dropped, use
+    /// [to_endpoints] to learn the distinct hosts in use and
+    /// [to_duplicates] to learn the dropped ones. Return
+    /// `Error::Invalid` for a malformed [Endpoint::HttpCustom] url, or a
+    /// plain http url with [Config::https_only].
+    ///
+    /// [to_endpoints]: Client::to_endpoints
+    /// [to_duplicates]: Client::to_duplicates
     pub fn add_endpoint(&mut self, endp: Endpoint) -> Result<&mut Self> {
         {
             let inner = err_at!(PoisonedLock, self.inner.lock())?;
@@ -55,13 +132,46 @@ impl Client {
                 .endpoints
                 .as_mut()
                 .unwrap()
-                .add_endpoint(endp);
+                .add_endpoint(endp)?;
         }
         Ok(self)
     }
 
+    /// Same as [add_endpoint], with `config` overriding the client-wide
+    /// timeouts and connection limits for this endpoint alone.
+    pub fn add_endpoint_with(
+        &mut self,
+        endp: Endpoint,
+        config: EndpointConfig,
+    ) -> Result<&mut Self> {
+        {
+            let inner = err_at!(PoisonedLock, self.inner.lock())?;
+            inner
+                .borrow_mut()
+                .endpoints
+                .as_mut()
+                .unwrap()
+                .add_endpoint_with(endp, config)?;
+        }
+        Ok(self)
+    }
+
+    /// Return the hash-info from drand-group, its public key, period,
+    /// genesis time and chain hash, as cached by the [boot] method.
+    /// Return `Error::Invalid` if the client is not booted yet. Compare
+    /// `hash` with the intended chain hash to confirm the chain this
+    /// client is connected to.
+    pub fn info(&self) -> Result<Info> {
+        let info = self.to_info()?;
+        if info.hash.is_empty() {
+            err_at!(Invalid, msg: format!("client {} not booted", self.name))?
+        }
+        Ok(info)
+    }
+
     /// Return the hash-info from drand-group. This call is meaningful
-    /// only after the [boot] method is called on this client.
+    /// only after the [boot] method is called on this client, refer
+    /// [Client::info] for a checked variant.
     pub fn to_info(&self) -> Result<Info> {
         let info = {
             let inner = err_at!(PoisonedLock, self.inner.lock())?;
@@ -71,6 +181,174 @@ impl Client {
         Ok(info)
     }
 
+    /// Return the client's check_point, the latest round verified so far.
+    /// After [boot], with `determinism`, every round from the configured
+    /// check_point, or round-1, till the check_point is chain-verified.
+    /// Subsequent [get] calls chain from this trusted anchor.
+    pub fn to_check_point(&self) -> Result<Option<Random>> {
+        let inner = err_at!(PoisonedLock, self.inner.lock())?;
+        let check_point = inner.borrow().endpoints.as_ref().unwrap().to_check_point();
+        Ok(check_point)
+    }
+
+    /// Estimate the latest round from hash-info and the local clock,
+    /// without a network call. Meaningful only after the [boot] method is
+    /// called on this client. Clock skew between local clock and the
+    /// drand-group can make the estimate off-by-one near a period's
+    /// boundary, and a round is published only after the group has
+    /// signed it. A known skew can be corrected for via
+    /// [Config::set_clock_skew], and the estimate is never less than the
+    /// latest round fetched so far.
+    pub fn current_round(&self) -> Result<u128> {
+        let inner = err_at!(PoisonedLock, self.inner.lock())?;
+        let round = inner.borrow().endpoints.as_ref().unwrap().current_round();
+        round
+    }
+
+    /// Return the drand-group's setup, if the endpoint exposes it as part
+    /// of hash-info. This call is meaningful only after the [boot] method
+    /// is called on this client.
+    pub fn group_info(&self) -> Result<Option<GroupInfo>> {
+        Ok(self.to_info()?.group)
+    }
+
+    /// Return the base-url of every endpoint added to this client.
+    pub fn to_endpoints(&self) -> Result<Vec<String>> {
+        let inner = err_at!(PoisonedLock, self.inner.lock())?;
+        let endpoints = inner.borrow().endpoints.as_ref().unwrap().to_endpoints();
+        Ok(endpoints)
+    }
+
+    /// Return the base-url of every endpoint dropped by [add_endpoint]
+    /// for resolving to an already added host, in the order they were
+    /// added.
+    ///
+    /// [add_endpoint]: Client::add_endpoint
+    pub fn to_duplicates(&self) -> Result<Vec<String>> {
+        let inner = err_at!(PoisonedLock, self.inner.lock())?;
+        let duplicates = inner.borrow().endpoints.as_ref().unwrap().to_duplicates();
+        Ok(duplicates)
+    }
+
+    /// Return the base-url of every endpoint along with its effective
+    /// max_conns, refer [Config::max_conns] and
+    /// [EndpointConfig::max_conns]. This bounds the number of rounds
+    /// [get_range] fetches concurrently from the endpoint, and caps its
+    /// idle connections kept for reuse. With [Config::http_client] only
+    /// the former applies, its pool is sized by the caller.
+    ///
+    /// [get_range]: Client::get_range
+    pub fn to_max_conns(&self) -> Result<Vec<(String, usize)>> {
+        let inner = err_at!(PoisonedLock, self.inner.lock())?;
+        let max_conns = inner.borrow().endpoints.as_ref().unwrap().to_max_conns();
+        Ok(max_conns)
+    }
+
+    /// Return the base-url of every endpoint along with its latency, the
+    /// rolling average over its recent fetches that drives endpoint
+    /// selection. Failed fetches and stale rounds are counted as twice
+    /// the average, demoting the endpoint. An endpoint yet to be measured
+    /// reports `u64::MAX` seconds. Cheap, no network call is made.
+    pub fn endpoint_latencies(&self) -> Result<Vec<(String, time::Duration)>> {
+        let inner = err_at!(PoisonedLock, self.inner.lock())?;
+        let latencies = inner
+            .borrow()
+            .endpoints
+            .as_ref()
+            .unwrap()
+            .endpoint_latencies();
+        Ok(latencies)
+    }
+
+    /// Subscribe to rounds that fail verification. A failure is reported
+    /// even when the client falls back on another endpoint and the call
+    /// succeeds, surfacing flaky or malicious endpoints. Subscribing again
+    /// replaces the earlier subscription.
+    pub fn subscribe_failures(&mut self) -> Result<mpsc::Receiver<VerifyFailure>> {
+        let inner = err_at!(PoisonedLock, self.inner.lock())?;
+        let rx = inner
+            .borrow_mut()
+            .endpoints
+            .as_mut()
+            .unwrap()
+            .subscribe_failures();
+        Ok(rx)
+    }
+
+    /// Return the number of bytes left in the client's byte budget, None
+    /// if there is no budget. Refer [Config::byte_budget].
+    pub fn to_remaining_budget(&self) -> Result<Option<usize>> {
+        let inner = err_at!(PoisonedLock, self.inner.lock())?;
+        let remaining = inner
+            .borrow()
+            .endpoints
+            .as_ref()
+            .unwrap()
+            .to_remaining_budget();
+        Ok(remaining)
+    }
+
+    /// Reset the bytes downloaded so far, restoring the full byte budget.
+    pub fn reset_budget(&self) -> Result<()> {
+        let inner = err_at!(PoisonedLock, self.inner.lock())?;
+        inner.borrow().endpoints.as_ref().unwrap().reset_budget();
+        Ok(())
+    }
+
+    /// Pin every subsequent fetch to the endpoint with `base_url`, as
+    /// listed by [to_endpoints], bypassing the configured
+    /// [SelectionStrategy](crate::SelectionStrategy) and latency. Useful
+    /// during an incident to steer clear of mirrors serving bad data.
+    /// There is no fall over to other endpoints while pinned, fetches
+    /// fail if the pinned endpoint fails, and so do fetches requiring a
+    /// [quorum](crate::Config::quorum). Pass None to restore normal
+    /// selection. Return `Error::Invalid` if no such endpoint is added.
+    ///
+    /// [to_endpoints]: Client::to_endpoints
+    pub fn set_preferred(&mut self, base_url: Option<&str>) -> Result<()> {
+        let inner = err_at!(PoisonedLock, self.inner.lock())?;
+        let res = inner
+            .borrow_mut()
+            .endpoints
+            .as_mut()
+            .unwrap()
+            .set_preferred(base_url);
+        res
+    }
+
+    /// Return the base-url of the endpoint pinned via [set_preferred].
+    ///
+    /// [set_preferred]: Client::set_preferred
+    pub fn to_preferred(&self) -> Result<Option<String>> {
+        let inner = err_at!(PoisonedLock, self.inner.lock())?;
+        let preferred = inner.borrow().endpoints.as_ref().unwrap().to_preferred();
+        Ok(preferred)
+    }
+
+    /// Forget the latency history of every endpoint, putting them on an
+    /// equal footing for subsequent [get] calls. Useful once a known
+    /// connectivity outage is over, or after waking from sleep, so that
+    /// endpoints that were briefly slow or unreachable are picked again.
+    ///
+    /// [get]: Client::get
+    pub fn reset_metrics(&mut self) -> Result<()> {
+        let inner = err_at!(PoisonedLock, self.inner.lock())?;
+        inner
+            .borrow_mut()
+            .endpoints
+            .as_mut()
+            .unwrap()
+            .reset_metrics();
+        Ok(())
+    }
+
+    /// Return the number of [boot] and [get] operations in flight,
+    /// including those waiting for a slot under
+    /// [Config::max_inflight_ops].
+    pub fn to_inflight_ops(&self) -> Result<usize> {
+        self.inflight.to_count()
+    }
+
     /// Return back the client's name.
     pub fn to_name(&self) -> String {
         self.name.clone()
@@ -78,10 +356,25 @@ impl Client {
 
     /// Boot a client. Will verify the endpoint's hash-info and if
     /// configured verify the chain of randomness from root-of-trust or
-    /// previous-check-point to latest randomness.
+    /// previous-check-point to latest randomness. `chain_hash`, if None,
+    /// defaults to [Config::chain_hash].
+    ///
+    /// Errors are prefixed with the failed phase and endpoint, like
+    /// `boot-phase1 https://api.drand.sh`. `phase1` fetches hash-info and
+    /// latest round, `agreement` cross-validates them across endpoints and
+    /// `phase2` establishes the check_point.
+    ///
+    /// With cross-validation, every endpoint is booted concurrently and
+    /// validated against the first endpoint to succeed. An endpoint
+    /// disagreeing with it fails the boot with `Error::NotSecure`, refer
+    /// [Error::to_mismatch]. Without cross-validation, the fastest endpoint
+    /// is booted, falling over to the next fastest on failure, endpoints
+    /// with no latency measured yet are tried in the order they were
+    /// added, refer [Config::cross_validate]. Either way,
+    /// an endpoint that fails otherwise, say unreachable, is dropped from
+    /// the client, and the boot fails only if no endpoint is left.
     pub fn boot(&mut self, chain_hash: Option<Vec<u8>>) -> Result<()> {
-        use futures::executor::block_on;
-
+        let _slot = self.inflight.acquire()?;
         let fut = async {
             let inner = err_at!(PoisonedLock, self.inner.lock())?;
             inner
@@ -93,13 +386,92 @@ impl Client {
                 .await?;
             Ok::<(), Error>(())
         };
-        block_on(fut)
+        block_on(fut)?
     }
 
-    /// Get requested round of randomness.
-    pub fn get(&mut self, round: Option<u128>) -> Result<Random> {
-        use futures::executor::block_on;
+    /// Boot a client for each chain in `chains`, concurrently, on this
+    /// client's http endpoints addressing the chain by its hash. Each
+    /// chain keeps its own hash-info and check_point, and is booted like
+    /// [boot] with the chain-hash as its root of trust. Booting fails if
+    /// any of the chains can't be booted.
+    ///
+    /// Booted chains replace the ones from a previous call, and are
+    /// obtained via [to_chain]. gRPC endpoints can't address a chain
+    /// by its hash and are not used for them.
+    ///
+    /// [boot]: Client::boot
+    /// [to_chain]: Client::to_chain
+    pub fn boot_chains(&mut self, chains: &[Vec<u8>]) -> Result<()> {
+        let _slot = self.inflight.acquire()?;
+        let fut = async {
+            let inner = err_at!(PoisonedLock, self.inner.lock())?;
+            let endpoints = {
+                let inner = inner.borrow();
+                inner
+                    .endpoints
+                    .as_ref()
+                    .unwrap()
+                    .boot_chains(chains)
+                    .await?
+            };
+            let config = inner.borrow()._config.clone();
+            inner.borrow_mut().chains = chains
+                .iter()
+                .cloned()
+                .zip(endpoints)
+                .map(|(chain_hash, endpoints)| {
+                    let inner = InnerClient {
+                        _config: config.clone(),
+                        endpoints: Some(endpoints),
+                        chains: Vec::default(),
+                    };
+                    let client = Client {
+                        name: self.name.clone(),
+                        inner: Arc::new(Mutex::new(RefCell::new(inner))),
+                        inflight: self.inflight.clone(),
+                    };
+                    (chain_hash, client)
+                })
+                .collect();
+            Ok::<(), Error>(())
+        };
+        block_on(fut)?
+    }
 
+    /// Return the client for `chain_hash`, booted via [boot_chains]. The
+    /// returned client shares [Config::max_inflight_ops] with this client.
+    ///
+    /// [boot_chains]: Client::boot_chains
+    pub fn to_chain(&self, chain_hash: &[u8]) -> Result<Client> {
+        let inner = err_at!(PoisonedLock, self.inner.lock())?;
+        let inner = inner.borrow();
+        match inner.chains.iter().find(|(hash, _)| hash == chain_hash) {
+            Some((_, client)) => Ok(client.clone()),
+            None => err_at!(Invalid, msg: format!("chain {} not booted", hex::encode(chain_hash))),
+        }
+    }
+
+    /// Get requested round of randomness. Latest round is fetched when
+    /// `round` is None.
+    ///
+    /// Fetched rounds advance the client's check_point, which is
+    /// monotonic. Fetching a round older than the check_point, or a stale
+    /// latest round from a lagging endpoint, leaves it untouched.
+    ///
+    /// In secure mode, if verification fails, hash-info is refreshed once
+    /// from the endpoints, anchored to the current chain-hash, and the
+    /// round is fetched again before returning `Error::NotSecure`. The
+    /// refreshed hash-info is cross-validated across endpoints, even with
+    /// [Config::cross_validate] disabled, and shall verify the check_point.
+    ///
+    /// Use [get_timed] to also learn which endpoint served the round, and
+    /// [get_latest] or [get_round] to spell out the intent.
+    ///
+    /// [get_timed]: Client::get_timed
+    /// [get_latest]: Client::get_latest
+    /// [get_round]: Client::get_round
+    pub fn get(&mut self, round: Option<u128>) -> Result<Random> {
+        let _slot = self.inflight.acquire()?;
         let fut = async {
             let inner = err_at!(PoisonedLock, self.inner.lock())?;
             let r = inner
@@ -111,8 +483,664 @@ impl Client {
                 .await?;
             Ok::<Random, Error>(r)
         };
-        block_on(fut)
+        block_on(fut)?
     }
+
+    /// Get the latest round of randomness, same as `get(None)`.
+    ///
+    /// Latest round is as seen by the endpoint that served it. Mirrors
+    /// publish a round a little apart, hence the returned round may still
+    /// be propagating and other endpoints can respond with 404 for it for
+    /// a short while. Round before the returned round, or rounds up to
+    /// [current_round] less one, are settled across the drand-group.
+    ///
+    /// [current_round]: Client::current_round
+    pub fn get_latest(&mut self) -> Result<Random> {
+        self.get(None)
+    }
+
+    /// Get randomness for `round`, same as `get(Some(round))`. Return
+    /// `Error::RoundUnavailable` if the round is yet to be published,
+    /// endpoints respond with 404 for it, callers can wait and retry, or
+    /// use [watch] to wait for a future round.
+    ///
+    /// [watch]: Client::watch
+    pub fn get_round(&mut self, round: u128) -> Result<Random> {
+        self.get(Some(round))
+    }
+
+    /// Same as [get] but additionally return the latency of the fetch and
+    /// the endpoint that served the randomness.
+    pub fn get_timed(&mut self, round: Option<u128>) -> Result<Timed<Random>> {
+        let _slot = self.inflight.acquire()?;
+        let fut = async {
+            let inner = err_at!(PoisonedLock, self.inner.lock())?;
+            let r = inner
+                .borrow_mut()
+                .endpoints
+                .as_mut()
+                .unwrap()
+                .get_timed(round)
+                .await?;
+            Ok::<Timed<Random>, Error>(r)
+        };
+        block_on(fut)?
+    }
+
+    /// Same as [get_timed] but give up once `deadline` passes, returning
+    /// `Error::Timeout`. Unlike [Config::timeout], which bounds a single
+    /// request, the deadline bounds the entire call, including retries
+    /// and falling over to other endpoints. Useful to enforce a caller's
+    /// own SLA. `deadline` is measured on the monotonic clock, irrespective
+    /// of [Config::set_clock].
+    ///
+    /// [get_timed]: Client::get_timed
+    pub fn get_deadline(
+        &mut self,
+        round: Option<u128>,
+        deadline: time::Instant,
+    ) -> Result<Timed<Random>> {
+        let _slot = self.inflight.acquire()?;
+        let fut = async {
+            let inner = err_at!(PoisonedLock, self.inner.lock())?;
+            let r = inner
+                .borrow_mut()
+                .endpoints
+                .as_mut()
+                .unwrap()
+                .get_deadline(round, deadline)
+                .await?;
+            Ok::<Timed<Random>, Error>(r)
+        };
+        block_on(fut)?
+    }
+
+    /// Ping every endpoint's hash-info concurrently and return whether
+    /// each is reachable, in the order of [to_endpoints]. Unreachable
+    /// endpoints are skipped by [get] till a later health check finds
+    /// them reachable again, while reachable ones start afresh with the
+    /// measured latency. Can be called periodically, or on demand say
+    /// after a spell of failures.
+    ///
+    /// [to_endpoints]: Client::to_endpoints
+    /// [get]: Client::get
+    pub fn health_check(&mut self) -> Result<Vec<bool>> {
+        let _slot = self.inflight.acquire()?;
+        let fut = async {
+            let inner = err_at!(PoisonedLock, self.inner.lock())?;
+            let status = inner
+                .borrow_mut()
+                .endpoints
+                .as_mut()
+                .unwrap()
+                .health_check()
+                .await;
+            Ok::<Vec<bool>, Error>(status)
+        };
+        block_on(fut)?
+    }
+
+    /// Get rounds of randomness in `start..end`, in round order, fetching
+    /// up to [Config::max_conns] rounds concurrently from the fastest
+    /// endpoint. Useful for backfilling historical randomness.
+    ///
+    /// In secure mode, consecutive rounds are verified to chain from each
+    /// other and the first round's signature is verified, round-1 is
+    /// verified against the group_hash. Unlike [get], the check_point is
+    /// left untouched. Return `Error::Invalid` if `start` is zero or
+    /// beyond `end`.
+    ///
+    /// [get]: Client::get
+    pub fn get_range(&mut self, start: u128, end: u128) -> Result<Vec<Random>> {
+        let _slot = self.inflight.acquire()?;
+        let fut = async {
+            let inner = err_at!(PoisonedLock, self.inner.lock())?;
+            let rs = inner
+                .borrow_mut()
+                .endpoints
+                .as_mut()
+                .unwrap()
+                .get_range(start, end)
+                .await?;
+            Ok::<Vec<Random>, Error>(rs)
+        };
+        block_on(fut)?
+    }
+
+    /// Pre-fetch rounds in `start..end` into the client's cache, refer
+    /// [Config::cache], so that later [get] calls for them are served
+    /// without a network call. Rounds are fetched from the fastest
+    /// endpoint, up to [Config::max_conns] at a time, and verified as with
+    /// [get]. Rounds that fail to fetch or verify are skipped, return the
+    /// count of rounds warmed. As the cache holds a bounded number of
+    /// rounds, only the latest of a large range stay cached.
+    ///
+    /// Return `Error::Invalid` if the cache is not enabled, or if `start`
+    /// is zero or beyond `end`.
+    ///
+    /// [get]: Client::get
+    pub fn warm(&mut self, start: u128, end: u128) -> Result<usize> {
+        let _slot = self.inflight.acquire()?;
+        let fut = async {
+            let inner = err_at!(PoisonedLock, self.inner.lock())?;
+            let n = inner
+                .borrow_mut()
+                .endpoints
+                .as_mut()
+                .unwrap()
+                .warm(start, end)
+                .await?;
+            Ok::<usize, Error>(n)
+        };
+        block_on(fut)?
+    }
+
+    /// Audit the chain of rounds from `start` till `end`, excluding `end`,
+    /// calling `f` on each round, in round order, once it is verified.
+    /// Unlike [get_range], rounds are checked for their randomness, their
+    /// link with the previous round and their signature irrespective of
+    /// configuration, and are not held on to, so arbitrarily long chains
+    /// can be audited in bounded memory. The first round is linked to
+    /// group_hash if it is round-1, else only its signature is verified.
+    ///
+    /// Return the count of verified rounds. Stop at the first break with
+    /// `Error::NotSecure`, or at the first failed fetch, rounds verified
+    /// till then are already passed to `f`. Return `Error::Invalid` if
+    /// `start` is zero or beyond `end`.
+    ///
+    /// [get_range]: Client::get_range
+    pub fn verify_chain<F>(&mut self, start: u128, end: u128, mut f: F) -> Result<u128>
+    where
+        F: FnMut(Random),
+    {
+        use futures::stream::StreamExt;
+
+        let _slot = self.inflight.acquire()?;
+        let fut = async {
+            let inner = err_at!(PoisonedLock, self.inner.lock())?;
+            let mut inner = inner.borrow_mut();
+            let rounds = inner.endpoints.as_mut().unwrap().verify_chain(start, end)?;
+            futures::pin_mut!(rounds);
+
+            let mut n = 0;
+            while let Some(r) = rounds.next().await {
+                f(r?);
+                n += 1;
+            }
+            Ok::<u128, Error>(n)
+        };
+        block_on(fut)?
+    }
+
+    /// Fetch rounds from `start` till `end`, excluding `end`, calling `f`
+    /// on each round in round order. Meant for large historical syncs,
+    /// where [get_range] would hold the whole range in memory. Rounds are
+    /// fetched at most `max_conns` ahead of `f`, a slow `f` holds up
+    /// fetching, and are not held on to once passed to `f`.
+    ///
+    /// In secure mode, rounds are verified as with [verify_chain], else
+    /// they are taken as is. Return the count of rounds passed to `f`.
+    /// Stop at the first failed fetch or verification, rounds till then
+    /// are already passed to `f`. Return `Error::Invalid` if `start` is
+    /// zero or beyond `end`.
+    ///
+    /// [get_range]: Client::get_range
+    /// [verify_chain]: Client::verify_chain
+    pub fn for_each_round<F>(&mut self, start: u128, end: u128, mut f: F) -> Result<u128>
+    where
+        F: FnMut(Random),
+    {
+        use futures::stream::StreamExt;
+
+        let _slot = self.inflight.acquire()?;
+        let fut = async {
+            let inner = err_at!(PoisonedLock, self.inner.lock())?;
+            let mut inner = inner.borrow_mut();
+            let rounds = inner
+                .endpoints
+                .as_mut()
+                .unwrap()
+                .for_each_round(start, end)?;
+            futures::pin_mut!(rounds);
+
+            let mut n = 0;
+            while let Some(r) = rounds.next().await {
+                f(r?);
+                n += 1;
+            }
+            Ok::<u128, Error>(n)
+        };
+        block_on(fut)?
+    }
+
+    /// Watch the drand-group's beacon. Return an iterator that blocks
+    /// till the next round is published and yields it, verified as per
+    /// configuration, starting from the round after [current_round].
+    ///
+    /// A round that is late to land on the endpoints, refer
+    /// `Error::RoundUnavailable`, is polled with back-off till it appears.
+    /// Other transient failures are retried, falling over to other
+    /// endpoints, for about a period before the round's error is yielded,
+    /// and the iteration continues with the following round. Iteration
+    /// ends only if the client is not booted, or is stopped via
+    /// [WatchHandle]. While the iterator waits, clones of the client are
+    /// free to use it.
+    pub fn watch(&mut self) -> Watch<'_> {
+        Watch {
+            client: self,
+            next: None,
+            done: false,
+            handle: WatchHandle::default(),
+        }
+    }
+
+    /// Same as [watch], but rounds are yielded along with the outcome of
+    /// their verification, irrespective of [Config::secure]. Each round's
+    /// randomness and signature are verified, refer [Random::verify_with],
+    /// and a round that fails is yielded with `verified` as false, leaving
+    /// the consumer to decide how to treat it.
+    ///
+    /// With `secure=true`, [watch] yields an error for a round that fails
+    /// verification, this soft mode yields it instead. A failed round
+    /// doesn't advance the client's check_point.
+    ///
+    /// [watch]: Client::watch
+    pub fn watch_verified(&mut self) -> WatchVerified<'_> {
+        WatchVerified {
+            watch: self.watch(),
+        }
+    }
+
+    // wait for `round` to be published and fetch it using `fetch`, with
+    // back-off on failure, refer WatchRetry. The client is locked only
+    // to fetch, not while waiting, so that clones are not held up by a
+    // watch.
+    async fn watch_round<T, F>(&self, round: u128, fetch: F) -> Result<T>
+    where
+        F: for<'b> Fn(&'b mut Endpoints) -> Pin<Box<dyn Future<Output = Result<T>> + 'b>>,
+    {
+        let mut retry = {
+            let inner = err_at!(PoisonedLock, self.inner.lock())?;
+            let retry = inner
+                .borrow()
+                .endpoints
+                .as_ref()
+                .unwrap()
+                .to_watch_retry(round)?;
+            retry
+        };
+        retry.wait().await?;
+        loop {
+            let res = {
+                let inner = err_at!(PoisonedLock, self.inner.lock())?;
+                let mut inner = inner.borrow_mut();
+                fetch(inner.endpoints.as_mut().unwrap()).await
+            };
+            match res {
+                Ok(val) => break Ok(val),
+                Err(err) => {
+                    if !retry.retry(&err).await {
+                        break Err(err);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Verify `signature` for an arbitrary `message` against the booted
+    /// hash-info's public key, using the configured
+    /// [Config::set_verifier]. Caller is responsible for constructing the
+    /// message, refer [SignatureVerifier::verify_message]. Return
+    /// `Error::NotSecure` if the signature doesn't verify.
+    pub fn verify_message(&self, message: &[u8], signature: &[u8]) -> Result<()> {
+        let info = self.info()?;
+        let verifier = {
+            let inner = err_at!(PoisonedLock, self.inner.lock())?;
+            let verifier = inner.borrow().endpoints.as_ref().unwrap().to_verifier();
+            verifier
+        };
+        match verifier.verify_message(&info.public_key, message, signature)? {
+            true => Ok(()),
+            false => err_at!(NotSecure, msg: format!("fail verify message")),
+        }
+    }
+
+    /// Get requested round of randomness, verified as per configuration,
+    /// and return its 32-byte randomness. Typically used to seed an RNG
+    /// or as a commitment.
+    pub fn get_randomness(&mut self, round: u128) -> Result<[u8; 32]> {
+        let r = self.get(Some(round))?;
+        to_randomness(&r)
+    }
+}
+
+/// Builder for a booted [Client].
+///
+/// ```ignore
+/// let client = ClientBuilder::new("example")
+///     .endpoint(Endpoint::HttpDrandApi)
+///     .endpoint(Endpoint::HttpCloudflare)
+///     .secure(true)
+///     .build()?;
+/// ```
+pub struct ClientBuilder {
+    name: String,
+    config: Config,
+    endpoints: Vec<Endpoint>,
+    chain_hash: Option<Vec<u8>>,
+}
+
+impl ClientBuilder {
+    /// Start building a client named `name`, with default [Config].
+    pub fn new(name: &str) -> ClientBuilder {
+        ClientBuilder {
+            name: name.to_string(),
+            config: Config::default(),
+            endpoints: vec![],
+            chain_hash: None,
+        }
+    }
+
+    /// Use `config` as the client's configuration, replacing parameters
+    /// set so far.
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Add an endpoint, refer [Client::add_endpoint].
+    pub fn endpoint(mut self, endp: Endpoint) -> Self {
+        self.endpoints.push(endp);
+        self
+    }
+
+    /// Refer [Config::secure].
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.config.set_secure(secure);
+        self
+    }
+
+    /// Refer [Config::determinism].
+    pub fn determinism(mut self, determinism: bool) -> Self {
+        self.config.set_determinism(determinism);
+        self
+    }
+
+    /// Refer [Config::check_point].
+    pub fn check_point(mut self, check_point: Random) -> Self {
+        self.config.set_check_point(Some(check_point));
+        self
+    }
+
+    /// Refer [Config::http_client].
+    pub fn http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.config.set_http_client(http_client);
+        self
+    }
+
+    /// Pin the drand-group's chain-hash as root-of-trust while booting,
+    /// refer [Client::boot].
+    pub fn chain_hash(mut self, chain_hash: Vec<u8>) -> Self {
+        self.chain_hash = Some(chain_hash);
+        self
+    }
+
+    /// Create the client, add the endpoints and [boot](Client::boot) it.
+    /// Return `Error::Invalid` if no endpoint was added.
+    pub fn build(self) -> Result<Client> {
+        if self.endpoints.is_empty() {
+            err_at!(Invalid, msg: format!("initialize endpoint"))?
+        }
+
+        let mut client = Client::from_config(&self.name, self.config);
+        for endp in self.endpoints.into_iter() {
+            client.add_endpoint(endp)?;
+        }
+        client.boot(self.chain_hash)?;
+
+        Ok(client)
+    }
+}
+
+/// Iterator over the beacon's future rounds, refer [Client::watch].
+pub struct Watch<'a> {
+    client: &'a mut Client,
+    next: Option<u128>,
+    done: bool,
+    handle: WatchHandle,
+}
+
+impl<'a> Watch<'a> {
+    /// Return a handle to stop this iteration, typically from another
+    /// thread while shutting down.
+    pub fn to_handle(&self) -> WatchHandle {
+        self.handle.clone()
+    }
+}
+
+/// Handle to stop a [Watch], refer [Watch::to_handle].
+#[derive(Clone, Default)]
+pub struct WatchHandle {
+    stopped: Arc<AtomicBool>,
+    // abort handle for the in-flight wait/fetch, if any.
+    inflight: Arc<Mutex<Option<AbortHandle>>>,
+}
+
+impl WatchHandle {
+    /// Stop the iteration. A [Watch::next] blocked waiting for the next
+    /// round, or fetching it, returns None immediately, dropping any
+    /// in-flight request, and so does every call after that.
+    pub fn stop(&self) {
+        self.stopped.store(true, SeqCst);
+        if let Ok(mut inflight) = self.inflight.lock() {
+            if let Some(abort) = inflight.take() {
+                abort.abort()
+            }
+        }
+    }
+
+    /// Return whether the iteration is stopped.
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(SeqCst)
+    }
+
+    // register the abort handle for the next wait/fetch, return None if
+    // already stopped. Registration and stop are serialized via the lock,
+    // so that a stop is never missed.
+    fn register(&self) -> Result<Option<AbortRegistration>> {
+        let mut inflight = err_at!(PoisonedLock, self.inflight.lock())?;
+        if self.is_stopped() {
+            return Ok(None);
+        }
+        let (abort, reg) = AbortHandle::new_pair();
+        *inflight = Some(abort);
+        Ok(Some(reg))
+    }
+}
+
+impl<'a> Iterator for Watch<'a> {
+    type Item = Result<Random>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with(|client, round, reg| {
+            let fut = client.watch_round(round, |endpoints| Box::pin(endpoints.get(Some(round))));
+            block_on(Abortable::new(fut, reg))
+        })
+    }
+}
+
+impl<'a> Watch<'a> {
+    // wait for the next round and fetch it using `fetch`, which shall
+    // abort the wait or the fetch on `reg`. On stop, the wait or the
+    // in-flight request is dropped midway, the client remains usable.
+    fn next_with<T, F>(&mut self, fetch: F) -> Option<Result<T>>
+    where
+        F: FnOnce(&Client, u128, AbortRegistration) -> Result<result::Result<Result<T>, Aborted>>,
+    {
+        if self.done || self.handle.is_stopped() {
+            return None;
+        }
+        let round = match self.next {
+            Some(round) => round,
+            None => match self.client.current_round() {
+                Ok(round) => round + 1,
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            },
+        };
+        self.next = Some(round + 1);
+
+        let client = &self.client;
+        let res = {
+            let _slot = match client.inflight.acquire() {
+                Ok(slot) => slot,
+                Err(err) => return Some(Err(err)),
+            };
+            let reg = match self.handle.register() {
+                Ok(Some(reg)) => reg,
+                Ok(None) => return None,
+                Err(err) => return Some(Err(err)),
+            };
+            fetch(client, round, reg)
+        };
+        match res {
+            Ok(Ok(res)) => Some(res),
+            Ok(Err(_aborted)) => {
+                self.done = true;
+                None
+            }
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+/// Iterator over the beacon's future rounds, along with the outcome of
+/// their verification, refer [Client::watch_verified].
+pub struct WatchVerified<'a> {
+    watch: Watch<'a>,
+}
+
+impl<'a> WatchVerified<'a> {
+    /// Return a handle to stop this iteration, refer [Watch::to_handle].
+    pub fn to_handle(&self) -> WatchHandle {
+        self.watch.to_handle()
+    }
+}
+
+impl<'a> Iterator for WatchVerified<'a> {
+    type Item = Result<VerifiedRandom>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.watch.next_with(|client, round, reg| {
+            let fut = client.watch_round(round, |endpoints| {
+                Box::pin(async move {
+                    // rounds are fetched as is and verified after.
+                    let mut endpoints = Insecure::new(endpoints);
+                    endpoints.get_verified(round).await
+                })
+            });
+            block_on(Abortable::new(fut, reg))
+        })
+    }
+}
+
+// secure mode turned off for the endpoints, restored when dropped, even
+// if a fetch is dropped midway.
+struct Insecure<'a> {
+    endpoints: &'a mut Endpoints,
+    secure: bool,
+}
+
+impl<'a> Insecure<'a> {
+    fn new(endpoints: &'a mut Endpoints) -> Self {
+        let secure = endpoints.set_secure(false);
+        Insecure { endpoints, secure }
+    }
+}
+
+impl<'a> Deref for Insecure<'a> {
+    type Target = Endpoints;
+
+    fn deref(&self) -> &Endpoints {
+        self.endpoints
+    }
+}
+
+impl<'a> DerefMut for Insecure<'a> {
+    fn deref_mut(&mut self) -> &mut Endpoints {
+        self.endpoints
+    }
+}
+
+impl<'a> Drop for Insecure<'a> {
+    fn drop(&mut self) {
+        self.endpoints.set_secure(self.secure);
+    }
+}
+
+// Counting semaphore gating entry to boot/get, refer
+// Config::max_inflight_ops. Operations are counted even without a limit,
+// a limit of zero is no limit.
+#[derive(Clone)]
+struct Inflight {
+    limit: Option<usize>,
+    count: Arc<(Mutex<usize>, Condvar)>,
+}
+
+// releases its slot when dropped.
+struct InflightSlot {
+    count: Arc<(Mutex<usize>, Condvar)>,
+}
+
+impl Inflight {
+    fn new(limit: Option<usize>) -> Inflight {
+        let limit = limit.filter(|limit| *limit > 0);
+        let count = Arc::new((Mutex::new(0), Condvar::new()));
+        Inflight { limit, count }
+    }
+
+    fn to_count(&self) -> Result<usize> {
+        Ok(*err_at!(PoisonedLock, self.count.0.lock())?)
+    }
+
+    fn acquire(&self) -> Result<InflightSlot> {
+        let (lock, cvar) = self.count.as_ref();
+        let mut count = err_at!(PoisonedLock, lock.lock())?;
+        while self.limit.map(|limit| *count >= limit).unwrap_or(false) {
+            count = err_at!(PoisonedLock, cvar.wait(count))?;
+        }
+        *count += 1;
+
+        Ok(InflightSlot {
+            count: Arc::clone(&self.count),
+        })
+    }
+}
+
+impl Drop for InflightSlot {
+    fn drop(&mut self) {
+        let (lock, cvar) = self.count.as_ref();
+        if let Ok(mut count) = lock.lock() {
+            *count -= 1;
+            cvar.notify_one();
+        }
+    }
+}
+
+// reqwest requires a tokio runtime to drive its connections.
+fn block_on<F: Future>(fut: F) -> Result<F::Output> {
+    let mut rt = err_at!(Fatal, tokio::runtime::Runtime::new())?;
+    Ok(rt.block_on(fut))
+}
+
+// randomness is the SHA-256 hash of the round's signature, which is
+// confirmed here before handing out the fixed-size output.
+fn to_randomness(r: &Random) -> Result<[u8; 32]> {
+    let randomness = r.randomness_array()?;
+    r.check_randomness()?;
+    Ok(randomness)
 }
 
 #[cfg(test)]
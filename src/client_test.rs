@@ -6,6 +6,10 @@ fn test_client_empty_endpoint() {
     let mut client = Client::from_config("test", config);
 
     assert!(client.to_info().is_ok());
+    match client.info() {
+        Err(Error::Invalid(_, _, _)) => (),
+        res => panic!("unexpected {:?}", res),
+    }
     assert!(client.boot(None).is_err());
     assert!(client.get(None).is_err());
 }
@@ -32,3 +36,448 @@ fn test_client_empty_endpoint() {
 //fn test_client_1_continued_determinism() {
 //    todo!()
 //}
+
+#[test]
+fn test_client_restore_unbooted() {
+    let client = Client::from_config("test", Config::default());
+    let state = client.export_state().unwrap();
+    assert!(Client::from_state(Config::default(), state).is_err());
+}
+
+#[test]
+fn test_client_export_restore() {
+    let mut client = Client::from_config("test", Config::default());
+    client.add_endpoint(Endpoint::HttpDrandApi).unwrap();
+    client.boot(None).unwrap();
+    client.get(Some(2)).unwrap();
+
+    let state = client.export_state().unwrap();
+    let mut restored = Client::from_state(Config::default(), state).unwrap();
+    assert_eq!(restored.to_name(), "test");
+    assert_eq!(restored.to_info().unwrap(), client.to_info().unwrap());
+
+    restored.add_endpoint(Endpoint::HttpDrandApi).unwrap();
+    assert_eq!(restored.get(Some(3)).unwrap().round, 3);
+}
+
+#[test]
+fn test_client_get_randomness() {
+    let mut client = Client::from_config("test", Config::default());
+    client.add_endpoint(Endpoint::HttpDrandApi).unwrap();
+    client.boot(None).unwrap();
+
+    let randomness = client.get_randomness(1).unwrap();
+    assert_eq!(
+        hex::encode(randomness),
+        "101297f1ca7dc44ef6088d94ad5fb7ba03455dc33d53ddb412bbc4564ed986ec"
+    );
+}
+
+#[test]
+fn test_to_randomness() {
+    let signature = hex::decode("8d61d9100567de44682506aea1a7a6fa6e5491cd27a0a0ed349ef6910ac5ac20ff7bc3e09d7c046566c9f7f3c6f3b10104990e7cb424998203d8f7de586fb7fa5f60045417a432684f85093b06ca91c769f0e7ca19268375e659c2a2352b4655").unwrap();
+    let randomness =
+        hex::decode("101297f1ca7dc44ef6088d94ad5fb7ba03455dc33d53ddb412bbc4564ed986ec").unwrap();
+    let mut r = Random {
+        round: 1,
+        randomness: randomness.clone(),
+        signature,
+        previous_signature: vec![],
+    };
+    assert_eq!(to_randomness(&r).unwrap().to_vec(), randomness);
+
+    r.randomness[0] ^= 0xff;
+    assert!(to_randomness(&r).is_err());
+
+    r.randomness.pop();
+    assert!(to_randomness(&r).is_err());
+}
+
+#[test]
+fn test_client_get_timed() {
+    let mut client = Client::from_config("test", Config::default());
+    client.add_endpoint(Endpoint::HttpDrandApi).unwrap();
+    client.boot(None).unwrap();
+
+    let r = client.get_timed(Some(1)).unwrap();
+    assert_eq!(r.value.round, 1);
+    assert_eq!(r.host, "https://api.drand.sh");
+    assert!(r.elapsed > std::time::Duration::default());
+}
+
+#[test]
+fn test_client_no_cross_validate() {
+    let chain_hash = hex::decode(crate::MAINNET_CHAIN_HASH).unwrap();
+
+    let mut config = Config::default();
+    config.set_cross_validate(false);
+    let mut client = Client::from_config("test", config);
+    client.add_endpoint(Endpoint::HttpDrandApi).unwrap();
+    client.boot(Some(chain_hash)).unwrap();
+
+    assert_eq!(
+        hex::encode(client.info().unwrap().hash),
+        crate::MAINNET_CHAIN_HASH
+    );
+    assert_eq!(client.get(Some(1)).unwrap().round, 1);
+}
+
+#[test]
+fn test_client_boot_phase_tag() {
+    let mut client = Client::from_config("test", Config::default());
+    client.add_endpoint(Endpoint::HttpDrandApi).unwrap();
+
+    let chain_hash = vec![0_u8; 32];
+    let err = client.boot(Some(chain_hash)).unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("boot-phase1 https://api.drand.sh"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn test_client_duplicate_endpoint() {
+    let mut client = Client::from_config("test", Config::default());
+    client.add_endpoint(Endpoint::HttpDrandApi).unwrap();
+    client.add_endpoint(Endpoint::HttpDrandApi).unwrap();
+
+    assert_eq!(client.to_endpoints().unwrap(), vec!["https://api.drand.sh"]);
+    assert_eq!(
+        client.to_duplicates().unwrap(),
+        vec!["https://api.drand.sh"]
+    );
+
+    client.add_endpoint(Endpoint::HttpDrandApi2).unwrap();
+    client.add_endpoint(Endpoint::HttpDrandApi3).unwrap();
+    client.add_endpoint(Endpoint::HttpCloudflare).unwrap();
+    assert_eq!(
+        client.to_endpoints().unwrap(),
+        vec![
+            "https://api.drand.sh",
+            "https://api2.drand.sh",
+            "https://api3.drand.sh",
+            "https://drand.cloudflare.com",
+        ]
+    );
+
+    for base_url in ["https://api.drand.sh/", "HTTPS://API.drand.sh:443"].iter() {
+        let base_url = base_url.to_string();
+        client
+            .add_endpoint(Endpoint::HttpCustom { base_url })
+            .unwrap();
+    }
+    assert_eq!(client.to_endpoints().unwrap().len(), 4);
+    assert_eq!(client.to_duplicates().unwrap().len(), 3);
+}
+
+#[test]
+fn test_client_byte_budget() {
+    let mut config = Config::default();
+    config.set_byte_budget(4096);
+    let mut client = Client::from_config("test", config);
+    client.add_endpoint(Endpoint::HttpDrandApi).unwrap();
+    client.boot(None).unwrap();
+
+    let mut round = 1;
+    let err = loop {
+        match client.get(Some(round)) {
+            Ok(_) => round += 1,
+            Err(err) => break err,
+        }
+    };
+    assert!(round > 2, "{}", round);
+    assert!(err.to_string().contains("byte budget exhausted"), "{}", err);
+    // a response over the remaining budget is refused, not downloaded.
+    let remaining = client.to_remaining_budget().unwrap().unwrap();
+    assert!(remaining < 1024, "{}", remaining);
+
+    client.reset_budget().unwrap();
+    assert_eq!(client.to_remaining_budget().unwrap(), Some(4096));
+    assert_eq!(client.get(Some(round)).unwrap().round, round);
+}
+
+#[test]
+fn test_client_get_genesis() {
+    let mut config = Config::default();
+    config.set_secure(true);
+    let mut client = Client::from_config("test", config);
+    client.add_endpoint(Endpoint::HttpDrandApi).unwrap();
+    client.boot(None).unwrap();
+
+    let r = client.get(Some(1)).unwrap();
+    assert_eq!(r.round, 1);
+    assert_eq!(r.previous_signature, client.to_info().unwrap().group_hash);
+}
+
+#[test]
+fn test_inflight_ops() {
+    use std::{sync::atomic::AtomicUsize, sync::atomic::Ordering::SeqCst, thread, time};
+
+    let inflight = Arc::new(Inflight::new(Some(2)));
+    let (active, peak) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+
+    let mut handles = vec![];
+    for _ in 0..8 {
+        let (inflight, active, peak) = (inflight.clone(), active.clone(), peak.clone());
+        handles.push(thread::spawn(move || {
+            let _slot = inflight.acquire().unwrap();
+            let n = active.fetch_add(1, SeqCst) + 1;
+            peak.fetch_max(n, SeqCst);
+            thread::sleep(time::Duration::from_millis(20));
+            active.fetch_sub(1, SeqCst);
+        }));
+    }
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(peak.load(SeqCst), 2);
+    assert_eq!(inflight.to_count().unwrap(), 0);
+
+    for limit in [None, Some(0)].iter() {
+        let inflight = Inflight::new(*limit);
+        let slots: Vec<InflightSlot> = (0..8).map(|_| inflight.acquire().unwrap()).collect();
+        assert_eq!(inflight.to_count().unwrap(), 8);
+        drop(slots);
+        assert_eq!(inflight.to_count().unwrap(), 0);
+    }
+
+    // clones of a client share the cap, endpoint accepts connections but
+    // never responds, holding each health-check for the request timeout.
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let mut config = Config::default();
+    config
+        .set_max_inflight_ops(2)
+        .set_timeout(time::Duration::from_millis(100));
+    let mut client = Client::from_config("test", config);
+    client
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+
+    let handles: Vec<_> = (0..6)
+        .map(|_| {
+            let mut client = client.clone();
+            thread::spawn(move || client.health_check().unwrap())
+        })
+        .collect();
+    let mut peak = 0;
+    while handles.iter().any(|h| !h.is_finished()) {
+        peak = std::cmp::max(peak, client.to_inflight_ops().unwrap());
+        thread::sleep(time::Duration::from_millis(5));
+    }
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), vec![false]);
+    }
+    assert_eq!(peak, 2);
+    assert_eq!(client.to_inflight_ops().unwrap(), 0);
+}
+
+#[test]
+fn test_client_chain_verify() {
+    let latest = {
+        let mut client = Client::from_config("test", Config::default());
+        client.add_endpoint(Endpoint::HttpDrandApi).unwrap();
+        client.boot(None).unwrap();
+        client.get(None).unwrap()
+    };
+    let check_point = {
+        let mut client = Client::from_config("test", Config::default());
+        client.add_endpoint(Endpoint::HttpDrandApi).unwrap();
+        client.boot(None).unwrap();
+        client.get(Some(latest.round - 20)).unwrap()
+    };
+
+    let mut config = Config::default();
+    config
+        .set_determinism(true)
+        .set_secure(true)
+        .set_check_point(Some(check_point))
+        .set_max_conns(2);
+    let mut client = Client::from_config("test", config);
+    client.add_endpoint(Endpoint::HttpDrandApi).unwrap();
+    client.boot(None).unwrap();
+
+    let r = client.to_check_point().unwrap().unwrap();
+    assert!(r.round >= latest.round, "{} {}", r.round, latest.round);
+}
+
+#[test]
+fn test_client_custom_endpoint() {
+    let mut client = Client::from_config("test", Config::default());
+    let base_url = "https://drand.example.com/".to_string();
+    client
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+    assert_eq!(
+        client.to_endpoints().unwrap(),
+        vec!["https://drand.example.com"]
+    );
+
+    let base_url = "drand.example.com".to_string();
+    match client.add_endpoint(Endpoint::HttpCustom { base_url }) {
+        Err(Error::Invalid(_, _, _)) => (),
+        Err(err) => panic!("{}", err),
+        Ok(_) => panic!("expected Invalid"),
+    }
+    assert_eq!(client.to_endpoints().unwrap().len(), 1);
+}
+
+#[test]
+fn test_client_watch() {
+    let mut client = Client::from_config("test", Config::default());
+    assert_eq!(client.watch().count(), 1);
+
+    client.add_endpoint(Endpoint::HttpDrandApi).unwrap();
+    client.boot(None).unwrap();
+    let latest = client.get(None).unwrap();
+
+    let rounds: Vec<Random> = client.watch().take(2).map(|r| r.unwrap()).collect();
+    assert!(rounds[0].round > latest.round);
+    assert_eq!(rounds[1].round, rounds[0].round + 1);
+}
+
+#[test]
+fn test_client_watch_stop() {
+    let mut client = Client::from_config("test", Config::default());
+    let mut watch = client.watch();
+    let handle = watch.to_handle();
+    handle.stop();
+    assert!(handle.is_stopped());
+    assert!(watch.next().is_none());
+
+    client.add_endpoint(Endpoint::HttpDrandApi).unwrap();
+    client.boot(None).unwrap();
+
+    let mut watch = client.watch();
+    let handle = watch.to_handle();
+    let stopper = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        handle.stop();
+    });
+    let start = std::time::Instant::now();
+    // stopped before the next round is due, if not published already.
+    while watch.next().is_some() {}
+    assert!(start.elapsed() < std::time::Duration::from_secs(30));
+    assert!(watch.next().is_none());
+    stopper.join().unwrap();
+
+    client.get(None).unwrap();
+}
+
+// clock running `FAST` times faster than real time, from `base` as of
+// `start`.
+struct FastClock {
+    base: time::SystemTime,
+    start: Arc<Mutex<time::Instant>>,
+}
+
+const FAST: u32 = 100;
+
+impl crate::Clock for FastClock {
+    fn now(&self) -> time::SystemTime {
+        self.base + self.start.lock().unwrap().elapsed() * FAST
+    }
+
+    fn delay(&self, dur: time::Duration) -> std::pin::Pin<Box<dyn Future<Output = ()> + Send>> {
+        Box::pin(tokio::time::delay_for(dur / FAST))
+    }
+}
+
+// client booted on a mock endpoint, with its clock midway through round
+// 6 once booted, refer endpoints_test::mock_server.
+fn mock_client(serves: usize) -> (Client, std::thread::JoinHandle<Vec<String>>) {
+    let genesis = time::UNIX_EPOCH + time::Duration::from_secs(1595431050);
+    let start = Arc::new(Mutex::new(time::Instant::now()));
+    let mut config = Config::default();
+    config.set_clock(Box::new(FastClock {
+        base: genesis + time::Duration::from_secs(5 * 30 + 15),
+        start: Arc::clone(&start),
+    }));
+    let mut client = Client::from_config("test", config);
+    let (base_url, server) = crate::endpoints::endpoints_test::mock_server(0, serves + 2);
+    client
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+    client.boot(None).unwrap();
+    *start.lock().unwrap() = time::Instant::now();
+    (client, server)
+}
+
+#[test]
+fn test_client_watch_unlocked() {
+    let (mut client, server) = mock_client(2);
+
+    // round 7 is due in about 150ms, get on a clone is not held up by the
+    // watch waiting for it.
+    let mut clone = client.clone();
+    let getter = std::thread::spawn(move || {
+        std::thread::sleep(time::Duration::from_millis(50));
+        let r = clone.get(Some(5)).unwrap();
+        (r.round, time::Instant::now())
+    });
+    let r = client.watch().next().unwrap().unwrap();
+    let watched = time::Instant::now();
+    assert_eq!(r.round, 7);
+
+    let (round, got) = getter.join().unwrap();
+    assert_eq!(round, 5);
+    assert!(got < watched);
+    assert_eq!(server.join().unwrap()[2..], ["/public/5", "/public/7"]);
+}
+
+#[test]
+fn test_client_get_watch() {
+    let (mut client, server) = mock_client(6);
+
+    // watch rounds 7 and 8 while a clone keeps fetching older rounds.
+    let mut clone = client.clone();
+    let getter = std::thread::spawn(move || {
+        let mut check_points = vec![];
+        for round in [5, 6, 5, 6].iter() {
+            std::thread::sleep(time::Duration::from_millis(100));
+            assert_eq!(clone.get(Some(*round)).unwrap().round, *round);
+            check_points.push(clone.to_check_point().unwrap().map(|r| r.round));
+        }
+        check_points
+    });
+    let rounds: Vec<u128> = client.watch().take(2).map(|r| r.unwrap().round).collect();
+    assert_eq!(rounds, vec![7, 8]);
+
+    let check_points = getter.join().unwrap();
+    assert_eq!(check_points.len(), 4);
+    assert!(
+        check_points.windows(2).all(|w| w[0] <= w[1]),
+        "{:?}",
+        check_points
+    );
+    assert_eq!(client.to_check_point().unwrap().unwrap().round, 8);
+    assert_eq!(server.join().unwrap().len(), 8);
+}
+
+#[test]
+fn test_client_builder() {
+    match ClientBuilder::new("test").secure(true).build() {
+        Err(Error::Invalid(_, msg, _)) => assert_eq!(msg, "initialize endpoint"),
+        Err(err) => panic!("unexpected {}", err),
+        Ok(_) => panic!("unexpected client"),
+    }
+
+    let base_url = "ftp://drand.example.com".to_string();
+    let res = ClientBuilder::new("test")
+        .endpoint(Endpoint::HttpCustom { base_url })
+        .build();
+    assert!(matches!(res, Err(Error::Invalid(_, _, _))));
+
+    let client = ClientBuilder::new("test")
+        .endpoint(Endpoint::HttpDrandApi)
+        .endpoint(Endpoint::HttpDrandApi2)
+        .secure(true)
+        .determinism(false)
+        .build()
+        .unwrap();
+    assert!(client.to_check_point().unwrap().is_some());
+    assert_eq!(client.to_endpoints().unwrap().len(), 2);
+}
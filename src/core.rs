@@ -1,9 +1,44 @@
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
-use std::{error, fmt, result, time};
+use std::{
+    cmp,
+    convert::{TryFrom, TryInto},
+    error, fmt,
+    future::Future,
+    ops,
+    pin::Pin,
+    result, str,
+    sync::Arc,
+    time,
+};
+
+#[cfg(feature = "serialize")]
+use crate::http::InfoJson;
+use crate::http::RandomJson;
+use crate::{
+    store::CheckpointStore,
+    verify::{self, SignatureVerifier},
+};
 
 pub const MAX_CONNS: usize = 4;
 
+/// Size of a compressed BLS signature, on G2, in bytes.
+pub const SIGNATURE_SIZE: usize = 96;
+
+/// Default timeout for a complete request to an endpoint.
+pub const REQUEST_TIMEOUT: time::Duration = time::Duration::from_secs(10);
+
+/// Default delay before retrying a failed request to an endpoint.
+pub const RETRY_DELAY: time::Duration = time::Duration::from_millis(100);
+
+/// Tolerated skew between local clock and the drand-group's clock.
+pub const MAX_CLOCK_SKEW: time::Duration = time::Duration::from_secs(5);
+
+/// Longest period accepted in a drand-group's hash-info.
+pub const MAX_PERIOD: time::Duration = time::Duration::from_secs(86400);
+
 #[derive(Clone, Debug)]
 /// Configuration parameters for Client.
 pub struct Config {
@@ -26,12 +61,171 @@ pub struct Config {
     pub determinism: bool,
     /// Ensure all future rounds from latest round is verified.
     ///
+    /// This is beacon verification, the cryptographic check on rounds,
+    /// and is unrelated to transport security. Rounds fetched over plain
+    /// http, say from a local drand node without certificates, verify
+    /// all the same, refer `https_only` for transport security.
+    ///
     /// Default: false
     pub secure: bool,
-    /// Maximum number of concurrent connections allowed per remote.
+    /// Maximum number of requests in flight per remote, bounding the
+    /// number of rounds fetched concurrently by
+    /// [Client::get_range](crate::Client::get_range). Also caps the idle
+    /// connections kept open to the remote for reuse, it does not limit
+    /// concurrency by itself.
     ///
     /// Default: MAX_CONNS
     pub max_conns: usize,
+    /// Cross-validate hash-info and latest round across all endpoints
+    /// while booting. When false, client boots only with the fastest
+    /// endpoint, saving the extra round-trips, and relies on the
+    /// `chain_hash` root-of-trust and chain verification to trust it.
+    /// Suitable when there is a single trusted endpoint, but a lying
+    /// endpoint goes unnoticed unless `chain_hash` is pinned.
+    ///
+    /// Fastest is by latency measured so far, no latency is measured
+    /// before the first boot, unless restored via
+    /// [Client::from_state](crate::Client::from_state), and the first
+    /// endpoint added is booted. Add the trusted endpoint first.
+    ///
+    /// Default: true
+    pub cross_validate: bool,
+    /// Drand-group's chain-hash, pinned as root-of-trust while booting if
+    /// none is passed to [Client::boot](crate::Client::boot). Chain-hashes
+    /// are commonly shared as hex, refer [Config::set_chain_hash_hex].
+    ///
+    /// When pinned, http endpoints are addressed under `/{chain-hash}/`,
+    /// as in `/{chain-hash}/public/latest`, to pick the chain on nodes
+    /// serving several of them, say mainnet and testnet. Booting checks
+    /// that the hash-info served under that path carries the same hash.
+    /// gRPC endpoints are not addressed by chain-hash, they serve the
+    /// node's default chain, which must carry the pinned hash.
+    ///
+    /// Default: None
+    pub chain_hash: Option<Vec<u8>>,
+    /// Backend used to verify BLS signatures of randomness.
+    ///
+    /// Default: [DrandVerifier](crate::DrandVerifier)
+    pub verifier: Arc<dyn SignatureVerifier>,
+    /// Maximum number of bytes the client can download, across all its
+    /// endpoints. Once exhausted, fetches fail with IOError until the
+    /// budget is reset. A response over the remaining budget is refused,
+    /// without downloading it in full.
+    ///
+    /// Default: None, no limit.
+    pub byte_budget: Option<usize>,
+    /// Maximum number of concurrent [boot] and [get] operations across
+    /// the clones of a client, further operations wait for one of them to
+    /// complete. Zero is taken as no limit.
+    ///
+    /// [boot]: crate::Client::boot
+    /// [get]: crate::Client::get
+    ///
+    /// Default: None, no limit.
+    pub max_inflight_ops: Option<usize>,
+    /// Maximum number of requests per second the client makes, across
+    /// all its endpoints, allowing a burst of up to as many requests.
+    /// Requests over the limit wait for their turn rather than fail.
+    /// Keeps batch operations, like [get_range], within the quotas of
+    /// public endpoints.
+    ///
+    /// [get_range]: crate::Client::get_range
+    ///
+    /// Default: None, no limit.
+    pub rate_limit: Option<u32>,
+    /// Timeout for a complete request to an endpoint, from connecting till
+    /// the response body is read. A timed out endpoint fails the request
+    /// and the client falls over to another endpoint. Can be overridden
+    /// per endpoint, refer [EndpointConfig].
+    ///
+    /// Default: REQUEST_TIMEOUT, 10 seconds.
+    pub timeout: time::Duration,
+    /// Number of times a failed request is retried on the same endpoint,
+    /// before falling over to another endpoint. Only IOError, which
+    /// includes timeouts, is retried, verification and parse errors fail
+    /// right away.
+    ///
+    /// Default: 0, no retries.
+    pub max_retries: usize,
+    /// Delay before the first retry, doubled for every subsequent retry
+    /// along with some jitter.
+    ///
+    /// Default: RETRY_DELAY, 100 milliseconds.
+    pub retry_delay: time::Duration,
+    /// How endpoints are picked for a fetch, refer [SelectionStrategy].
+    ///
+    /// Default: SelectionStrategy::Fastest
+    pub selection: SelectionStrategy,
+    /// Fetch every round from `n` endpoints, `(n, k)`, and accept it only
+    /// if at least `k` of them agree on its randomness and signature.
+    /// Failing which the fetch fails with `Error::NotSecure`. Stronger
+    /// than the default, which races two endpoints and takes the later
+    /// round.
+    ///
+    /// Default: None, no quorum.
+    pub quorum: Option<(usize, usize)>,
+    /// In `secure` mode, return a round only if two endpoints agree on
+    /// it, same as a `quorum` of `(2, 2)`, rather than falling back on a
+    /// lone endpoint when the other fails. Fails the fetch otherwise.
+    /// Latest round can briefly differ across endpoints while a round
+    /// propagates, failing the fetch with `Error::NotSecure`. Ignored if
+    /// `quorum` is configured.
+    ///
+    /// Default: false
+    pub require_agreement: bool,
+    /// Endpoints that failed badly enough, or were found unreachable by
+    /// a health check, are not picked for fetches. When no other endpoint
+    /// is left, try the least slow among them, one at a time, rather than
+    /// failing outright. Helps to ride out a network-wide slowdown.
+    ///
+    /// Default: false
+    pub degraded_fallback: bool,
+    /// Source of wall-clock time, for estimating the current round and
+    /// for waiting on rounds to be published.
+    ///
+    /// Default: [SystemClock]
+    pub clock: Arc<dyn Clock>,
+    /// Known skew of the local clock, say measured against NTP, corrected
+    /// for in time based logic, like estimating the current round and
+    /// waiting for a round to be published.
+    ///
+    /// Default: None
+    pub clock_skew: Option<ClockSkew>,
+    /// Persist the check_point every time it advances, in `secure` mode,
+    /// and load it as the `check_point` when the client is created,
+    /// unless `check_point` is explicitly configured.
+    ///
+    /// Default: None
+    pub checkpoint_store: Option<Arc<dyn CheckpointStore>>,
+    /// Hooks invoked on every call to an endpoint, for metrics and
+    /// tracing.
+    ///
+    /// Default: [NoopObserver]
+    pub observer: Arc<dyn Observer>,
+    /// Cache fetched rounds in memory, so that repeated [get] calls for
+    /// the same round, or for the latest round within its period, are
+    /// served without a network call. Rounds can be pre-fetched into the
+    /// cache with [warm].
+    ///
+    /// [get]: crate::Client::get
+    /// [warm]: crate::Client::warm
+    ///
+    /// Default: false
+    pub cache: bool,
+    /// Pre-built HTTP client for talking to http endpoints, say to route
+    /// through a proxy, to trust custom TLS roots, or to share a
+    /// connection pool across clients. Its own timeouts, connection limits
+    /// and user-agent apply, `timeout` and [EndpointConfig] don't.
+    ///
+    /// Default: None, an HTTP client is built from configuration.
+    pub http_client: Option<reqwest::Client>,
+    /// Transport security, refuse to add endpoints that are not reached
+    /// over TLS, that is, `http://` urls. Independent of `secure`, plain
+    /// http endpoints are allowed by default so that a client can talk
+    /// to a local drand node while testing.
+    ///
+    /// Default: false
+    pub https_only: bool,
 }
 
 impl Default for Config {
@@ -41,6 +235,26 @@ impl Default for Config {
             determinism: false,
             secure: false,
             max_conns: MAX_CONNS,
+            cross_validate: true,
+            chain_hash: None,
+            verifier: verify::default_verifier(),
+            byte_budget: None,
+            max_inflight_ops: None,
+            rate_limit: None,
+            timeout: REQUEST_TIMEOUT,
+            max_retries: 0,
+            retry_delay: RETRY_DELAY,
+            selection: SelectionStrategy::default(),
+            quorum: None,
+            require_agreement: false,
+            degraded_fallback: false,
+            clock: Arc::new(SystemClock),
+            clock_skew: None,
+            checkpoint_store: None,
+            observer: Arc::new(NoopObserver),
+            cache: false,
+            http_client: None,
+            https_only: false,
         }
     }
 }
@@ -65,24 +279,324 @@ impl Config {
         self.max_conns = max_conns;
         self
     }
+
+    pub fn set_cross_validate(&mut self, cross_validate: bool) -> &mut Self {
+        self.cross_validate = cross_validate;
+        self
+    }
+
+    pub fn set_chain_hash(&mut self, chain_hash: Vec<u8>) -> &mut Self {
+        self.chain_hash = Some(chain_hash);
+        self
+    }
+
+    /// Same as [Config::set_chain_hash], decoding `chain_hash` from hex.
+    /// Return `Error::HexParse` for malformed hex and `Error::Invalid` if
+    /// it doesn't decode to a 32-byte hash.
+    pub fn set_chain_hash_hex(&mut self, chain_hash: &str) -> Result<&mut Self> {
+        let hash = err_at!(HexParse, source: hex::decode(chain_hash), "chain_hash")?;
+        if hash.len() != 32 {
+            err_at!(Invalid, msg: format!("chain_hash of {} bytes", hash.len()))?
+        }
+        Ok(self.set_chain_hash(hash))
+    }
+
+    pub fn set_verifier(&mut self, verifier: Box<dyn SignatureVerifier>) -> &mut Self {
+        self.verifier = Arc::from(verifier);
+        self
+    }
+
+    pub fn set_byte_budget(&mut self, byte_budget: usize) -> &mut Self {
+        self.byte_budget = Some(byte_budget);
+        self
+    }
+
+    pub fn set_max_inflight_ops(&mut self, max_inflight_ops: usize) -> &mut Self {
+        self.max_inflight_ops = Some(max_inflight_ops);
+        self
+    }
+
+    pub fn set_rate_limit(&mut self, per_second: u32) -> &mut Self {
+        self.rate_limit = Some(per_second);
+        self
+    }
+
+    pub fn set_timeout(&mut self, timeout: time::Duration) -> &mut Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn set_retry(&mut self, max_retries: usize, base_delay: time::Duration) -> &mut Self {
+        self.max_retries = max_retries;
+        self.retry_delay = base_delay;
+        self
+    }
+
+    pub fn set_selection_strategy(&mut self, selection: SelectionStrategy) -> &mut Self {
+        self.selection = selection;
+        self
+    }
+
+    pub fn set_quorum(&mut self, n: usize, k: usize) -> &mut Self {
+        self.quorum = Some((n, k));
+        self
+    }
+
+    pub fn set_require_agreement(&mut self, require_agreement: bool) -> &mut Self {
+        self.require_agreement = require_agreement;
+        self
+    }
+
+    pub fn set_degraded_fallback(&mut self, degraded_fallback: bool) -> &mut Self {
+        self.degraded_fallback = degraded_fallback;
+        self
+    }
+
+    pub fn set_clock(&mut self, clock: Box<dyn Clock>) -> &mut Self {
+        self.clock = Arc::from(clock);
+        self
+    }
+
+    pub fn set_clock_skew(&mut self, clock_skew: Option<ClockSkew>) -> &mut Self {
+        self.clock_skew = clock_skew;
+        self
+    }
+
+    pub fn set_checkpoint_store(&mut self, store: Box<dyn CheckpointStore>) -> &mut Self {
+        self.checkpoint_store = Some(Arc::from(store));
+        self
+    }
+
+    pub fn set_observer(&mut self, observer: Box<dyn Observer>) -> &mut Self {
+        self.observer = Arc::from(observer);
+        self
+    }
+
+    pub fn set_cache(&mut self, cache: bool) -> &mut Self {
+        self.cache = cache;
+        self
+    }
+
+    pub fn set_http_client(&mut self, http_client: reqwest::Client) -> &mut Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    pub fn set_https_only(&mut self, https_only: bool) -> &mut Self {
+        self.https_only = https_only;
+        self
+    }
+}
+
+/// Trait to plug in the source of wall-clock time, refer
+/// [Config::set_clock]. Time based logic, like estimating the current
+/// round and waiting for a round to be published, goes through the clock,
+/// so that tests can control the passage of time. Every wait, for a round
+/// in watch, retry back-off and rate-limit, is a [Clock::delay], so timers
+/// of a runtime other than tokio, say futures-timer or async-io, can be
+/// plugged in here.
+pub trait Clock: Send + Sync {
+    /// Return the current wall-clock time.
+    fn now(&self) -> time::SystemTime;
+
+    /// Return a future that completes once `dur` has passed on this clock.
+    fn delay(&self, dur: time::Duration) -> Pin<Box<dyn Future<Output = ()> + Send>>;
+}
+
+impl fmt::Debug for dyn Clock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Clock")
+    }
+}
+
+/// Trait to observe calls made by the client to its endpoints, to bridge
+/// them to a metrics or tracing framework, refer [Config::set_observer].
+///
+/// Every request issued while booting, or to get rounds, is followed by
+/// either [Observer::on_success] or [Observer::on_error], retries being
+/// separate requests. Callbacks are invoked inline, they shall be cheap
+/// and must not block. All callbacks default to no-op.
+pub trait Observer: Send + Sync {
+    /// A request is issued to `endpoint`, identified by its base-url.
+    fn on_request(&self, _endpoint: &str) {}
+
+    /// `endpoint` served `round`, verified as per configuration, the call
+    /// having taken `latency`.
+    fn on_success(&self, _endpoint: &str, _round: u128, _latency: time::Duration) {}
+
+    /// Request to `endpoint` failed with `err`, verification failures
+    /// being `Error::NotSecure`.
+    fn on_error(&self, _endpoint: &str, _err: &Error) {}
+
+    /// `endpoint` served `round` as the latest round, more than a period
+    /// behind `expected`, the latest round estimated from the local clock.
+    /// Points to an endpoint that is lagging or not in sync with the
+    /// drand-group.
+    fn on_stale(&self, _endpoint: &str, _round: u128, _expected: u128) {}
+}
+
+impl fmt::Debug for dyn Observer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Observer")
+    }
+}
+
+/// Default observer, ignores every event.
+#[derive(Clone, Debug, Default)]
+pub struct NoopObserver;
+
+impl Observer for NoopObserver {}
+
+/// Default clock, system time and tokio timers. Its delays shall be
+/// awaited within a tokio runtime, which the [Client](crate::Client)
+/// provides.
+#[derive(Clone, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> time::SystemTime {
+        time::SystemTime::now()
+    }
+
+    fn delay(&self, dur: time::Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        Box::pin(tokio::time::delay_for(dur))
+    }
+}
+
+/// Skew of the local clock relative to true time, refer
+/// [Config::set_clock_skew].
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum ClockSkew {
+    /// Local clock runs ahead of true time by this much.
+    Ahead(time::Duration),
+    /// Local clock runs behind true time by this much.
+    Behind(time::Duration),
+}
+
+// clock corrected for a known skew, delays are passed through as is.
+pub(crate) struct SkewedClock {
+    pub(crate) clock: Arc<dyn Clock>,
+    pub(crate) skew: ClockSkew,
+}
+
+impl Clock for SkewedClock {
+    fn now(&self) -> time::SystemTime {
+        let now = self.clock.now();
+        match self.skew {
+            ClockSkew::Ahead(skew) => now.checked_sub(skew).unwrap_or(now),
+            ClockSkew::Behind(skew) => now.checked_add(skew).unwrap_or(now),
+        }
+    }
+
+    fn delay(&self, dur: time::Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        self.clock.delay(dur)
+    }
+}
+
+/// Strategy to pick the endpoints for a fetch. Every fetch is raced on two
+/// endpoints, or on all of them with `FirstOk`, endpoints that are down
+/// are never picked. Latency of an endpoint is its rolling average over
+/// its recent fetches.
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default)]
+pub enum SelectionStrategy {
+    /// Pick the endpoints with least latency.
+    #[default]
+    Fastest,
+    /// Take turns among endpoints, in the order they were added,
+    /// spreading the load evenly.
+    RoundRobin,
+    /// Pick endpoints at random, weighted by the inverse of their latency,
+    /// spreading the load among comparably fast endpoints.
+    Weighted,
+    /// Race the fetch on every healthy endpoint and take the first to
+    /// succeed, so that a slow pair of endpoints doesn't hold up the fetch
+    /// when another is quick. Trades load on the endpoints for lower tail
+    /// latency. Where two endpoints are picked, say for a range, they are
+    /// picked as with `Fastest`.
+    FirstOk,
+}
+
+/// Per-endpoint configuration, overriding the client-wide parameters for a
+/// single endpoint. Refer [Client::add_endpoint_with].
+///
+/// [Client::add_endpoint_with]: crate::Client::add_endpoint_with
+#[derive(Clone, Debug, Default)]
+pub struct EndpointConfig {
+    /// Timeout for a complete request to this endpoint.
+    ///
+    /// Default: None, use client-wide [Config::timeout].
+    pub request_timeout: Option<time::Duration>,
+    /// Timeout for connecting to this endpoint.
+    ///
+    /// Default: None, use client-wide value.
+    pub connect_timeout: Option<time::Duration>,
+    /// Maximum number of requests in flight to this endpoint, refer
+    /// [Config::max_conns].
+    ///
+    /// Default: None, use [Config::max_conns].
+    pub max_conns: Option<usize>,
+}
+
+impl EndpointConfig {
+    pub fn set_request_timeout(&mut self, timeout: time::Duration) -> &mut Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    pub fn set_connect_timeout(&mut self, timeout: time::Duration) -> &mut Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    pub fn set_max_conns(&mut self, max_conns: usize) -> &mut Self {
+        self.max_conns = Some(max_conns);
+        self
+    }
 }
 
 /// Type alias for Result return type, used by this package.
 pub type Result<T> = result::Result<T, Error>;
 
+/// Originating error, say from reqwest, serde_json or hex, that caused
+/// an [Error]. Refer [Error::source](std::error::Error::source). Shared
+/// so that Error stays cheap to clone.
+pub type Source = Arc<dyn error::Error + Send + Sync>;
+
 /// Error variants that can be returned by this package's API.
 ///
 /// Each variant carries a prefix, typically identifying the
-/// error location.
+/// error location, a message, and optionally the originating error.
+///
+/// Errors compare equal if they are of the same variant with the same
+/// prefix and message, the originating error is not compared, its text
+/// is already part of the message.
+#[derive(Clone)]
 pub enum Error {
-    Fatal(String, String),
-    PoisonedLock(String, String),
-    NotSecure(String, String),
-    Invalid(String, String),
-    IOError(String, String),
-    JsonParse(String, String),
-    StringParse(String, String),
-    HexParse(String, String),
+    Fatal(String, String, Option<Source>),
+    PoisonedLock(String, String, Option<Source>),
+    NotSecure(String, String, Option<Source>),
+    Invalid(String, String, Option<Source>),
+    IOError(String, String, Option<Source>),
+    JsonParse(String, String, Option<Source>),
+    StringParse(String, String, Option<Source>),
+    HexParse(String, String, Option<Source>),
+    /// Operation was abandoned once its deadline passed, refer
+    /// [Client::get_deadline](crate::Client::get_deadline).
+    Timeout(String, String, Option<Source>),
+    /// Every endpoint failed, carries the base-url of each endpoint tried
+    /// along with its error.
+    Exhausted(String, Vec<(String, Error)>),
+    /// Endpoint responded with a non-success HTTP status for `url`, say
+    /// 404 for a round that is yet to be published.
+    Http {
+        status: u16,
+        url: String,
+    },
+    /// Requested `round` is yet to be published, every endpoint tried
+    /// responded with 404 or failed. Wait for the round and retry.
+    RoundUnavailable {
+        round: u128,
+    },
 }
 
 impl fmt::Display for Error {
@@ -90,14 +604,55 @@ impl fmt::Display for Error {
         use Error::*;
 
         match self {
-            Fatal(p, msg) => write!(f, "{} Fatal: {}", p, msg),
-            PoisonedLock(p, msg) => write!(f, "{} PoisonedLock: {}", p, msg),
-            NotSecure(p, msg) => write!(f, "{} NotSecure: {}", p, msg),
-            Invalid(p, msg) => write!(f, "{} Invalid: {}", p, msg),
-            IOError(p, msg) => write!(f, "{} IOError: {}", p, msg),
-            JsonParse(p, msg) => write!(f, "{} JsonParse: {}", p, msg),
-            StringParse(p, msg) => write!(f, "{} StringParse: {}", p, msg),
-            HexParse(p, msg) => write!(f, "{} HexParse: {}", p, msg),
+            Fatal(p, msg, _) => write!(f, "{} Fatal: {}", p, msg),
+            PoisonedLock(p, msg, _) => write!(f, "{} PoisonedLock: {}", p, msg),
+            NotSecure(p, msg, _) => write!(f, "{} NotSecure: {}", p, msg),
+            Invalid(p, msg, _) => write!(f, "{} Invalid: {}", p, msg),
+            IOError(p, msg, _) => write!(f, "{} IOError: {}", p, msg),
+            JsonParse(p, msg, _) => write!(f, "{} JsonParse: {}", p, msg),
+            StringParse(p, msg, _) => write!(f, "{} StringParse: {}", p, msg),
+            HexParse(p, msg, _) => write!(f, "{} HexParse: {}", p, msg),
+            Timeout(p, msg, _) => write!(f, "{} Timeout: {}", p, msg),
+            Exhausted(p, errs) => {
+                write!(f, "{} Exhausted:", p)?;
+                for (host, err) in errs.iter() {
+                    write!(f, " [{}: {}]", host, err)?;
+                }
+                Ok(())
+            }
+            Http { status, url } => write!(f, "Http: status {} for {}", status, url),
+            RoundUnavailable { round } => write!(f, "RoundUnavailable: round {}", round),
+        }
+    }
+}
+
+impl PartialEq for Error {
+    fn eq(&self, other: &Error) -> bool {
+        use Error::*;
+
+        match (self, other) {
+            (Fatal(p1, m1, _), Fatal(p2, m2, _)) => p1 == p2 && m1 == m2,
+            (PoisonedLock(p1, m1, _), PoisonedLock(p2, m2, _)) => p1 == p2 && m1 == m2,
+            (NotSecure(p1, m1, _), NotSecure(p2, m2, _)) => p1 == p2 && m1 == m2,
+            (Invalid(p1, m1, _), Invalid(p2, m2, _)) => p1 == p2 && m1 == m2,
+            (IOError(p1, m1, _), IOError(p2, m2, _)) => p1 == p2 && m1 == m2,
+            (JsonParse(p1, m1, _), JsonParse(p2, m2, _)) => p1 == p2 && m1 == m2,
+            (StringParse(p1, m1, _), StringParse(p2, m2, _)) => p1 == p2 && m1 == m2,
+            (HexParse(p1, m1, _), HexParse(p2, m2, _)) => p1 == p2 && m1 == m2,
+            (Timeout(p1, m1, _), Timeout(p2, m2, _)) => p1 == p2 && m1 == m2,
+            (Exhausted(p1, e1), Exhausted(p2, e2)) => p1 == p2 && e1 == e2,
+            (
+                Http {
+                    status: s1,
+                    url: u1,
+                },
+                Http {
+                    status: s2,
+                    url: u2,
+                },
+            ) => s1 == s2 && u1 == u2,
+            (RoundUnavailable { round: r1 }, RoundUnavailable { round: r2 }) => r1 == r2,
+            (_, _) => false,
         }
     }
 }
@@ -108,13 +663,118 @@ impl fmt::Debug for Error {
     }
 }
 
-impl error::Error for Error {}
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        use Error::*;
+
+        let src = match self {
+            Fatal(_, _, src) | PoisonedLock(_, _, src) | NotSecure(_, _, src) => src,
+            Invalid(_, _, src) | IOError(_, _, src) | JsonParse(_, _, src) => src,
+            StringParse(_, _, src) | HexParse(_, _, src) | Timeout(_, _, src) => src,
+            Exhausted(_, _) | Http { .. } | RoundUnavailable { .. } => return None,
+        };
+        src.as_ref()
+            .map(|e| e.as_ref() as &(dyn error::Error + 'static))
+    }
+}
+
+// conversions for `?`, prefixed with the `?`'s call site as with err_at!.
+// The call site is that of the conversion, use err_at! to tag an error
+// with a message.
+impl From<reqwest::Error> for Error {
+    #[track_caller]
+    fn from(err: reqwest::Error) -> Error {
+        let msg = err.to_string();
+        Error::IOError(caller_prefix(), msg, Some(Arc::new(err)))
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    #[track_caller]
+    fn from(err: serde_json::Error) -> Error {
+        let msg = err.to_string();
+        Error::JsonParse(caller_prefix(), msg, Some(Arc::new(err)))
+    }
+}
+
+impl From<hex::FromHexError> for Error {
+    #[track_caller]
+    fn from(err: hex::FromHexError) -> Error {
+        let msg = err.to_string();
+        Error::HexParse(caller_prefix(), msg, Some(Arc::new(err)))
+    }
+}
+
+#[track_caller]
+fn caller_prefix() -> String {
+    let loc = std::panic::Location::caller();
+    format!("{}:{}", loc.file(), loc.line())
+}
+
+impl Error {
+    /// Return the endpoints' disagreement behind this error, if this is a
+    /// [NotSecure](Error::NotSecure) error from cross-validation.
+    pub fn to_mismatch(&self) -> Option<&Mismatch> {
+        match self {
+            Error::NotSecure(_, _, Some(src)) => src.downcast_ref::<Mismatch>(),
+            _ => None,
+        }
+    }
+
+    /// Return the break in the chain behind this error, if this is a
+    /// [NotSecure](Error::NotSecure) error from chained verification.
+    pub fn to_chain_break(&self) -> Option<&ChainBreak> {
+        match self {
+            Error::NotSecure(_, _, Some(src)) => src.downcast_ref::<ChainBreak>(),
+            _ => None,
+        }
+    }
+
+    /// Return whether this error is due to a round that is yet to be
+    /// published, endpoints respond with 404 for such a round. Refer
+    /// [Client::get_round](crate::Client::get_round).
+    pub fn is_unpublished(&self) -> bool {
+        match self {
+            Error::RoundUnavailable { .. } => true,
+            Error::Http { status, .. } => *status == 404,
+            Error::Exhausted(_, errs) => errs.iter().any(|(_, err)| err.is_unpublished()),
+            _ => false,
+        }
+    }
 
-// TODO: Is there any way to use info.hash to validate the first round of
-// randomness.
+    /// Prepend `tag` to the error's prefix, to add context like the boot
+    /// phase or the endpoint, as the error moves up the call stack.
+    pub(crate) fn tag_prefix(self, tag: &str) -> Error {
+        use Error::*;
+
+        let t = |p: String| format!("{} {}", tag, p);
+        match self {
+            Fatal(p, msg, src) => Fatal(t(p), msg, src),
+            PoisonedLock(p, msg, src) => PoisonedLock(t(p), msg, src),
+            NotSecure(p, msg, src) => NotSecure(t(p), msg, src),
+            Invalid(p, msg, src) => Invalid(t(p), msg, src),
+            IOError(p, msg, src) => IOError(t(p), msg, src),
+            JsonParse(p, msg, src) => JsonParse(t(p), msg, src),
+            StringParse(p, msg, src) => StringParse(t(p), msg, src),
+            HexParse(p, msg, src) => HexParse(t(p), msg, src),
+            Timeout(p, msg, src) => Timeout(t(p), msg, src),
+            Exhausted(p, errs) => Exhausted(t(p), errs),
+            // carries no prefix, the url or round locates the error.
+            err @ Http { .. } | err @ RoundUnavailable { .. } => err,
+        }
+    }
+}
 
 /// Type captures the drand-group's hash-info.
+///
+/// With `serialize` feature, it (de)serializes in drand's JSON format,
+/// byte fields as hex strings, and period and genesis_time in seconds.
 #[derive(Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(Serialize, Deserialize),
+    serde(try_from = "InfoJson", into = "InfoJson")
+)]
 pub struct Info {
     /// Distributed public key of the drand group.
     pub public_key: Vec<u8>,
@@ -128,6 +788,11 @@ pub struct Info {
     pub hash: Vec<u8>,
     /// Use as previous_signature to validate the first round of randomness.
     pub group_hash: Vec<u8>,
+    /// Group setup, available only if the endpoint returns them as part
+    /// of the hash-info.
+    pub group: Option<GroupInfo>,
+    /// Signing scheme used by the drand-group, refer [Scheme].
+    pub scheme: Scheme,
 }
 
 impl Default for Info {
@@ -138,16 +803,303 @@ impl Default for Info {
             genesis_time: time::UNIX_EPOCH,
             hash: Vec::default(),
             group_hash: Vec::default(),
+            group: None,
+            scheme: Scheme::default(),
+        }
+    }
+}
+
+impl Info {
+    /// Construct hash-info with `genesis_time` and `period` from Unix
+    /// seconds, as in drand's JSON, leaving the other fields to their
+    /// default. Return `Error::Invalid` if genesis_time is too far in the
+    /// future to be represented, or if period is zero or longer than a
+    /// day.
+    pub fn from_unix(genesis_unix: u64, period_secs: u64) -> Result<Info> {
+        let period = time::Duration::from_secs(period_secs);
+        if period_secs == 0 || period > MAX_PERIOD {
+            err_at!(Invalid, msg: format!("period {} out of range", period_secs))?
+        }
+        let genesis_time = time::UNIX_EPOCH.checked_add(time::Duration::from_secs(genesis_unix));
+        match genesis_time {
+            Some(genesis_time) => Ok(Info {
+                period,
+                genesis_time,
+                ..Info::default()
+            }),
+            None => err_at!(Invalid, msg: format!("genesis_time {} out of range", genesis_unix)),
+        }
+    }
+
+    /// Return the hash-info of drand's mainnet `default` chain, as
+    /// published by the League of Entropy, without touching the network.
+    /// Its chain-hash is `8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce`,
+    /// 30 second period and [Scheme::Chained] signatures.
+    ///
+    /// Along with [verify_offline](crate::verify_offline), this is enough
+    /// to verify randomness obtained out-of-band. Parameters are bundled
+    /// with the crate and shall be updated with a new release if drand
+    /// rotates the chain's keys, check the chain-hash when in doubt.
+    pub fn mainnet_default() -> Info {
+        Info {
+            public_key: hex::decode("868f005eb8e6e4ca0a47c8a77ceaa5309a47978a7c71bc5cce96366b5d7a569937c529eeda66c7293784a9402801af31").unwrap(),
+            period: time::Duration::from_secs(30),
+            genesis_time: time::UNIX_EPOCH + time::Duration::from_secs(1595431050),
+            hash: hex::decode(crate::MAINNET_CHAIN_HASH).unwrap(),
+            group_hash: hex::decode("176f93498eac9ca337150b46d21dd58673ea4e3581185f869672e59fa4cb390a").unwrap(),
+            group: None,
+            scheme: Scheme::Chained,
+        }
+    }
+
+    /// Return the hash-info of drand's mainnet `quicknet` chain, without
+    /// touching the network. Its chain-hash is
+    /// `52db9ba70e0cc0f6eaf7803dd07447a1f5477735fd3f661792ba94600c84e971`,
+    /// 3 second period and [Scheme::UnchainedG1] signatures, verified by
+    /// [verify_offline](crate::verify_offline) via the default
+    /// [DrandVerifier](crate::DrandVerifier). Same caveats as
+    /// [Info::mainnet_default] apply.
+    pub fn quicknet_default() -> Info {
+        Info {
+            public_key: hex::decode("83cf0f2896adee7eb8b5f01fcad3912212c437e0073e911fb90022d3e760183c8c4b450b6a0a6c3ac6a5776a2d1064510d1fec758c921cc22b0e17e63aaf4bcb5ed66304de9cf809bd274ca73bab4af5a6e9c76a4bc09e76eae8991ef5ece45a").unwrap(),
+            period: time::Duration::from_secs(3),
+            genesis_time: time::UNIX_EPOCH + time::Duration::from_secs(1692803367),
+            hash: hex::decode(crate::QUICKNET_CHAIN_HASH).unwrap(),
+            group_hash: hex::decode("f477d5c89f21a17c863a7f937c6a6d15859414d2be09cd448d4279af331c5d3e").unwrap(),
+            group: None,
+            scheme: Scheme::UnchainedG1,
+        }
+    }
+
+    /// Return genesis_time as Unix seconds, 0 if it precedes the Unix
+    /// epoch.
+    pub fn genesis_unix(&self) -> u64 {
+        match self.genesis_time.duration_since(time::UNIX_EPOCH) {
+            Ok(dur) => dur.as_secs(),
+            Err(_) => 0,
+        }
+    }
+
+    /// Return period in whole seconds.
+    pub fn period_secs(&self) -> u64 {
+        self.period.as_secs()
+    }
+
+    /// Return the round active at wall-clock time `at`, that is, the
+    /// latest round published at or before `at`. Return `Error::Invalid`
+    /// if `at` precedes genesis_time.
+    pub fn round_at(&self, at: time::SystemTime) -> Result<u128> {
+        if self.period == time::Duration::default() {
+            err_at!(Invalid, msg: format!("period is zero"))?
+        }
+        let elapsed = match at.duration_since(self.genesis_time) {
+            Ok(elapsed) => elapsed,
+            Err(_) => err_at!(Invalid, msg: format!("{:?} precedes genesis", at))?,
+        };
+        Ok((elapsed.as_nanos() / self.period.as_nanos()) + 1)
+    }
+
+    /// Return the first round to be published at or after `deadline`,
+    /// round-1 if `deadline` precedes genesis_time. Return
+    /// `Error::Invalid` if period is zero.
+    ///
+    /// Useful for timelock encryption, where data is sealed until the
+    /// returned round is published. With an unchained scheme the round's
+    /// signature is a BLS signature over SHA-256 of the round, refer
+    /// [Random::to_digest], and doubles as the identity-based decryption
+    /// key for that digest under the group's public key. Encrypt to the
+    /// digest with an IBE scheme, like tlock's, and, after
+    /// [Info::time_of_round], decrypt using the round's signature fetched
+    /// via [Client::get_round](crate::Client::get_round), or verified via
+    /// [verify_offline](crate::verify_offline) when obtained out-of-band.
+    pub fn round_for_deadline(&self, deadline: time::SystemTime) -> Result<u128> {
+        if self.period == time::Duration::default() {
+            err_at!(Invalid, msg: format!("period is zero"))?
+        }
+        let elapsed = match deadline.duration_since(self.genesis_time) {
+            Ok(elapsed) => elapsed.as_nanos(),
+            Err(_) => return Ok(1),
+        };
+        let period = self.period.as_nanos();
+        match elapsed % period {
+            0 => Ok((elapsed / period) + 1),
+            _ => Ok((elapsed / period) + 2),
+        }
+    }
+
+    /// Return the wall-clock time at which `round` is, or was, expected
+    /// to be published, that is, `genesis_time + (round - 1) * period`.
+    /// Return `Error::Invalid` for round 0, drand rounds are 1-indexed.
+    pub fn time_of_round(&self, round: u128) -> Result<time::SystemTime> {
+        if round == 0 {
+            err_at!(Invalid, msg: format!("round 0, rounds are 1-indexed"))?
+        }
+        let offset = (round - 1)
+            .checked_mul(self.period.as_nanos())
+            .and_then(|nanos| u64::try_from(nanos).ok())
+            .map(time::Duration::from_nanos);
+        match offset.and_then(|offset| self.genesis_time.checked_add(offset)) {
+            Some(at) => Ok(at),
+            None => err_at!(Invalid, msg: format!("round {} out of range", round)),
+        }
+    }
+
+    /// Return the time left, from `now`, till the next round is due, for
+    /// a countdown. Before genesis_time, return the time left till
+    /// round-1. At a round's boundary the next round is a period away.
+    /// Return zero if period is zero.
+    pub fn time_to_next_round(&self, now: time::SystemTime) -> time::Duration {
+        let period = self.period.as_nanos();
+        if period == 0 {
+            return time::Duration::default();
+        }
+        match now.duration_since(self.genesis_time) {
+            Ok(elapsed) => {
+                let left = period - (elapsed.as_nanos() % period);
+                let secs = (left / 1_000_000_000) as u64;
+                time::Duration::new(secs, (left % 1_000_000_000) as u32)
+            }
+            Err(err) => err.duration(),
+        }
+    }
+
+    /// Return the drand-group's public key as a hex string.
+    pub fn public_key_hex(&self) -> String {
+        hex::encode(&self.public_key)
+    }
+
+    /// Return the chain-hash as a hex string, the same as drand's
+    /// `/info` and as configured for root-of-trust.
+    pub fn hash_hex(&self) -> String {
+        hex::encode(&self.hash)
+    }
+
+    /// Return the group-hash as a hex string.
+    pub fn group_hash_hex(&self) -> String {
+        hex::encode(&self.group_hash)
+    }
+
+    /// Verify the genesis round, round-1, of this drand-group. Round-1
+    /// has no previous round, drand chains it to the `group_hash`, which
+    /// in turn is covered by the chain-hash. A verified round-1 anchors
+    /// the chain of randomness in the root-of-trust. For unchained scheme
+    /// only its signature is verified.
+    pub fn verify_genesis(&self, round1: &Random) -> Result<()> {
+        self.verify_genesis_with(round1, &verify::DrandVerifier)
+    }
+
+    /// Same as [Info::verify_genesis], using `verifier` as the backend.
+    pub fn verify_genesis_with(
+        &self,
+        round1: &Random,
+        verifier: &dyn SignatureVerifier,
+    ) -> Result<()> {
+        if round1.round != 1 {
+            err_at!(Invalid, msg: format!("{} not genesis", round1))?
+        }
+        if self.scheme == Scheme::Chained && round1.previous_signature != self.group_hash {
+            let brk = ChainBreak {
+                round: 1,
+                expected: self.group_hash.clone(),
+                got: round1.previous_signature.clone(),
+            };
+            err_at!(NotSecure, source: Err(brk))?
+        }
+        round1.verify_with(self, verifier)
+    }
+}
+
+/// Signing scheme of a drand chain, as advertised by the `schemeID` in
+/// its hash-info. Hash-info lacking `schemeID` is taken as `Chained`,
+/// unless its public-key is on G2, 96 bytes, taken as `UnchainedG1`.
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default)]
+pub enum Scheme {
+    /// `pedersen-bls-chained`, each round signs its previous_signature
+    /// and its round.
+    #[default]
+    Chained,
+    /// `pedersen-bls-unchained`, each round signs only its round, rounds
+    /// carry no previous_signature. In secure mode, a round is verified
+    /// by itself, rounds since the check_point are not fetched.
+    Unchained,
+    /// `bls-unchained-g1-rfc9380`, unchained, with signatures on G1 and
+    /// the public key on G2, the message hashed to G1 as per RFC 9380.
+    /// Used by the `quicknet` chain. Supported by the default
+    /// [DrandVerifier](crate::DrandVerifier), custom backends shall
+    /// implement [SignatureVerifier::verify_g1].
+    UnchainedG1,
+}
+
+impl Scheme {
+    // scheme for hash-info predating `schemeID`. Such chains are chained,
+    // with public-key on G1, save for a public-key on G2, 96 bytes, which
+    // can only be the rfc9380 scheme with signatures on G1.
+    pub(crate) fn from_public_key(public_key: &[u8]) -> Scheme {
+        match public_key.len() {
+            96 => Scheme::UnchainedG1,
+            _ => Scheme::Chained,
+        }
+    }
+}
+
+impl fmt::Display for Scheme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> result::Result<(), fmt::Error> {
+        match self {
+            Scheme::Chained => write!(f, "pedersen-bls-chained"),
+            Scheme::Unchained => write!(f, "pedersen-bls-unchained"),
+            Scheme::UnchainedG1 => write!(f, "bls-unchained-g1-rfc9380"),
         }
     }
 }
 
+impl str::FromStr for Scheme {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Scheme> {
+        match s {
+            "pedersen-bls-chained" => Ok(Scheme::Chained),
+            "pedersen-bls-unchained" => Ok(Scheme::Unchained),
+            "bls-unchained-g1-rfc9380" => Ok(Scheme::UnchainedG1),
+            _ => err_at!(Invalid, msg: format!("unsupported scheme {:?}", s)),
+        }
+    }
+}
+
+/// Type captures the drand-group's setup, as exposed by some endpoints.
+///
+/// Useful to audit the chain setup, like the threshold parameter. Not all
+/// endpoints return these details, hence every field is optional.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct GroupInfo {
+    /// Minimum number of nodes required to generate a round of randomness.
+    pub threshold: Option<u32>,
+    /// Number of nodes in the drand-group.
+    pub nodes: Option<u32>,
+    /// Coefficients of the distributed public key, the first coefficient
+    /// is the group's `public_key`.
+    pub dist_key: Vec<Vec<u8>>,
+}
+
 /// Type captures randomness from drand-group for a single round.
 ///
 /// This randomness can be verified at the client side using root-of-trust
 /// and the group's hash-info.
-#[derive(Clone, Eq, PartialEq, Debug)]
+///
+/// With `serialize` feature, it (de)serializes in drand's JSON format,
+/// byte fields as hex strings.
+///
+/// Randomness is ordered by its round index, so that a list of rounds
+/// sorts in round order and `max()` picks the latest round. Byte fields
+/// only break the tie between two differing copies of the same round,
+/// keeping the ordering consistent with equality.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(Serialize, Deserialize),
+    serde(try_from = "RandomJson", into = "RandomJson")
+)]
 pub struct Random {
+    // round must be the first field, for ordering.
     /// Sequentially increasing integer - the randomness round index.
     pub round: u128,
     /// SHA-256 hash of the signature.
@@ -164,11 +1116,399 @@ impl fmt::Display for Random {
     }
 }
 
+/// Type wraps a value fetched from drand-group, along with the time
+/// taken to fetch it and the endpoint that served it.
+#[derive(Clone, Debug)]
+pub struct Timed<T> {
+    /// Value fetched from the endpoint.
+    pub value: T,
+    /// Latency of the endpoint's response.
+    pub elapsed: time::Duration,
+    /// Base-url of the endpoint that served the value.
+    pub host: String,
+}
+
+/// Type wraps a round of randomness along with the outcome of its
+/// verification, refer [Client::watch_verified](crate::Client::watch_verified).
+#[derive(Clone, Debug)]
+pub struct VerifiedRandom {
+    /// Round of randomness, as served by the endpoint.
+    pub random: Random,
+    /// Whether the round's randomness and signature verified.
+    pub verified: bool,
+    /// Reason the round failed verification, if it did.
+    pub verify_error: Option<Error>,
+}
+
+/// Type describes a round that failed verification, refer
+/// [Client::subscribe_failures](crate::Client::subscribe_failures).
+#[derive(Clone, Debug, PartialEq)]
+pub struct VerifyFailure {
+    /// Requested round, None when the latest round was requested.
+    pub round: Option<u128>,
+    /// Base-url of the endpoint that served the round.
+    pub host: String,
+    /// Verification error.
+    pub error: Error,
+}
+
+/// Disagreement between endpoints, found while cross-validating them
+/// during boot. Carried as the source of [Error::NotSecure], refer
+/// [Error::to_mismatch]. `expected` is the value served by the reference
+/// endpoint and `got` is the value served by the disagreeing endpoint,
+/// whose base-url is part of the error's prefix.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Mismatch {
+    PublicKey {
+        expected: Vec<u8>,
+        got: Vec<u8>,
+    },
+    ChainHash {
+        expected: Vec<u8>,
+        got: Vec<u8>,
+    },
+    Round {
+        expected: u128,
+        got: u128,
+    },
+    Randomness {
+        round: u128,
+        expected: Vec<u8>,
+        got: Vec<u8>,
+    },
+    Signature {
+        round: u128,
+        expected: Vec<u8>,
+        got: Vec<u8>,
+    },
+    PreviousSignature {
+        round: u128,
+        expected: Vec<u8>,
+        got: Vec<u8>,
+    },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> result::Result<(), fmt::Error> {
+        use Mismatch::*;
+
+        let h = hex::encode;
+        match self {
+            PublicKey { expected, got } => {
+                write!(f, "public-key {} != {}", h(expected), h(got))
+            }
+            ChainHash { expected, got } => write!(f, "hash {} != {}", h(expected), h(got)),
+            Round { expected, got } => write!(f, "round {} != {}", expected, got),
+            Randomness { expected, got, .. } => {
+                write!(f, "randomness {} != {}", h(expected), h(got))
+            }
+            Signature { expected, got, .. } => {
+                write!(f, "signature {} != {}", h(expected), h(got))
+            }
+            PreviousSignature { expected, got, .. } => {
+                write!(f, "previous_signature {} != {}", h(expected), h(got))
+            }
+        }
+    }
+}
+
+impl error::Error for Mismatch {}
+
+/// Break in a chain of rounds, found while verifying a chained scheme.
+/// `round`'s previous_signature, `got`, doesn't match `expected`, the
+/// signature of the round before it, or group_hash for round-1. Carried
+/// as the source of [Error::NotSecure], refer [Error::to_chain_break].
+/// Displays the first 8 bytes of either signature.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChainBreak {
+    pub round: u128,
+    pub expected: Vec<u8>,
+    pub got: Vec<u8>,
+}
+
+impl fmt::Display for ChainBreak {
+    fn fmt(&self, f: &mut fmt::Formatter) -> result::Result<(), fmt::Error> {
+        let h = |bytes: &[u8]| hex::encode(&bytes[..cmp::min(bytes.len(), 8)]);
+        write!(
+            f,
+            "chain broken at round {}, previous_signature {}.. != {}..",
+            self.round,
+            h(&self.expected),
+            h(&self.got)
+        )
+    }
+}
+
+impl error::Error for ChainBreak {}
+
+/// Compact proof-of-beacon, for forwarding a round to on-chain verifiers.
+///
+/// Canonical byte layout, refer [OnchainProof::to_bytes]:
+///
+/// | offset | size | field                                    |
+/// |--------|------|------------------------------------------|
+/// | 0      | 8    | round, unsigned 64-bit big-endian        |
+/// | 8      | 96   | signature, compressed G2 point           |
+/// | 104    | 48   | public_key, compressed G1 point          |
+/// | 152    | rest | previous_signature, 96 bytes, or the 32 byte group_hash for round-1, empty for unchained rounds |
+///
+/// Randomness is not part of the proof, it is SHA-256 of the signature.
+/// Fixed sizes are that of the default scheme, signatures on G2 and
+/// public key on G1. Chains signing on G1, like quicknet with 48 byte
+/// signatures and 96 byte public key, can't be encoded.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct OnchainProof {
+    /// Randomness round index.
+    pub round: u64,
+    /// BLS signature for this round.
+    pub signature: Vec<u8>,
+    /// Distributed public key of the drand group.
+    pub public_key: Vec<u8>,
+    /// Signature of the previous round, input to the signed message.
+    pub previous_signature: Vec<u8>,
+}
+
+impl OnchainProof {
+    /// Size of compressed public key, on G1, in bytes.
+    pub const PUBLIC_KEY_SIZE: usize = 48;
+
+    const HEADER_SIZE: usize = 8 + SIGNATURE_SIZE + Self::PUBLIC_KEY_SIZE;
+
+    /// Encode proof in its canonical byte layout.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(Self::HEADER_SIZE + self.previous_signature.len());
+        data.extend_from_slice(&self.round.to_be_bytes());
+        data.extend_from_slice(&self.signature);
+        data.extend_from_slice(&self.public_key);
+        data.extend_from_slice(&self.previous_signature);
+        data
+    }
+
+    /// Decode proof from its canonical byte layout.
+    pub fn from_bytes(data: &[u8]) -> Result<OnchainProof> {
+        if data.len() < Self::HEADER_SIZE {
+            err_at!(Invalid, msg: format!("onchain proof size {}", data.len()))?
+        }
+        let (round, data) = data.split_at(8);
+        let (signature, data) = data.split_at(SIGNATURE_SIZE);
+        let (public_key, previous_signature) = data.split_at(Self::PUBLIC_KEY_SIZE);
+
+        let mut bytes = [0_u8; 8];
+        bytes.copy_from_slice(round);
+        Ok(OnchainProof {
+            round: u64::from_be_bytes(bytes),
+            signature: signature.to_vec(),
+            public_key: public_key.to_vec(),
+            previous_signature: previous_signature.to_vec(),
+        })
+    }
+}
+
 impl Random {
-    pub fn to_digest(&self) -> Result<Vec<u8>> {
+    /// Encode this round in drand's JSON wire format, same as served by
+    /// `/public/<round>`, byte fields as hex strings. For unchained rounds
+    /// `previous_signature` is left out.
+    pub fn to_json(&self) -> String {
+        let val: RandomJson = self.clone().into();
+        // hex strings and integers, serialization can't fail.
+        serde_json::to_string(&val).unwrap()
+    }
+
+    /// Decode a round from drand's JSON wire format. Return
+    /// `Error::JsonParse` for malformed JSON and `Error::HexParse` for
+    /// malformed hex fields.
+    pub fn from_json(data: &str) -> Result<Random> {
+        let val: RandomJson = serde_json::from_str(data)?;
+        val.try_into()
+    }
+
+    /// Construct a round from its hex encoded byte fields, as served by
+    /// drand. Pass an empty `previous_signature` for unchained rounds.
+    /// Return `Error::HexParse` for malformed hex.
+    pub fn from_hex_parts(
+        round: u128,
+        randomness: &str,
+        signature: &str,
+        previous_signature: &str,
+    ) -> Result<Random> {
+        let val = Random {
+            round,
+            randomness: err_at!(HexParse, source: hex::decode(randomness), "randomness")?,
+            signature: err_at!(HexParse, source: hex::decode(signature), "signature")?,
+            previous_signature: err_at!(
+                HexParse,
+                source: hex::decode(previous_signature),
+                "previous_signature"
+            )?,
+        };
+
+        Ok(val)
+    }
+
+    /// Return the randomness as a hex string.
+    pub fn randomness_hex(&self) -> String {
+        hex::encode(&self.randomness)
+    }
+
+    /// Return the signature as a hex string.
+    pub fn signature_hex(&self) -> String {
+        hex::encode(&self.signature)
+    }
+
+    /// Return the previous round's signature as a hex string, empty for
+    /// unchained rounds.
+    pub fn previous_signature_hex(&self) -> String {
+        hex::encode(&self.previous_signature)
+    }
+
+    /// Return the 32-byte randomness as a fixed-size array, handy for
+    /// seeding RNGs and constant-time comparisons. Return `Error::Invalid`
+    /// if randomness is not 32 bytes long.
+    pub fn randomness_array(&self) -> Result<[u8; 32]> {
+        let n = self.randomness.len();
+        let msg = format!("{} randomness size {}", self, n);
+        err_at!(Invalid, self.randomness.as_slice().try_into(), msg)
+    }
+
+    /// Return a ChaCha20 RNG seeded with this round's 32-byte randomness,
+    /// as is. Same round always yields the same sequence of draws.
+    /// Requires the `rand` feature.
+    #[cfg(feature = "rand")]
+    pub fn to_rng(&self) -> Result<rand_chacha::ChaCha20Rng> {
+        use rand_chacha::rand_core::SeedableRng;
+
+        Ok(rand_chacha::ChaCha20Rng::from_seed(
+            self.randomness_array()?,
+        ))
+    }
+
+    /// Return a compact proof for this round, to be verified on-chain
+    /// against `info`'s public key. `previous_signature` is empty for a
+    /// round from an unchained scheme. Return `Error::Invalid` for a
+    /// G1 signature, like quicknet's, refer [OnchainProof] for layout.
+    pub fn to_onchain_proof(&self, info: &Info) -> Result<OnchainProof> {
+        let round = err_at!(Invalid, u64::try_from(self.round))?;
+        if self.signature.len() != SIGNATURE_SIZE {
+            let n = self.signature.len();
+            err_at!(Invalid, msg: format!("{} signature size {}", self, n))?
+        }
+        if info.public_key.len() != OnchainProof::PUBLIC_KEY_SIZE {
+            let n = info.public_key.len();
+            err_at!(Invalid, msg: format!("public_key size {}", n))?
+        }
+
+        Ok(OnchainProof {
+            round,
+            signature: self.signature.clone(),
+            public_key: info.public_key.clone(),
+            previous_signature: self.previous_signature.clone(),
+        })
+    }
+
+    /// Reconstruct randomness from an on-chain proof, re-deriving the
+    /// randomness from the signature. The returned round is not verified,
+    /// the proof's public key must be checked against a trusted [Info].
+    pub fn from_onchain_proof(proof: &OnchainProof) -> Random {
+        Random {
+            round: u128::from(proof.round),
+            randomness: Sha256::digest(&proof.signature).to_vec(),
+            signature: proof.signature.clone(),
+            previous_signature: proof.previous_signature.clone(),
+        }
+    }
+
+    /// Return the message signed by the drand-group for this round. For
+    /// chained scheme it is SHA-256 of `previous_signature` and the round
+    /// as big-endian u64, for unchained scheme SHA-256 of the round alone.
+    pub fn to_digest(&self, scheme: Scheme) -> Result<Vec<u8>> {
+        let round = err_at!(Invalid, u64::try_from(self.round))?;
         let mut hasher = Sha256::default();
-        hasher.update(&self.previous_signature);
-        hasher.update(self.round.to_be_bytes());
+        if let Scheme::Chained = scheme {
+            hasher.update(&self.previous_signature);
+        }
+        hasher.update(round.to_be_bytes());
         Ok(hasher.finalize().to_vec())
     }
+
+    /// Verify this round's BLS signature, over the message from
+    /// [Random::to_digest], against `info`'s public key, after checking
+    /// its randomness, refer [Random::check_randomness]. Uses the default
+    /// [DrandVerifier](crate::DrandVerifier) backend. Return
+    /// `Error::NotSecure` if either check fails.
+    pub fn verify(&self, info: &Info) -> Result<()> {
+        self.verify_with(info, &verify::DrandVerifier)
+    }
+
+    /// Same as [Random::verify], using `verifier` as the backend.
+    pub fn verify_with(&self, info: &Info, verifier: &dyn SignatureVerifier) -> Result<()> {
+        self.check_randomness()?;
+        let round = err_at!(Invalid, u64::try_from(self.round))?;
+        let psign: &[u8] = match info.scheme {
+            Scheme::Chained => &self.previous_signature,
+            Scheme::Unchained | Scheme::UnchainedG1 => &[],
+        };
+        match verify::verify_signature(verifier, info, round, psign, &self.signature)? {
+            true => Ok(()),
+            false => err_at!(NotSecure, msg: format!("fail verify {}", self)),
+        }
+    }
+
+    /// Check that `randomness` is SHA-256 of `signature`, as defined by
+    /// drand for every scheme. Cheap, no pairing involved, and rejects a
+    /// round whose randomness was tampered with while its signature was
+    /// left intact. Return `Error::NotSecure` on mismatch.
+    pub fn check_randomness(&self) -> Result<()> {
+        if Sha256::digest(&self.signature)[..] != self.randomness[..] {
+            err_at!(NotSecure, msg: format!("{} randomness mismatch", self))?
+        }
+        Ok(())
+    }
+
+    /// Cheap sanity check on `previous_signature`, without fetching the
+    /// previous round. For round-1 it must be the group's `group_hash`,
+    /// for later rounds it must be a well formed signature, that is, a
+    /// compressed point on G2. Meant to reject garbage before the costlier
+    /// chain verification. Unchained rounds carry no previous_signature.
+    pub fn check_previous_signature_wellformed(&self, info: &Info) -> Result<()> {
+        let psign = &self.previous_signature;
+        match self.round {
+            0 => err_at!(Invalid, msg: format!("round {}", self.round)),
+            _ if info.scheme != Scheme::Chained => Ok(()),
+            1 if psign == &info.group_hash => Ok(()),
+            1 => {
+                let brk = ChainBreak {
+                    round: 1,
+                    expected: info.group_hash.clone(),
+                    got: psign.clone(),
+                };
+                err_at!(NotSecure, source: Err(brk))
+            }
+            _ if psign.len() != SIGNATURE_SIZE => {
+                let n = psign.len();
+                err_at!(NotSecure, msg: format!("{} previous_signature size {}", self, n))
+            }
+            _ if !verify::is_g2_point(psign) => {
+                err_at!(NotSecure, msg: format!("{} previous_signature not on G2", self))
+            }
+            _ => Ok(()),
+        }
+    }
 }
+
+/// Return the rounds missing between the smallest and the largest round
+/// in `rounds`, as contiguous spans. `rounds` need not be sorted and may
+/// contain duplicates.
+pub fn find_gaps(rounds: &[Random]) -> Vec<ops::Range<u128>> {
+    let mut seen: Vec<u128> = rounds.iter().map(|r| r.round).collect();
+    seen.sort_unstable();
+    seen.dedup();
+
+    seen.windows(2)
+        .filter(|w| w[1] > w[0] + 1)
+        .map(|w| (w[0] + 1)..w[1])
+        .collect()
+}
+
+#[cfg(test)]
+#[path = "core_test.rs"]
+mod core_test;
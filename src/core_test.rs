@@ -0,0 +1,743 @@
+use super::*;
+
+#[test]
+fn test_error_from() {
+    fn decode(data: &str) -> Result<Vec<u8>> {
+        Ok(hex::decode(data)?)
+    }
+    let line = line!() - 2;
+    match decode("xyz") {
+        Err(Error::HexParse(p, _, Some(_))) => assert_eq!(p, format!("{}:{}", file!(), line)),
+        res => panic!("unexpected {:?}", res),
+    }
+
+    fn parse(data: &str) -> Result<u32> {
+        Ok(serde_json::from_str(data)?)
+    }
+    assert!(matches!(parse("x"), Err(Error::JsonParse(_, _, Some(_)))));
+    assert_eq!(parse("7").unwrap(), 7);
+}
+
+#[test]
+fn test_error_tag_prefix() {
+    let err = Error::NotSecure("src/http.rs:10".to_string(), "bad".to_string(), None);
+    let err = err.tag_prefix("boot-phase1 https://api.drand.sh");
+    assert_eq!(
+        err.to_string(),
+        "boot-phase1 https://api.drand.sh src/http.rs:10 NotSecure: bad"
+    );
+}
+
+#[test]
+fn test_error_exhausted() {
+    let errs = vec![
+        (
+            "https://api.drand.sh".to_string(),
+            Error::IOError("src/http.rs:10".to_string(), "timeout".to_string(), None),
+        ),
+        (
+            "https://api2.drand.sh".to_string(),
+            Error::NotSecure("src/http.rs:20".to_string(), "bad".to_string(), None),
+        ),
+    ];
+    let err = Error::Exhausted("src/endpoints.rs:30".to_string(), errs).tag_prefix("get");
+    assert_eq!(
+        err.to_string(),
+        "get src/endpoints.rs:30 Exhausted: \
+         [https://api.drand.sh: src/http.rs:10 IOError: timeout] \
+         [https://api2.drand.sh: src/http.rs:20 NotSecure: bad]"
+    );
+}
+
+#[test]
+fn test_find_gaps() {
+    let rounds = |rs: &[u128]| -> Vec<Random> {
+        rs.iter()
+            .map(|round| Random {
+                round: *round,
+                randomness: vec![],
+                signature: vec![],
+                previous_signature: vec![],
+            })
+            .collect()
+    };
+
+    assert_eq!(find_gaps(&rounds(&[])), vec![]);
+    assert_eq!(find_gaps(&rounds(&[7])), vec![]);
+    assert_eq!(find_gaps(&rounds(&[1, 2, 3, 4])), vec![]);
+    assert_eq!(find_gaps(&rounds(&[1, 2, 5, 6])), vec![3..5]);
+    assert_eq!(
+        find_gaps(&rounds(&[10, 1, 4, 2, 4, 7])),
+        vec![3..4, 5..7, 8..10]
+    );
+}
+
+#[test]
+fn test_previous_signature_wellformed() {
+    let info = Info {
+        group_hash: hex::decode("176f93498eac9ca337150b46d21dd58673ea4e3581185f869672e59fa4cb390a")
+            .unwrap(),
+        ..Info::default()
+    };
+    let mut r = Random {
+        round: 1,
+        randomness: vec![],
+        signature: vec![],
+        previous_signature: info.group_hash.clone(),
+    };
+    assert!(r.check_previous_signature_wellformed(&info).is_ok());
+
+    r.previous_signature[0] ^= 0xff;
+    assert!(r.check_previous_signature_wellformed(&info).is_err());
+
+    // signature of round-1 is the previous_signature of round-2.
+    r.round = 2;
+    r.previous_signature = hex::decode("8d61d9100567de44682506aea1a7a6fa6e5491cd27a0a0ed349ef6910ac5ac20ff7bc3e09d7c046566c9f7f3c6f3b10104990e7cb424998203d8f7de586fb7fa5f60045417a432684f85093b06ca91c769f0e7ca19268375e659c2a2352b4655").unwrap();
+    assert!(r.check_previous_signature_wellformed(&info).is_ok());
+
+    r.previous_signature.pop();
+    assert!(r.check_previous_signature_wellformed(&info).is_err());
+
+    r.previous_signature = vec![0_u8; SIGNATURE_SIZE];
+    assert!(r.check_previous_signature_wellformed(&info).is_err());
+
+    r.round = 0;
+    assert!(r.check_previous_signature_wellformed(&info).is_err());
+}
+
+#[test]
+fn test_onchain_proof() {
+    let info = Info {
+        public_key: vec![0xa1; 48],
+        ..Info::default()
+    };
+    let signature = vec![0xb2; SIGNATURE_SIZE];
+    let r = Random {
+        round: 0x0102,
+        randomness: Sha256::digest(&signature).to_vec(),
+        signature,
+        previous_signature: vec![0xc3; SIGNATURE_SIZE],
+    };
+
+    let proof = r.to_onchain_proof(&info).unwrap();
+    let data = proof.to_bytes();
+    assert_eq!(data.len(), 8 + 96 + 48 + 96);
+    assert_eq!(&data[..8], &[0, 0, 0, 0, 0, 0, 1, 2]);
+    assert_eq!(&data[8..104], r.signature.as_slice());
+    assert_eq!(&data[104..152], info.public_key.as_slice());
+    assert_eq!(&data[152..], r.previous_signature.as_slice());
+
+    let proof2 = OnchainProof::from_bytes(&data).unwrap();
+    assert_eq!(proof2, proof);
+    assert_eq!(Random::from_onchain_proof(&proof2), r);
+
+    assert!(OnchainProof::from_bytes(&data[..151]).is_err());
+
+    let mut big = r.clone();
+    big.round = u128::from(u64::MAX) + 1;
+    assert!(big.to_onchain_proof(&info).is_err());
+    assert!(r.to_onchain_proof(&Info::default()).is_err());
+}
+
+#[test]
+fn test_random_verify() {
+    struct Mock;
+
+    impl SignatureVerifier for Mock {
+        fn verify(&self, pk: &[u8], round: u64, psign: &[u8], sign: &[u8]) -> Result<bool> {
+            Ok(pk == [1; 48] && round == 2 && psign == [2; 96] && sign == [3; 96])
+        }
+    }
+
+    let info = Info {
+        public_key: vec![1; 48],
+        ..Info::default()
+    };
+    let mut r = Random {
+        round: 2,
+        randomness: Sha256::digest(&[3; 96]).to_vec(),
+        signature: vec![3; 96],
+        previous_signature: vec![2; 96],
+    };
+    r.verify_with(&info, &Mock).unwrap();
+
+    // randomness is checked before the signature.
+    r.randomness[0] ^= 0xff;
+    assert!(r.check_randomness().is_err());
+    match r.verify_with(&info, &Mock) {
+        Err(Error::NotSecure(_, _, _)) => (),
+        res => panic!("unexpected {:?}", res),
+    }
+    r.randomness[0] ^= 0xff;
+    r.check_randomness().unwrap();
+
+    let digest = {
+        let mut data = vec![2; 96];
+        data.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 2]);
+        Sha256::digest(&data).to_vec()
+    };
+    assert_eq!(r.to_digest(Scheme::Chained).unwrap(), digest);
+
+    r.signature = vec![4; 96];
+    r.randomness = Sha256::digest(&r.signature).to_vec();
+    match r.verify_with(&info, &Mock) {
+        Err(Error::NotSecure(_, _, _)) => (),
+        res => panic!("unexpected {:?}", res),
+    }
+
+    r.round = u128::from(u64::MAX) + 1;
+    assert!(r.to_digest(Scheme::Chained).is_err());
+    match r.verify_with(&info, &Mock) {
+        Err(Error::Invalid(_, _, _)) => (),
+        res => panic!("unexpected {:?}", res),
+    }
+}
+
+#[test]
+fn test_info_verify_genesis() {
+    struct Mock;
+
+    impl SignatureVerifier for Mock {
+        fn verify(&self, _: &[u8], _: u64, _: &[u8], sign: &[u8]) -> Result<bool> {
+            Ok(sign == [3; 96])
+        }
+    }
+
+    let info = Info {
+        group_hash: vec![0xaa; 32],
+        ..Info::default()
+    };
+    let mut r = Random {
+        round: 1,
+        randomness: Sha256::digest(&[3; 96]).to_vec(),
+        signature: vec![3; 96],
+        previous_signature: vec![0xaa; 32],
+    };
+    info.verify_genesis_with(&r, &Mock).unwrap();
+
+    r.signature = vec![4; 96];
+    match info.verify_genesis_with(&r, &Mock) {
+        Err(Error::NotSecure(_, _, _)) => (),
+        res => panic!("unexpected {:?}", res),
+    }
+
+    r.signature = vec![3; 96];
+    r.previous_signature = vec![0xbb; 32];
+    match info.verify_genesis_with(&r, &Mock) {
+        Err(Error::NotSecure(_, _, _)) => (),
+        res => panic!("unexpected {:?}", res),
+    }
+
+    r.round = 2;
+    match info.verify_genesis_with(&r, &Mock) {
+        Err(Error::Invalid(_, _, _)) => (),
+        res => panic!("unexpected {:?}", res),
+    }
+}
+
+#[test]
+fn test_scheme() {
+    for scheme in [Scheme::Chained, Scheme::Unchained, Scheme::UnchainedG1].iter() {
+        assert_eq!(scheme.to_string().parse::<Scheme>().unwrap(), *scheme);
+    }
+    assert!("bls-unknown".parse::<Scheme>().is_err());
+
+    // unchained scheme signs SHA-256 of the round alone.
+    let r = Random {
+        round: 1,
+        randomness: Sha256::digest(&[3; 96]).to_vec(),
+        signature: vec![3; 96],
+        previous_signature: vec![2; 96],
+    };
+    assert_eq!(
+        hex::encode(r.to_digest(Scheme::Unchained).unwrap()),
+        "cd2662154e6d76b2b2b92e70c0cac3ccf534f9b74eb5b89819ec509083d00a50"
+    );
+    assert_ne!(
+        r.to_digest(Scheme::Unchained).unwrap(),
+        r.to_digest(Scheme::Chained).unwrap()
+    );
+
+    struct Mock;
+
+    impl SignatureVerifier for Mock {
+        fn verify(&self, _: &[u8], _: u64, psign: &[u8], _: &[u8]) -> Result<bool> {
+            Ok(psign.is_empty())
+        }
+    }
+
+    let mut info = Info {
+        scheme: Scheme::Unchained,
+        ..Info::default()
+    };
+    r.verify_with(&info, &Mock).unwrap();
+    r.check_previous_signature_wellformed(&info).unwrap();
+    info.verify_genesis_with(&r, &Mock).unwrap();
+
+    info.scheme = Scheme::Chained;
+    assert!(r.verify_with(&info, &Mock).is_err());
+
+    // backend without G1 support fails rfc9380 scheme.
+    info.scheme = Scheme::UnchainedG1;
+    match r.verify_with(&info, &Mock) {
+        Err(Error::Invalid(_, _, _)) => (),
+        res => panic!("unexpected {:?}", res),
+    }
+}
+
+#[test]
+fn test_scheme_vectors() {
+    use crate::SignatureVerifier;
+
+    // mainnet's round-1, chained to its group_hash.
+    let r = Random {
+        round: 1,
+        randomness: vec![],
+        signature: vec![],
+        previous_signature: hex::decode(
+            "176f93498eac9ca337150b46d21dd58673ea4e3581185f869672e59fa4cb390a",
+        )
+        .unwrap(),
+    };
+    assert_eq!(
+        hex::encode(r.to_digest(Scheme::Chained).unwrap()),
+        "3bdd496a35f3c3b6b4318da8b77d3ce4793cbdf26e08156f6fd8c49e85b727cb"
+    );
+
+    // rfc9380 scheme signs SHA-256 of the round alone, on G1.
+    let r = Random {
+        round: 1000,
+        randomness: Sha256::digest(&[7; 48]).to_vec(),
+        signature: vec![7; 48],
+        previous_signature: vec![],
+    };
+    assert_eq!(
+        hex::encode(r.to_digest(Scheme::UnchainedG1).unwrap()),
+        "f652498d092acd949bad74e40683bf3824fb817980504a0c7e6722cfc5a9c0a3"
+    );
+
+    struct Mock;
+
+    impl SignatureVerifier for Mock {
+        fn verify(&self, _: &[u8], _: u64, _: &[u8], _: &[u8]) -> Result<bool> {
+            Ok(false)
+        }
+
+        fn verify_g1(&self, pk: &[u8], round: u64, signature: &[u8]) -> Result<bool> {
+            Ok(pk.len() == 96 && round == 1000 && signature.len() == 48)
+        }
+    }
+
+    let info = Info {
+        public_key: vec![9; 96],
+        scheme: Scheme::UnchainedG1,
+        ..Info::default()
+    };
+    r.verify_with(&info, &Mock).unwrap();
+    r.check_previous_signature_wellformed(&info).unwrap();
+}
+
+#[test]
+fn test_info_round_at() {
+    let secs = time::Duration::from_secs;
+    let info = Info {
+        period: secs(30),
+        genesis_time: time::UNIX_EPOCH + secs(1595431050),
+        ..Info::default()
+    };
+
+    let genesis = info.genesis_time;
+    assert_eq!(info.round_at(genesis).unwrap(), 1);
+    assert_eq!(info.round_at(genesis + secs(29)).unwrap(), 1);
+    assert_eq!(info.round_at(genesis + secs(30)).unwrap(), 2);
+    assert_eq!(info.round_at(genesis + secs(3000)).unwrap(), 101);
+    match info.round_at(genesis - secs(1)) {
+        Err(Error::Invalid(_, _, _)) => (),
+        res => panic!("unexpected {:?}", res),
+    }
+    assert!(Info::default().round_at(genesis).is_err());
+}
+
+#[test]
+fn test_info_time_of_round() {
+    let secs = time::Duration::from_secs;
+    let info = Info {
+        period: secs(30),
+        genesis_time: time::UNIX_EPOCH + secs(1595431050),
+        ..Info::default()
+    };
+
+    let genesis = info.genesis_time;
+    assert_eq!(info.time_of_round(1).unwrap(), genesis);
+    assert_eq!(info.time_of_round(101).unwrap(), genesis + secs(3000));
+    match info.time_of_round(0) {
+        Err(Error::Invalid(_, _, _)) => (),
+        res => panic!("unexpected {:?}", res),
+    }
+    assert!(info.time_of_round(u128::MAX).is_err());
+
+    for round in [1, 2, 100, 101, 1_000_000].iter() {
+        let at = info.time_of_round(*round).unwrap();
+        assert_eq!(info.round_at(at).unwrap(), *round);
+        assert_eq!(info.round_at(at + secs(29)).unwrap(), *round);
+    }
+}
+
+#[test]
+fn test_config_chain_hash_hex() {
+    let hash = "8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce";
+    let mut config = Config::default();
+    config.set_chain_hash_hex(hash).unwrap();
+    assert_eq!(config.chain_hash, Some(hex::decode(hash).unwrap()));
+
+    match config.set_chain_hash_hex("8990zz") {
+        Err(Error::HexParse(_, _, _)) => (),
+        res => panic!("unexpected {:?}", res.map(|c| c.chain_hash.clone())),
+    }
+    match config.set_chain_hash_hex("8990e7a9") {
+        Err(Error::Invalid(_, _, _)) => (),
+        res => panic!("unexpected {:?}", res.map(|c| c.chain_hash.clone())),
+    }
+    assert_eq!(config.chain_hash, Some(hex::decode(hash).unwrap()));
+}
+
+#[test]
+fn test_info_time_to_next_round() {
+    let secs = time::Duration::from_secs;
+    let info = Info {
+        period: secs(30),
+        genesis_time: time::UNIX_EPOCH + secs(1595431050),
+        ..Info::default()
+    };
+
+    let genesis = info.genesis_time;
+    assert_eq!(info.time_to_next_round(genesis - secs(100)), secs(100));
+    assert_eq!(info.time_to_next_round(genesis), secs(30));
+    assert_eq!(info.time_to_next_round(genesis + secs(1)), secs(29));
+    for round in [2, 100, 1_000_000].iter() {
+        let at = info.time_of_round(*round).unwrap();
+        assert_eq!(info.time_to_next_round(at - secs(1)), secs(1));
+        assert_eq!(info.time_to_next_round(at), secs(30));
+        let nanos = time::Duration::from_nanos(1);
+        assert_eq!(info.time_to_next_round(at + nanos), secs(30) - nanos);
+        let next = at + secs(10) + info.time_to_next_round(at + secs(10));
+        assert_eq!(next, info.time_of_round(*round + 1).unwrap());
+    }
+
+    let info = Info {
+        period: time::Duration::default(),
+        ..info
+    };
+    assert_eq!(info.time_to_next_round(genesis), time::Duration::default());
+}
+
+#[test]
+fn test_info_round_for_deadline() {
+    let secs = time::Duration::from_secs;
+    let info = Info {
+        period: secs(30),
+        genesis_time: time::UNIX_EPOCH + secs(1595431050),
+        ..Info::default()
+    };
+
+    let genesis = info.genesis_time;
+    assert_eq!(info.round_for_deadline(genesis - secs(100)).unwrap(), 1);
+    assert_eq!(info.round_for_deadline(genesis).unwrap(), 1);
+    assert_eq!(info.round_for_deadline(genesis + secs(1)).unwrap(), 2);
+    for round in [2, 100, 1_000_000].iter() {
+        let at = info.time_of_round(*round).unwrap();
+        assert_eq!(info.round_for_deadline(at).unwrap(), *round);
+        assert_eq!(info.round_for_deadline(at - secs(29)).unwrap(), *round);
+        assert_eq!(info.round_for_deadline(at + secs(1)).unwrap(), *round + 1);
+    }
+
+    let info = Info {
+        period: time::Duration::default(),
+        ..info
+    };
+    assert!(info.round_for_deadline(genesis).is_err());
+}
+
+#[cfg(feature = "serialize")]
+#[test]
+fn test_serde() {
+    let info = Info {
+        public_key: vec![0xa1; 48],
+        period: time::Duration::from_secs(30),
+        genesis_time: time::UNIX_EPOCH + time::Duration::from_secs(1595431050),
+        hash: vec![0xb2; 32],
+        group_hash: vec![0xc3; 32],
+        group: None,
+        scheme: Scheme::Chained,
+    };
+    let data = serde_json::to_string(&info).unwrap();
+    assert!(data.contains(r#""period":30"#), "{}", data);
+    assert!(data.contains(&hex::encode(&info.public_key)), "{}", data);
+    assert_eq!(serde_json::from_str::<Info>(&data).unwrap(), info);
+
+    let r = Random {
+        round: 1234,
+        randomness: vec![1; 32],
+        signature: vec![2; 96],
+        previous_signature: vec![3; 96],
+    };
+    let data = serde_json::to_string(&r).unwrap();
+    assert!(data.contains(&hex::encode(&r.signature)), "{}", data);
+    assert_eq!(serde_json::from_str::<Random>(&data).unwrap(), r);
+
+    let data = data.replace(&hex::encode(&r.signature), "xyz");
+    assert!(serde_json::from_str::<Random>(&data).is_err());
+}
+
+#[test]
+fn test_error_source() {
+    use std::error::Error as _;
+
+    let res: Result<Vec<u8>> = err_at!(HexParse, source: hex::decode("xyz"));
+    let err = res.unwrap_err();
+    let src = err.source().expect("missing source");
+    assert!(src.downcast_ref::<hex::FromHexError>().is_some());
+    assert!(err.to_string().contains("HexParse: "), "{}", err);
+    assert!(err.to_string().ends_with(&src.to_string()), "{}", err);
+
+    let res: Result<Vec<u8>> = err_at!(HexParse, hex::decode("xyz"));
+    assert!(res.unwrap_err().source().is_none());
+    let res: Result<()> = err_at!(Invalid, msg: format!("bad"));
+    assert!(res.unwrap_err().source().is_none());
+}
+
+#[test]
+fn test_error_clone_eq() {
+    use std::error::Error as _;
+
+    let res: Result<Vec<u8>> = err_at!(HexParse, source: hex::decode("xyz"));
+    let err = res.unwrap_err();
+    let cloned = err.clone();
+    assert_eq!(cloned, err);
+    assert!(cloned.source().is_some());
+    let res: Result<Vec<u8>> = Err(err);
+    assert_eq!(res.clone(), res);
+
+    let (p, m) = ("p".to_string(), "bad".to_string());
+    let err = Error::NotSecure(p.clone(), m.clone(), None);
+    assert_eq!(err, Error::NotSecure(p.clone(), m.clone(), None));
+    assert_ne!(err, Error::Invalid(p.clone(), m.clone(), None));
+    assert_ne!(err, Error::NotSecure(p.clone(), "good".to_string(), None));
+
+    let errs = vec![("a".to_string(), err.clone())];
+    let err = Error::Exhausted(p.clone(), errs.clone());
+    assert_eq!(err.clone(), Error::Exhausted(p, errs));
+}
+
+#[test]
+fn test_error_http() {
+    let url = "https://api.drand.sh/public/10".to_string();
+    let err = Error::Http { status: 404, url }.tag_prefix("get");
+    assert_eq!(
+        err.to_string(),
+        "Http: status 404 for https://api.drand.sh/public/10"
+    );
+
+    assert!(err.is_unpublished());
+
+    let url = "https://api.drand.sh/public/10".to_string();
+    assert!(!Error::Http { status: 503, url }.is_unpublished());
+    let io_err = Error::IOError("a".to_string(), "b".to_string(), None);
+    assert!(!io_err.is_unpublished());
+
+    // any endpoint not having the round yet.
+    let errs = vec![("a".to_string(), io_err.clone()), ("b".to_string(), err)];
+    assert!(Error::Exhausted("c".to_string(), errs).is_unpublished());
+    let errs = vec![("a".to_string(), io_err)];
+    assert!(!Error::Exhausted("c".to_string(), errs).is_unpublished());
+
+    let err = Error::RoundUnavailable { round: 10 }.tag_prefix("get");
+    assert_eq!(err.to_string(), "RoundUnavailable: round 10");
+    assert!(err.is_unpublished());
+}
+
+#[test]
+fn test_randomness_array() {
+    let mut r = Random {
+        round: 10,
+        randomness: vec![0xab; 32],
+        signature: vec![],
+        previous_signature: vec![],
+    };
+    assert_eq!(r.randomness_array().unwrap(), [0xab; 32]);
+
+    for n in [0, 31, 33, 64].iter() {
+        r.randomness = vec![0xab; *n];
+        match r.randomness_array() {
+            Err(Error::Invalid(_, _, _)) => (),
+            res => panic!("unexpected {:?}", res),
+        }
+    }
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn test_to_rng() {
+    use rand_chacha::rand_core::RngCore;
+
+    let r = Random {
+        round: 10,
+        randomness: (0..32).collect(),
+        signature: vec![],
+        previous_signature: vec![],
+    };
+    let draws = |mut rng: rand_chacha::ChaCha20Rng| -> Vec<u64> {
+        (0..4).map(|_| rng.next_u64()).collect()
+    };
+    let (a, b) = (draws(r.to_rng().unwrap()), draws(r.to_rng().unwrap()));
+    assert_eq!(a, b);
+
+    let other = Random {
+        randomness: vec![0xab; 32],
+        ..r.clone()
+    };
+    assert_ne!(a, draws(other.to_rng().unwrap()));
+
+    let bad = Random {
+        randomness: vec![0xab; 31],
+        ..r
+    };
+    assert!(bad.to_rng().is_err());
+}
+
+#[test]
+fn test_random_json() {
+    // mainnet's `/public/1`.
+    let data = r#"{"round":1,"randomness":"101297f1ca7dc44ef6088d94ad5fb7ba03455dc33d53ddb412bbc4564ed986ec","signature":"8d61d9100567de44682506aea1a7a6fa6e5491cd27a0a0ed349ef6910ac5ac20ff7bc3e09d7c046566c9f7f3c6f3b10104990e7cb424998203d8f7de586fb7fa5f60045417a432684f85093b06ca91c769f0e7ca19268375e659c2a2352b4655","previous_signature":"176f93498eac9ca337150b46d21dd58673ea4e3581185f869672e59fa4cb390a"}"#;
+
+    let r = Random::from_json(data).unwrap();
+    assert_eq!(r.round, 1);
+    assert_eq!(Sha256::digest(&r.signature).to_vec(), r.randomness);
+    assert_eq!(
+        hex::encode(&r.previous_signature),
+        "176f93498eac9ca337150b46d21dd58673ea4e3581185f869672e59fa4cb390a"
+    );
+    assert_eq!(r.to_json(), data);
+
+    // unchained rounds carry no previous_signature.
+    let r = Random {
+        previous_signature: vec![],
+        ..r
+    };
+    let data = r.to_json();
+    assert!(!data.contains("previous_signature"), "{}", data);
+    assert_eq!(Random::from_json(&data).unwrap(), r);
+
+    match Random::from_json(r#"{"round":1,"randomness":"#) {
+        Err(Error::JsonParse(_, _, _)) => (),
+        res => panic!("unexpected {:?}", res),
+    }
+    match Random::from_json(r#"{"round":1,"randomness":"xyz","signature":""}"#) {
+        Err(Error::HexParse(_, _, _)) => (),
+        res => panic!("unexpected {:?}", res),
+    }
+}
+
+#[test]
+fn test_random_ord() {
+    let random = |round: u128, signature: u8| Random {
+        round,
+        randomness: vec![0xaa],
+        signature: vec![signature],
+        previous_signature: vec![],
+    };
+
+    let mut rs = [random(3, 1), random(1, 9), random(2, 5)];
+    rs.sort();
+    let rounds: Vec<u128> = rs.iter().map(|r| r.round).collect();
+    assert_eq!(rounds, vec![1, 2, 3]);
+    assert_eq!(rs.iter().max().unwrap().round, 3);
+
+    // round decides over the bytes, which only break ties.
+    assert!(random(2, 0) > random(1, 9));
+    assert!(random(2, 1) > random(2, 0));
+    assert_eq!(random(2, 1).cmp(&random(2, 1)), std::cmp::Ordering::Equal);
+}
+
+#[test]
+fn test_hex_helpers() {
+    let r = Random::from_hex_parts(10, "aabb", "ccdd", "").unwrap();
+    assert_eq!(r.round, 10);
+    assert_eq!(r.randomness, vec![0xaa, 0xbb]);
+    assert_eq!(r.randomness_hex(), "aabb");
+    assert_eq!(r.signature_hex(), "ccdd");
+    assert_eq!(r.previous_signature_hex(), "");
+
+    match Random::from_hex_parts(10, "aabb", "xyz", "") {
+        Err(Error::HexParse(_, msg, _)) => assert!(msg.contains("signature"), "{}", msg),
+        res => panic!("unexpected {:?}", res),
+    }
+
+    let info = Info {
+        public_key: vec![0x01, 0x02],
+        hash: vec![0xab],
+        group_hash: vec![0xcd, 0xef],
+        ..Info::default()
+    };
+    assert_eq!(info.public_key_hex(), "0102");
+    assert_eq!(info.hash_hex(), "ab");
+    assert_eq!(info.group_hash_hex(), "cdef");
+}
+
+#[test]
+fn test_info_unix() {
+    let info = Info::from_unix(1595431050, 30).unwrap();
+    assert_eq!(info.genesis_unix(), 1595431050);
+    assert_eq!(info.period_secs(), 30);
+    assert_eq!(
+        info.genesis_time,
+        time::UNIX_EPOCH + time::Duration::from_secs(1595431050)
+    );
+    assert_eq!(info.period, time::Duration::from_secs(30));
+    assert_eq!(info.public_key, Vec::<u8>::default());
+
+    // far-future genesis_time is an error, not a panic.
+    match Info::from_unix(u64::MAX, 30) {
+        Err(Error::Invalid(_, _, _)) => (),
+        res => panic!("unexpected {:?}", res),
+    }
+    for period in [0, MAX_PERIOD.as_secs() + 1, u64::MAX].iter() {
+        match Info::from_unix(1595431050, *period) {
+            Err(Error::Invalid(_, msg, _)) => assert!(msg.contains("period"), "{}", msg),
+            res => panic!("unexpected {:?}", res),
+        }
+    }
+
+    let info = Info {
+        genesis_time: time::UNIX_EPOCH - time::Duration::from_secs(10),
+        ..Info::default()
+    };
+    assert_eq!(info.genesis_unix(), 0);
+}
+
+#[test]
+fn test_info_known_chains() {
+    let info = Info::mainnet_default();
+    assert_eq!(hex::encode(&info.hash), crate::MAINNET_CHAIN_HASH);
+    assert_eq!(info.period_secs(), 30);
+    assert_eq!(info.genesis_unix(), 1595431050);
+    assert_eq!(info.scheme, Scheme::Chained);
+    assert_eq!(info.public_key.len(), 48);
+
+    // round-1 of mainnet, verified without any network call.
+    let signature = hex::decode("8d61d9100567de44682506aea1a7a6fa6e5491cd27a0a0ed349ef6910ac5ac20ff7bc3e09d7c046566c9f7f3c6f3b10104990e7cb424998203d8f7de586fb7fa5f60045417a432684f85093b06ca91c769f0e7ca19268375e659c2a2352b4655").unwrap();
+    let r = Random {
+        round: 1,
+        randomness: Sha256::digest(&signature).to_vec(),
+        signature,
+        previous_signature: info.group_hash.clone(),
+    };
+    assert!(crate::verify_offline(&info, &r).is_ok());
+
+    let info = Info::quicknet_default();
+    assert_eq!(hex::encode(&info.hash), crate::QUICKNET_CHAIN_HASH);
+    assert_eq!(info.period_secs(), 3);
+    assert_eq!(info.genesis_unix(), 1692803367);
+    assert_eq!(info.scheme, Scheme::UnchainedG1);
+    assert_eq!(info.public_key.len(), 96);
+}
@@ -1,6 +1,25 @@
-use std::time;
+use futures::stream::{self, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
 
-use crate::{client::Endpoint, core::MAX_CONNS, http::Http, Config, Error, Info, Random, Result};
+use std::{
+    cmp,
+    collections::BTreeMap,
+    convert::TryInto,
+    sync::{mpsc, Arc},
+    time,
+};
+
+#[cfg(feature = "grpc")]
+use crate::grpc::Grpc;
+use crate::{
+    client::Endpoint,
+    core::{SkewedClock, MAX_CLOCK_SKEW, MAX_CONNS, RETRY_DELAY},
+    http::{Budget, Http, HttpClients, InfoJson, RandomJson, RateLimit},
+    store::CheckpointStore,
+    verify::{self, SignatureVerifier},
+    Clock, Config, EndpointConfig, Error, Info, Mismatch, NoopObserver, Observer, Random, Result,
+    SelectionStrategy, SystemClock, Timed, VerifiedRandom, VerifyFailure,
+};
 
 // State of each endpoint. An endpoint is booted and subsequently
 // used to watch/get future rounds of random-ness.
@@ -11,6 +30,25 @@ pub(crate) struct State {
     pub(crate) determinism: bool,
     pub(crate) secure: bool,
     pub(crate) max_conns: usize,
+    pub(crate) cross_validate: bool,
+    pub(crate) chain_hash: Option<Vec<u8>>,
+    pub(crate) verifier: Arc<dyn SignatureVerifier>,
+    pub(crate) budget: Budget,
+    pub(crate) rate_limit: RateLimit,
+    pub(crate) request_timeout: Option<time::Duration>,
+    pub(crate) connect_timeout: Option<time::Duration>,
+    pub(crate) max_retries: usize,
+    pub(crate) retry_delay: time::Duration,
+    pub(crate) selection: SelectionStrategy,
+    pub(crate) quorum: Option<(usize, usize)>,
+    pub(crate) require_agreement: bool,
+    pub(crate) degraded_fallback: bool,
+    pub(crate) clock: Arc<dyn Clock>,
+    pub(crate) checkpoint_store: Option<Arc<dyn CheckpointStore>>,
+    pub(crate) observer: Arc<dyn Observer>,
+    pub(crate) http_client: Option<reqwest::Client>,
+    pub(crate) http_clients: HttpClients,
+    pub(crate) https_only: bool,
 }
 
 impl Default for State {
@@ -21,6 +59,25 @@ impl Default for State {
             determinism: bool::default(),
             secure: bool::default(),
             max_conns: MAX_CONNS,
+            cross_validate: true,
+            chain_hash: None,
+            verifier: verify::default_verifier(),
+            budget: Budget::default(),
+            rate_limit: RateLimit::default(),
+            request_timeout: None,
+            connect_timeout: None,
+            max_retries: 0,
+            retry_delay: RETRY_DELAY,
+            selection: SelectionStrategy::default(),
+            quorum: None,
+            require_agreement: false,
+            degraded_fallback: false,
+            clock: Arc::new(SystemClock),
+            checkpoint_store: None,
+            observer: Arc::new(NoopObserver),
+            http_client: None,
+            http_clients: HttpClients::default(),
+            https_only: false,
         }
     }
 }
@@ -29,217 +86,1316 @@ impl From<Config> for State {
     fn from(mut cfg: Config) -> Self {
         State {
             info: Info::default(),
-            check_point: cfg.check_point.take(),
+            check_point: match (cfg.check_point.take(), &cfg.checkpoint_store) {
+                (None, Some(store)) => store.load(),
+                (check_point, _) => check_point,
+            },
             determinism: cfg.determinism,
             secure: cfg.secure,
             max_conns: cfg.max_conns,
+            cross_validate: cfg.cross_validate,
+            chain_hash: cfg.chain_hash,
+            verifier: cfg.verifier,
+            budget: Budget::new(cfg.byte_budget),
+            rate_limit: RateLimit::new(cfg.rate_limit),
+            request_timeout: Some(cfg.timeout),
+            connect_timeout: None,
+            max_retries: cfg.max_retries,
+            retry_delay: cfg.retry_delay,
+            selection: cfg.selection,
+            quorum: cfg.quorum,
+            require_agreement: cfg.require_agreement,
+            degraded_fallback: cfg.degraded_fallback,
+            clock: match cfg.clock_skew {
+                Some(skew) => Arc::new(SkewedClock {
+                    clock: cfg.clock,
+                    skew,
+                }),
+                None => cfg.clock,
+            },
+            checkpoint_store: cfg.checkpoint_store,
+            observer: cfg.observer,
+            http_client: cfg.http_client,
+            http_clients: HttpClients::default(),
+            https_only: cfg.https_only,
         }
     }
 }
 
+impl State {
+    // apply per-endpoint overrides on top of client-wide parameters.
+    fn with_overrides(mut self, config: &EndpointConfig) -> State {
+        self.request_timeout = config.request_timeout.or(self.request_timeout);
+        self.connect_timeout = config.connect_timeout.or(self.connect_timeout);
+        self.max_conns = config.max_conns.unwrap_or(self.max_conns);
+        self
+    }
+}
+
+/// Serializable snapshot of a booted client, used for hot restarts.
+///
+/// Refer [Client::export_state] and [Client::from_state]. On import,
+/// the hash-info and the per-endpoint latency history are trusted as
+/// is, so store the snapshot where it can't be tampered with. The
+/// hash-info is checked for completeness and the `check_point`, which
+/// is also the latest round handed out by the client, is re-verified
+/// against the hash-info's public-key.
+///
+/// [Client::export_state]: crate::Client::export_state
+/// [Client::from_state]: crate::Client::from_state
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ClientState {
+    name: String,
+    info: InfoJson,
+    check_point: Option<RandomJson>,
+    elapsed: Vec<(String, Vec<time::Duration>)>,
+}
+
 // Endpoints is an enumeration of several known http endpoint from
 // main-net.
 pub(crate) struct Endpoints {
     name: String,
     state: State,
     endpoints: Vec<Inner>,
+    // latency history restored from ClientState, keyed by base-url, and
+    // applied as and when matching endpoints are added.
+    restored: Vec<(String, Vec<time::Duration>)>,
+    // subscriber to verification failures, refer report_failure().
+    failures: Option<mpsc::Sender<VerifyFailure>>,
+    // count of endpoint picks, drives round-robin selection.
+    picks: usize,
+    // base-url of the endpoint pinned by the operator, refer
+    // set_preferred().
+    preferred: Option<String>,
+    // highest round fetched so far, refer current_round().
+    observed: Option<u128>,
+    // fetched rounds, when enabled in Config.
+    cache: Option<Cache>,
+    // base-url of endpoints dropped for resolving to an added host.
+    duplicates: Vec<String>,
 }
 
 impl Endpoints {
     pub(crate) fn from_config(name: &str, config: Config) -> Self {
+        let cache = Cache::from_config(&config);
         Endpoints {
             name: name.to_string(),
             state: config.into(),
             endpoints: Vec::default(),
+            restored: Vec::default(),
+            failures: None,
+            picks: 0,
+            preferred: None,
+            observed: None,
+            cache,
+            duplicates: Vec::default(),
+        }
+    }
+
+    pub(crate) fn from_state(config: Config, cs: ClientState) -> Result<Self> {
+        let cache = Cache::from_config(&config);
+        let mut state: State = config.into();
+        state.info = cs.info.try_into()?;
+        state.check_point = match cs.check_point {
+            Some(r) => Some(r.try_into()?),
+            None => None,
+        };
+        Self::restore_validate(&state)?;
+
+        let val = Endpoints {
+            name: cs.name,
+            state,
+            endpoints: Vec::default(),
+            restored: cs.elapsed,
+            failures: None,
+            picks: 0,
+            preferred: None,
+            observed: None,
+            cache,
+            duplicates: Vec::default(),
+        };
+        Ok(val)
+    }
+
+    pub(crate) fn to_client_state(&self) -> ClientState {
+        let elapsed = self
+            .endpoints
+            .iter()
+            .map(|endp| (endp.to_base_url(), endp.to_elapsed_window()))
+            .collect();
+        ClientState {
+            name: self.name.clone(),
+            info: self.state.info.clone().into(),
+            check_point: self.state.check_point.clone().map(|r| r.into()),
+            elapsed,
         }
     }
 
-    pub(crate) fn add_endpoint(&mut self, endp: Endpoint) -> &mut Self {
+    pub(crate) fn add_endpoint(&mut self, endp: Endpoint) -> Result<&mut Self> {
+        self.add_endpoint_with(endp, EndpointConfig::default())
+    }
+
+    pub(crate) fn add_endpoint_with(
+        &mut self,
+        endp: Endpoint,
+        config: EndpointConfig,
+    ) -> Result<&mut Self> {
         let name = self.name.to_string();
-        let endp = match endp {
+        let mut endp = match endp {
             Endpoint::HttpDrandApi => {
                 let endp = Http::new_drand_api();
-                Inner::Http { name, endp }
+                Inner::Http { name, endp, config }
             }
             Endpoint::HttpDrandApi2 => {
-                let endp = Http::new_drand_api();
-                Inner::Http { name, endp }
+                let endp = Http::new_drand_api2();
+                Inner::Http { name, endp, config }
             }
             Endpoint::HttpDrandApi3 => {
-                let endp = Http::new_drand_api();
-                Inner::Http { name, endp }
+                let endp = Http::new_drand_api3();
+                Inner::Http { name, endp, config }
             }
             Endpoint::HttpCloudflare => {
-                let endp = Http::new_drand_api();
-                Inner::Http { name, endp }
+                let endp = Http::new_cloudflare();
+                Inner::Http { name, endp, config }
+            }
+            Endpoint::HttpCustom { base_url } => {
+                let endp = Http::new_custom(&base_url)?;
+                Inner::Http { name, endp, config }
+            }
+            #[cfg(feature = "grpc")]
+            Endpoint::Grpc { addr } => {
+                let endp = Grpc::new(&addr)?;
+                Inner::Grpc { name, endp, config }
             }
         };
+        let base_url = endp.to_base_url();
+        if self.state.https_only && !base_url.starts_with("https://") {
+            err_at!(Invalid, msg: format!("not a https endpoint {:?}", base_url))?
+        }
+        // same host added twice gives no redundancy, drop the duplicate.
+        if self.endpoints.iter().any(|e| e.to_base_url() == base_url) {
+            trace_event!(warn, endpoint = %base_url, "dropping duplicate endpoint");
+            self.duplicates.push(base_url);
+            return Ok(self);
+        }
+        if let Some((_, window)) = self.restored.iter().find(|(u, _)| u == &base_url) {
+            endp.set_elapsed_window(window.clone());
+        }
         self.endpoints.push(endp);
-        self
+        Ok(self)
+    }
+
+    pub(crate) fn to_endpoints(&self) -> Vec<String> {
+        self.endpoints.iter().map(|e| e.to_base_url()).collect()
+    }
+
+    pub(crate) fn to_duplicates(&self) -> Vec<String> {
+        self.duplicates.clone()
+    }
+
+    // effective max_conns for each endpoint, after per-endpoint overrides.
+    pub(crate) fn to_max_conns(&self) -> Vec<(String, usize)> {
+        self.endpoints
+            .iter()
+            .map(|e| {
+                let max_conns = e.to_config().max_conns.unwrap_or(self.state.max_conns);
+                (e.to_base_url(), max_conns)
+            })
+            .collect()
+    }
+
+    // rolling average latency of each endpoint, as used for selection.
+    pub(crate) fn endpoint_latencies(&self) -> Vec<(String, time::Duration)> {
+        self.endpoints
+            .iter()
+            .map(|e| (e.to_base_url(), e.to_elapsed()))
+            .collect()
+    }
+
+    pub(crate) fn subscribe_failures(&mut self) -> mpsc::Receiver<VerifyFailure> {
+        let (tx, rx) = mpsc::channel();
+        self.failures = Some(tx);
+        rx
+    }
+
+    pub(crate) fn to_remaining_budget(&self) -> Option<usize> {
+        self.state.budget.to_remaining()
+    }
+
+    pub(crate) fn reset_budget(&self) {
+        self.state.budget.reset()
+    }
+
+    // forget the latency history of every endpoint, putting them on an
+    // equal footing. Endpoints found unreachable are picked again.
+    pub(crate) fn reset_metrics(&mut self) {
+        for endp in self.endpoints.iter_mut() {
+            endp.set_elapsed_window(vec![time::Duration::default()]);
+        }
+    }
+
+    // pin every fetch to the endpoint with `base_url`, None restores the
+    // configured selection strategy.
+    pub(crate) fn set_preferred(&mut self, base_url: Option<&str>) -> Result<()> {
+        let base_url = base_url.map(|u| u.trim_end_matches('/'));
+        match base_url {
+            Some(u) if !self.endpoints.iter().any(|e| e.to_base_url() == u) => {
+                err_at!(Invalid, msg: format!("no endpoint {:?}", u))?
+            }
+            _ => (),
+        }
+        trace_event!(info, preferred = ?base_url, "preferred endpoint");
+        self.preferred = base_url.map(|u| u.to_string());
+        Ok(())
+    }
+
+    pub(crate) fn to_preferred(&self) -> Option<String> {
+        self.preferred.clone()
+    }
+
+    pub(crate) fn to_name(&self) -> String {
+        self.name.clone()
+    }
+
+    // turn secure mode on or off, return its previous value.
+    pub(crate) fn set_secure(&mut self, secure: bool) -> bool {
+        std::mem::replace(&mut self.state.secure, secure)
     }
 
     pub(crate) fn to_info(&self) -> Info {
         self.state.info.clone()
     }
 
+    pub(crate) fn to_check_point(&self) -> Option<Random> {
+        self.state.check_point.clone()
+    }
+
+    pub(crate) fn to_verifier(&self) -> Arc<dyn SignatureVerifier> {
+        Arc::clone(&self.state.verifier)
+    }
+
+    // estimate latest round from the local clock, without a network call.
+    // A fetched round is known to be published, so a clock running behind
+    // can't hold the estimate below it. It is not clamped from above, a
+    // lagging endpoint can't be told apart from a clock running ahead,
+    // refer check_stale(). A clock stepped back before genesis falls back
+    // to the latest fetched round, if any.
+    pub(crate) fn current_round(&self) -> Result<u128> {
+        match (
+            self.state.info.round_at(self.state.clock.now()),
+            self.observed,
+        ) {
+            (Ok(round), observed) => Ok(cmp::max(round, observed.unwrap_or_default())),
+            (Err(_), Some(observed)) => Ok(observed),
+            (Err(err), None) => Err(err),
+        }
+    }
+
     pub(crate) async fn boot(&mut self, chain_hash: Option<Vec<u8>>) -> Result<()> {
         let agent = self.user_agent();
-        // root of trust.
+        // root of trust, passed in or pinned via configuration.
+        let chain_hash = chain_hash.or_else(|| self.state.chain_hash.clone());
         let rot = chain_hash.as_ref().map(|x| x.as_slice());
-        let (info, latest) = match self.endpoints.len() {
+        let cross_validate = self.state.cross_validate;
+        let (primary, info, latest) = self.boot_info(rot, cross_validate, agent.clone()).await?;
+
+        let old = self.state.check_point.as_ref().map(|r| r.round);
+        self.state.info = info;
+        // rounds cached under a stale hash-info can't be trusted.
+        if let Some(cache) = self.cache.as_mut() {
+            *cache = Cache::default()
+        }
+        self.state = {
+            let s = self.state.clone();
+            let endp = &mut self.endpoints[primary];
+            let res = endp.boot_phase2(s, latest, agent.clone()).await;
+            res.map_err(|e| endp.tag_boot(e, "phase2"))?
+        };
+        self.save_check_point(old);
+
+        Ok(())
+    }
+
+    // boot each chain in `chains` concurrently, on a copy of this
+    // client's http endpoints addressing the chain by its hash. Every
+    // chain is booted with its own state, starting afresh without a
+    // check_point or checkpoint_store, and booting fails if any of them
+    // fails. gRPC endpoints serve only the node's default chain and are
+    // left out.
+    pub(crate) async fn boot_chains(&self, chains: &[Vec<u8>]) -> Result<Vec<Endpoints>> {
+        let mut chains: Vec<Endpoints> = chains
+            .iter()
+            .map(|chain_hash| self.to_chain(chain_hash.clone()))
+            .collect();
+        let boots = chains.iter_mut().map(|endpoints| endpoints.boot(None));
+        for res in futures::future::join_all(boots).await.into_iter() {
+            res?
+        }
+        Ok(chains)
+    }
+
+    fn to_chain(&self, chain_hash: Vec<u8>) -> Endpoints {
+        let state = State {
+            info: Info::default(),
+            check_point: None,
+            chain_hash: Some(chain_hash),
+            checkpoint_store: None,
+            ..self.state.clone()
+        };
+        let endpoints = self
+            .endpoints
+            .iter()
+            .filter(|e| matches!(e, Inner::Http { .. }))
+            .cloned()
+            .collect();
+        Endpoints {
+            name: self.name.clone(),
+            state,
+            endpoints,
+            restored: Vec::default(),
+            failures: None,
+            picks: 0,
+            preferred: None,
+            observed: None,
+            cache: self.cache.as_ref().map(|_| Cache::default()),
+            duplicates: Vec::default(),
+        }
+    }
+
+    // fetch hash-info and the latest round, cross-validated across
+    // endpoints unless `cross_validate` is false. Return the index of the
+    // endpoint that served them, the primary, to continue the boot with.
+    // Endpoints that failed, say unreachable, are dropped.
+    async fn boot_info(
+        &mut self,
+        rot: Option<&[u8]>,
+        cross_validate: bool,
+        agent: Option<reqwest::header::HeaderValue>,
+    ) -> Result<(usize, Info, Random)> {
+        let res = match self.endpoints.len() {
             0 => err_at!(Invalid, msg: format!("initialize endpoint"))?,
-            1 => self.endpoints[0].boot_phase1(rot, agent.clone()).await?,
+            1 => {
+                let endp = &mut self.endpoints[0];
+                let res = endp.boot_phase1(&self.state, rot, agent.clone()).await;
+                let (info, latest) = res.map_err(|e| endp.tag_boot(e, "phase1"))?;
+                (0, info, latest)
+            }
+            // without cross-validation, boot only with the fastest endpoint,
+            // falling over to the next fastest, and trust the root-of-trust
+            // and chain verification instead. Endpoints with no latency
+            // measured yet, as on a fresh client, stay in the order added.
+            _ if !cross_validate => {
+                let mut order: Vec<usize> = (0..self.endpoints.len()).collect();
+                order.sort_by_key(|i| self.endpoints[*i].to_elapsed());
+
+                let mut failed: Vec<(usize, Error)> = vec![];
+                let mut booted = None;
+                for i in order.into_iter() {
+                    let endp = &mut self.endpoints[i];
+                    match endp.boot_phase1(&self.state, rot, agent.clone()).await {
+                        Ok(val) => {
+                            booted = Some((i, val));
+                            break;
+                        }
+                        Err(err) => match endp.tag_boot(err, "phase1") {
+                            err @ Error::NotSecure(_, _, _) => return Err(err),
+                            err => failed.push((i, err)),
+                        },
+                    }
+                }
+                let (primary, (info, latest)) = match booted {
+                    Some(booted) => booted,
+                    None => return self.to_exhausted(failed),
+                };
+
+                let skip: Vec<usize> = failed.iter().map(|(i, _)| *i).collect();
+                for (_, _err) in failed.iter() {
+                    trace_event!(warn, error = %_err, "dropping endpoint");
+                }
+                let mut i = 0;
+                self.endpoints.retain(|_| {
+                    i += 1;
+                    !skip.contains(&(i - 1))
+                });
+                let primary = primary - skip.iter().filter(|i| **i < primary).count();
+
+                (primary, info, latest)
+            }
             _ => {
-                let (info, latest) = {
-                    let endp = &mut self.endpoints[0];
-                    endp.boot_phase1(rot, agent.clone()).await?
+                // hash-info and latest round from every endpoint, concurrently.
+                let results = {
+                    let (state, agent) = (&self.state, agent.clone());
+                    let phase1 = self.endpoints.iter_mut().map(|endp| {
+                        let agent = agent.clone();
+                        async move {
+                            let res = endp.boot_phase1(state, rot, agent).await;
+                            res.map_err(|e| endp.tag_boot(e, "phase1"))
+                        }
+                    });
+                    futures::future::join_all(phase1).await
                 };
 
+                // first endpoint to succeed is the reference, the rest are
+                // validated against it. Disagreeing endpoint fails the boot,
+                // while an endpoint that failed otherwise is dropped.
+                let mut booted = vec![];
+                let mut failed = vec![];
+                for (i, res) in results.into_iter().enumerate() {
+                    match res {
+                        Ok((info, latest)) => booted.push((i, info, latest)),
+                        Err(err @ Error::NotSecure(_, _, _)) => return Err(err),
+                        Err(err) => failed.push((i, err)),
+                    }
+                }
+                if booted.is_empty() {
+                    return self.to_exhausted(failed);
+                }
+                for (_, _err) in failed.iter() {
+                    trace_event!(warn, error = %_err, "dropping endpoint");
+                }
+                let (reference, info, latest) = booted.remove(0);
+
                 let mut tail = vec![];
-                for mut endp in self.endpoints[1..].to_vec() {
+                for (i, info2, latest2) in booted.into_iter() {
+                    let mut endp = self.endpoints[i].clone();
                     let (info1, latest1) = (info.clone(), latest.clone());
-                    tail.push(async {
-                        let (info2, _) = {
-                            let agent = agent.clone();
-                            endp.boot_phase1(rot, agent).await?
-                        };
-
-                        Self::boot_validate_info(info1, info2)?;
+                    let (state, agent) = (&self.state, agent.clone());
+                    tail.push(async move {
+                        Self::boot_validate_info(info1, info2)
+                            .map_err(|e| endp.tag_boot(e, "agreement"))?;
 
-                        let s = {
-                            let mut s = State::default();
-                            s.check_point = None;
-                            s.secure = false;
-                            s
-                        };
-                        let (_, r) = {
+                        // endpoints can be a round apart, fetch the
+                        // reference's latest round to compare.
+                        let r = if latest2.round == latest1.round {
+                            latest2
+                        } else {
+                            let s = State {
+                                check_point: None,
+                                secure: false,
+                                ..state.clone()
+                            };
                             let round = Some(latest1.round);
-                            endp.get(s, round, agent.clone()).await?
+                            let res = endp.get(s, round, agent.clone()).await;
+                            res.map_err(|e| endp.tag_boot(e, "agreement"))?.1
                         };
-                        Self::boot_validate_latest(latest1, r)?;
+                        Self::boot_validate_latest(latest1, r)
+                            .map_err(|e| endp.tag_boot(e, "agreement"))?;
 
                         Ok::<Inner, Error>(endp)
                     })
                 }
 
-                futures::future::join_all(tail).await;
+                let results = futures::future::join_all(tail).await;
+                self.boot_collect(reference, results)?;
 
-                (info, latest)
+                (0, info, latest)
             }
         };
 
-        self.state.info = info;
-        self.state = {
-            let s = self.state.clone();
-            self.endpoints[0]
-                .boot_phase2(s, latest, agent.clone())
-                .await?
+        Ok(res)
+    }
+
+    // fold back cross-validation results of endpoints validated against
+    // the `reference` endpoint, which becomes the first endpoint.
+    // Disagreeing endpoint fails the boot, while an endpoint that failed
+    // otherwise, say unreachable, is dropped.
+    fn boot_collect(&mut self, reference: usize, results: Vec<Result<Inner>>) -> Result<()> {
+        let mut endpoints = vec![self.endpoints[reference].clone()];
+        for res in results.into_iter() {
+            match res {
+                Ok(endp) => endpoints.push(endp),
+                Err(err @ Error::NotSecure(_, _, _)) => return Err(err),
+                Err(_err) => {
+                    trace_event!(warn, error = %_err, "dropping endpoint");
+                }
+            }
+        }
+        self.endpoints = endpoints;
+
+        Ok(())
+    }
+
+    // verification failure is often due to stale hash-info, say booted
+    // against a degraded endpoint, rather than a bad round. Refresh the
+    // hash-info once, anchored to the current chain-hash, and retry.
+    //
+    // An endpoint serving a forged public-key under the chain-hash could
+    // otherwise take over the client right when it failed verification.
+    // Refreshed hash-info is cross-validated across endpoints, even with
+    // cross-validation disabled, and is rejected unless the check_point,
+    // verified under the previous hash-info, verifies under it as well.
+    async fn refresh_info(&mut self) -> Result<()> {
+        let agent = self.user_agent();
+        let hash = self.state.info.hash.clone();
+        let rot = if hash.is_empty() {
+            None
+        } else {
+            Some(hash.as_slice())
         };
+        let (_, info, _) = self.boot_info(rot, true, agent).await?;
+        if let Some(r) = self.state.check_point.as_ref() {
+            let res = r.verify_with(&info, self.state.verifier.as_ref());
+            err_at!(NotSecure, res, "refreshed hash-info fails check_point")?
+        }
+        self.state.info = info;
+        // rounds cached under a stale hash-info can't be trusted.
+        if let Some(cache) = self.cache.as_mut() {
+            *cache = Cache::default()
+        }
 
         Ok(())
     }
 
     pub(crate) async fn get(&mut self, round: Option<u128>) -> Result<Random> {
+        Ok(self.get_timed(round).await?.value)
+    }
+
+    pub(crate) async fn get_timed(&mut self, round: Option<u128>) -> Result<Timed<Random>> {
+        let now = self.state.clock.now();
+        if let Some(val) = self
+            .cache
+            .as_ref()
+            .and_then(|c| c.get(&self.state, round, now))
+        {
+            return Ok(val);
+        }
+
+        let val = match self.do_get(round).await {
+            Err(err) if self.state.secure && Self::is_not_secure(&err) => {
+                self.refresh_info().await?;
+                self.do_get(round).await
+            }
+            res => res,
+        };
+        // endpoints respond with 404 for a round yet to be published.
+        let val = match (val, round) {
+            (Err(err), Some(round)) if err.is_unpublished() => {
+                return Err(Error::RoundUnavailable { round });
+            }
+            (val, _) => val?,
+        };
+
+        if let Some(cache) = self.cache.as_mut() {
+            cache.insert(round, val.clone())
+        }
+        Ok(val)
+    }
+
+    // ping every endpoint concurrently, and return whether each of them
+    // is reachable, in the order they were added.
+    pub(crate) async fn health_check(&mut self) -> Vec<bool> {
         let agent = self.user_agent();
-        let (state, r) = loop {
-            match self.get_endpoint_pair() {
-                (Some(mut e1), Some(mut e2)) => {
+        let state = &self.state;
+        let checks = self
+            .endpoints
+            .iter_mut()
+            .map(|endp| endp.health_check(state, agent.clone()));
+        futures::future::join_all(checks).await
+    }
+
+    // fetch rounds in `start..end` from the fastest endpoint, falling over
+    // to the next one on failure. Leaves the check_point untouched.
+    pub(crate) async fn get_range(&mut self, start: u128, end: u128) -> Result<Vec<Random>> {
+        if start == 0 || start > end {
+            err_at!(Invalid, msg: format!("invalid range {}..{}", start, end))?
+        }
+
+        let agent = self.user_agent();
+        let mut failed: Vec<(usize, Error)> = vec![];
+        loop {
+            let skip: Vec<usize> = failed.iter().map(|(i, _)| *i).collect();
+            match self.get_endpoint_pair(&skip) {
+                (Some((i, mut endp)), _) => {
+                    let state = &self.state;
+                    match endp.get_range(state, start, end, agent.clone()).await {
+                        Ok(items) => break Ok(items),
+                        Err(err) => {
+                            self.report_failure(Some(start), &endp, &err);
+                            failed.push((i, err));
+                        }
+                    }
+                }
+                (None, _) => break self.to_exhausted(failed),
+            }
+        }
+    }
+
+    // fetch rounds `start..end` from the fastest endpoint, at most
+    // max_conns at a time, into the cache, verified as per configuration.
+    // Rounds that fail to fetch or verify are skipped, return the count of
+    // rounds cached.
+    pub(crate) async fn warm(&mut self, start: u128, end: u128) -> Result<usize> {
+        if start == 0 || start > end {
+            err_at!(Invalid, msg: format!("invalid range {}..{}", start, end))?
+        }
+        if self.cache.is_none() {
+            err_at!(Invalid, msg: format!("cache not enabled"))?
+        }
+        let endp = match self.get_endpoint_pair(&[]) {
+            (Some((_, endp)), _) => endp,
+            (None, _) => err_at!(Invalid, msg: format!("no endpoint to fetch range"))?,
+        };
+        let (agent, host) = (self.user_agent(), endp.to_base_url());
+        let state = State {
+            check_point: None,
+            ..self.state.clone()
+        };
+        let max_conns = cmp::max(state.clone().with_overrides(endp.to_config()).max_conns, 1);
+
+        let rounds = stream::iter(start..end).map(|round| {
+            let (mut endp, state, agent) = (endp.clone(), state.clone(), agent.clone());
+            async move {
+                let (_, r) = endp.get(state, Some(round), agent).await?;
+                Ok::<_, Error>((r, endp.to_last_elapsed()))
+            }
+        });
+        let items: Vec<Result<(Random, time::Duration)>> =
+            rounds.buffer_unordered(max_conns).collect().await;
+
+        let cache = self.cache.as_mut().unwrap();
+        let mut n = 0;
+        for (r, elapsed) in items.into_iter().filter_map(|item| item.ok()) {
+            let host = host.clone();
+            cache.insert(
+                Some(r.round),
+                Timed {
+                    value: r,
+                    elapsed,
+                    host,
+                },
+            );
+            n += 1;
+        }
+        Ok(n)
+    }
+
+    // fetch rounds `start..end` from the fastest endpoint, at most
+    // max_conns at a time, and yield them in round order as they verify.
+    // Each round is checked for its randomness, its link with the previous
+    // round and its signature, the first round is linked to group_hash if
+    // it is round-1, else only its signature is verified. Only the previous
+    // round is retained, and the stream ends after the first error.
+    pub(crate) fn verify_chain(
+        &mut self,
+        start: u128,
+        end: u128,
+    ) -> Result<impl Stream<Item = Result<Random>>> {
+        self.stream_range(start, end, true)
+    }
+
+    // same as verify_chain(), rounds are verified only in secure mode.
+    pub(crate) fn for_each_round(
+        &mut self,
+        start: u128,
+        end: u128,
+    ) -> Result<impl Stream<Item = Result<Random>>> {
+        let secure = self.state.secure;
+        self.stream_range(start, end, secure)
+    }
+
+    // rounds are fetched ahead of the consumer by at most max_conns, a
+    // slow consumer holds up fetching.
+    fn stream_range(
+        &mut self,
+        start: u128,
+        end: u128,
+        verify: bool,
+    ) -> Result<impl Stream<Item = Result<Random>>> {
+        if start == 0 || start > end {
+            err_at!(Invalid, msg: format!("invalid range {}..{}", start, end))?
+        }
+        let endp = match self.get_endpoint_pair(&[]) {
+            (Some((_, endp)), _) => endp,
+            (None, _) => err_at!(Invalid, msg: format!("no endpoint to fetch range"))?,
+        };
+        let agent = self.user_agent();
+        // rounds are fetched as is and verified here.
+        let state = State {
+            secure: false,
+            check_point: None,
+            ..self.state.clone()
+        };
+        let (info, verifier) = (state.info.clone(), Arc::clone(&state.verifier));
+        let max_conns = cmp::max(state.clone().with_overrides(endp.to_config()).max_conns, 1);
+
+        let rounds = stream::iter(start..end).map(move |round| {
+            let (mut endp, state, agent) = (endp.clone(), state.clone(), agent.clone());
+            async move { Ok(endp.get(state, Some(round), agent).await?.1) }
+        });
+        let items = rounds.buffered(max_conns).scan(
+            (None, false),
+            move |(prev, done): &mut (Option<Random>, bool), item: Result<Random>| {
+                if *done {
+                    return futures::future::ready(None);
+                }
+                let res = item.and_then(|curr| {
+                    let verifier = verifier.as_ref();
+                    match prev.as_ref() {
+                        _ if !verify => (),
+                        Some(prev) => {
+                            if !verify::verify_chain(verifier, &info, &prev.signature, &curr)? {
+                                err_at!(NotSecure, msg: format!("chain break at {}", curr))?
+                            }
+                        }
+                        None if curr.round == 1 => info.verify_genesis_with(&curr, verifier)?,
+                        None => curr.verify_with(&info, verifier)?,
+                    }
+                    Ok(curr)
+                });
+                *done = res.is_err();
+                *prev = res.as_ref().ok().cloned();
+                futures::future::ready(Some(res))
+            },
+        );
+
+        Ok(items)
+    }
+
+    // same as get_timed(), abandoned once `deadline` passes, irrespective
+    // of retries and falling over to other endpoints. `deadline` is an
+    // instant on the monotonic clock, hence awaited on tokio's timer and
+    // not on the configured clock.
+    pub(crate) async fn get_deadline(
+        &mut self,
+        round: Option<u128>,
+        deadline: time::Instant,
+    ) -> Result<Timed<Random>> {
+        use futures::future::{select, Either};
+
+        let timer = tokio::time::delay_until(deadline.into());
+        let fut = self.get_timed(round);
+        futures::pin_mut!(fut);
+        match select(fut, timer).await {
+            Either::Left((res, _)) => res,
+            Either::Right(_) => {
+                let r = round.map_or("latest".to_string(), |r| r.to_string());
+                err_at!(Timeout, msg: format!("round {} past deadline", r))
+            }
+        }
+    }
+
+    // schedule to wait for `round` to be published and fetch it, refer
+    // WatchRetry. Callers wait and fetch in turns, so that the endpoints
+    // need not be held while waiting.
+    pub(crate) fn to_watch_retry(&self, round: u128) -> Result<WatchRetry> {
+        let period = self.state.info.period;
+        if period == time::Duration::default() {
+            err_at!(Invalid, msg: format!("watch before boot"))?
+        }
+        let val = WatchRetry {
+            clock: Arc::clone(&self.state.clock),
+            due: self.state.info.time_of_round(round)?,
+            period,
+            deadline: None,
+            backoff: cmp::min(time::Duration::from_millis(250), period),
+        };
+        Ok(val)
+    }
+
+    // same as get(), verifying the round here and reporting a failed
+    // verification in the returned value rather than as an error. Meant
+    // to be called with secure mode off, refer set_secure(), a round that
+    // fails verification leaves the check_point and the latest observed
+    // round untouched, and is evicted from the cache so that it is never
+    // served in secure mode.
+    pub(crate) async fn get_verified(&mut self, round: u128) -> Result<VerifiedRandom> {
+        let (check_point, observed) = (self.state.check_point.clone(), self.observed);
+        let r = self.get(Some(round)).await?;
+        let val = match r.verify_with(&self.state.info, self.state.verifier.as_ref()) {
+            Ok(()) => VerifiedRandom {
+                random: r,
+                verified: true,
+                verify_error: None,
+            },
+            Err(err) => {
+                self.state.check_point = check_point;
+                self.observed = observed;
+                if let Some(cache) = self.cache.as_mut() {
+                    cache.remove(r.round)
+                }
+                VerifiedRandom {
+                    random: r,
+                    verified: false,
+                    verify_error: Some(err),
+                }
+            }
+        };
+        Ok(val)
+    }
+
+    async fn do_get(&mut self, round: Option<u128>) -> Result<Timed<Random>> {
+        let (mut state, r, endp) = match (self.state.quorum, self.state.selection) {
+            (Some((n, k)), _) => self.get_quorum(round, n, k).await?,
+            // two endpoints shall confirm the round.
+            (None, _) if self.state.secure && self.state.require_agreement => {
+                self.get_quorum(round, 2, 2).await?
+            }
+            (None, SelectionStrategy::FirstOk) => self.get_first_ok(round).await?,
+            (None, _) => self.get_racing(round).await?,
+        };
+        if round.is_none() {
+            Self::validate_latest_time(&state.info, &r, state.clock.now())?;
+        }
+        let old = self.state.check_point.as_ref().map(|r| r.round);
+        let check_point = {
+            let (old, new) = (self.state.check_point.take(), state.check_point.take());
+            Self::merge_check_point(old, new)
+        };
+        self.state = state;
+        self.state.check_point = check_point;
+        self.save_check_point(old);
+        self.observed = cmp::max(self.observed, Some(r.round));
+
+        let val = Timed {
+            value: r,
+            elapsed: endp.to_last_elapsed(),
+            host: endp.to_base_url(),
+        };
+        Ok(val)
+    }
+
+    // race the fetch on two endpoints, falling over to the next pair of
+    // endpoints if both fail.
+    async fn get_racing(&mut self, round: Option<u128>) -> Result<(State, Random, Inner)> {
+        let agent = self.user_agent();
+        // endpoints that failed in this call, along with their error.
+        let mut failed: Vec<(usize, Error)> = vec![];
+        let val = loop {
+            let skip: Vec<usize> = failed.iter().map(|(i, _)| *i).collect();
+            match self.get_endpoint_pair(&skip) {
+                (Some((i, mut e1)), Some((j, mut e2))) => {
                     let (res1, res2) = futures::join!(
                         e1.get(self.state.clone(), round, agent.clone()),
                         e2.get(self.state.clone(), round, agent.clone()),
                     );
+                    if round.is_none() {
+                        if let Ok((_, r1)) = &res1 {
+                            self.check_stale(i, r1);
+                        }
+                        if let Ok((_, r2)) = &res2 {
+                            self.check_stale(j, r2);
+                        }
+                    }
                     match (res1, res2) {
                         (Ok((s1, r1)), Ok((s2, r2))) => {
                             if r1.round > r2.round {
-                                break (s1, r1);
+                                break (s1, r1, e1);
                             } else {
-                                break (s2, r2);
+                                break (s2, r2, e2);
                             };
                         }
-                        (Ok((s1, r1)), Err(_)) => break (s1, r1),
-                        (Err(_), Ok((s2, r2))) => break (s2, r2),
-                        (Err(_), Err(_)) => (),
+                        (Ok((s1, r1)), Err(err)) => {
+                            self.report_failure(round, &e2, &err);
+                            break (s1, r1, e1);
+                        }
+                        (Err(err), Ok((s2, r2))) => {
+                            self.report_failure(round, &e1, &err);
+                            break (s2, r2, e2);
+                        }
+                        (Err(err1), Err(err2)) => {
+                            self.report_failure(round, &e1, &err1);
+                            self.report_failure(round, &e2, &err2);
+                            failed.push((i, err1));
+                            failed.push((j, err2));
+                        }
                     };
                 }
-                (Some(mut e1), None) => {
+                (Some((i, mut e1)), None) => {
                     let state = self.state.clone();
-                    let (state, r) = e1.get(state, round, agent).await?;
-                    break (state, r);
-                }
-                (None, _) => {
-                    let msg = format!("missing/exhausted endpoint");
-                    err_at!(IOError, msg: msg)?
+                    match e1.get(state, round, agent.clone()).await {
+                        Ok((state, r)) => {
+                            if round.is_none() {
+                                self.check_stale(i, &r);
+                            }
+                            break (state, r, e1);
+                        }
+                        Err(err) => {
+                            self.report_failure(round, &e1, &err);
+                            failed.push((i, err));
+                        }
+                    }
                 }
+                (None, _) => return self.to_exhausted(failed),
             }
         };
-        self.state = state;
+        Ok(val)
+    }
+
+    // race the fetch on every healthy endpoint and return the first to
+    // succeed, the remaining fetches are dropped.
+    async fn get_first_ok(&mut self, round: Option<u128>) -> Result<(State, Random, Inner)> {
+        use futures::stream::FuturesUnordered;
+
+        let agent = self.user_agent();
+        let mut fetches: FuturesUnordered<_> = self
+            .get_endpoints(&[], usize::MAX)
+            .into_iter()
+            .map(|(i, mut endp)| {
+                let (state, agent) = (self.state.clone(), agent.clone());
+                async move {
+                    let res = endp.get(state, round, agent).await;
+                    (i, endp, res)
+                }
+            })
+            .collect();
 
-        Ok(r)
+        let mut failed: Vec<(usize, Error)> = vec![];
+        while let Some((i, endp, res)) = fetches.next().await {
+            match res {
+                Ok((state, r)) => {
+                    if round.is_none() {
+                        self.check_stale(i, &r);
+                    }
+                    return Ok((state, r, endp));
+                }
+                Err(err) => {
+                    self.report_failure(round, &endp, &err);
+                    failed.push((i, err));
+                }
+            }
+        }
+        self.to_exhausted(failed)
+    }
+
+    // fetch from `n` endpoints concurrently and accept the round only if
+    // at least `k` of them agree on it.
+    async fn get_quorum(
+        &mut self,
+        round: Option<u128>,
+        n: usize,
+        k: usize,
+    ) -> Result<(State, Random, Inner)> {
+        if k == 0 || k > n {
+            err_at!(Invalid, msg: format!("quorum {} of {}", k, n))?
+        }
+
+        let agent = self.user_agent();
+        let fetches = self.get_endpoints(&[], n).into_iter().map(|(i, mut endp)| {
+            let (state, agent) = (self.state.clone(), agent.clone());
+            async move {
+                let res = endp.get(state, round, agent).await;
+                (i, endp, res)
+            }
+        });
+        let results = futures::future::join_all(fetches).await;
+
+        let mut failed: Vec<(usize, Error)> = vec![];
+        let mut oks: Vec<(State, Random, Inner)> = vec![];
+        for (i, endp, res) in results.into_iter() {
+            match res {
+                Ok((state, r)) => {
+                    if round.is_none() {
+                        self.check_stale(i, &r);
+                    }
+                    oks.push((state, r, endp))
+                }
+                Err(err) => {
+                    self.report_failure(round, &endp, &err);
+                    failed.push((i, err));
+                }
+            }
+        }
+        if oks.len() < k {
+            return self.to_exhausted(failed);
+        }
+
+        // most agreed upon round, the later round on a tie.
+        let agree = |x: &Random, y: &Random| {
+            x.round == y.round && x.signature == y.signature && x.randomness == y.randomness
+        };
+        let mut best: Option<(usize, usize)> = None;
+        for (x, (_, r, _)) in oks.iter().enumerate() {
+            let votes = oks.iter().filter(|(_, o, _)| agree(o, r)).count();
+            best = match best {
+                Some((y, v)) if v > votes || (v == votes && oks[y].1.round >= r.round) => {
+                    Some((y, v))
+                }
+                _ => Some((x, votes)),
+            };
+        }
+        match best {
+            Some((x, votes)) if votes >= k => Ok(oks.swap_remove(x)),
+            Some((_, votes)) => {
+                let msg = format!("quorum not reached {} of {}, need {}", votes, n, k);
+                err_at!(NotSecure, msg: msg)
+            }
+            None => self.to_exhausted(failed),
+        }
     }
 }
 
 impl Endpoints {
+    fn restore_validate(state: &State) -> Result<()> {
+        let info = &state.info;
+        if info.public_key.is_empty() || info.period == time::Duration::default() {
+            err_at!(Invalid, msg: format!("restore incomplete hash-info"))?
+        }
+        match &state.check_point {
+            Some(r) => {
+                let (verifier, psign) = (state.verifier.as_ref(), &r.previous_signature);
+                match verify::verify_chain(verifier, info, psign, r)? {
+                    true => Ok(()),
+                    false => err_at!(NotSecure, msg: format!("restore check_point {}", r)),
+                }
+            }
+            None => Ok(()),
+        }
+    }
+
+    // report rounds that failed verification to the subscriber, even if
+    // another endpoint served the round, to surface flaky/malicious ones.
+    fn report_failure(&self, round: Option<u128>, endp: &Inner, err: &Error) {
+        if let (Some(tx), Error::NotSecure(_, _, _)) = (&self.failures, err) {
+            let failure = VerifyFailure {
+                round,
+                host: endp.to_base_url(),
+                error: err.clone(),
+            };
+            // subscriber might have gone away, which is fine.
+            tx.send(failure).ok();
+        }
+    }
+
+    // flag endpoint `i` that served `r` as the latest round, but more than
+    // a period behind the local clock's estimate. Stale endpoint is
+    // reported to the observer and penalised like a failed call, so that
+    // it is picked less often.
+    fn check_stale(&mut self, i: usize, r: &Random) {
+        use crate::http::MAX_ELAPSED;
+
+        let expected = match self.current_round() {
+            Ok(expected) if expected > r.round + 1 => expected,
+            _ => return,
+        };
+        let endp = &mut self.endpoints[i];
+        let url = endp.to_base_url();
+        trace_event!(warn, endpoint = %url, round = %r.round, expected = %expected, "stale");
+        self.state.observer.on_stale(&url, r.round, expected);
+
+        let mut window = endp.to_elapsed_window();
+        let penalty = endp.to_elapsed().checked_mul(2).unwrap_or(MAX_ELAPSED);
+        window.push(cmp::min(penalty, MAX_ELAPSED));
+        endp.set_elapsed_window(window);
+    }
+
+    // persist the check_point if it advanced past round `old`, only
+    // verified check_points, in secure mode, are persisted.
+    fn save_check_point(&self, old: Option<u128>) {
+        let state = &self.state;
+        let advanced = match (&state.check_point, old) {
+            (Some(r), Some(old)) => r.round > old,
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        match (&state.checkpoint_store, &state.check_point) {
+            (Some(store), Some(r)) if state.secure && advanced => store.save(r),
+            _ => (),
+        }
+    }
+
+    // check_point only moves forward, fetching an older round or a stale
+    // latest round from a lagging endpoint must not regress it.
+    fn merge_check_point(old: Option<Random>, new: Option<Random>) -> Option<Random> {
+        match (old, new) {
+            (Some(old), Some(new)) if new.round < old.round => Some(old),
+            (old, None) => old,
+            (_, new) => new,
+        }
+    }
+
+    // latest round can't have been emitted later than a period from now,
+    // allowing for some clock skew between local clock and drand-group.
+    fn validate_latest_time(info: &Info, r: &Random, now: time::SystemTime) -> Result<()> {
+        let emitted = info.time_of_round(r.round).ok();
+        let bound = match info.period.checked_add(MAX_CLOCK_SKEW) {
+            Some(ahead) => now.checked_add(ahead),
+            None => None,
+        };
+
+        match (emitted, bound) {
+            (Some(emitted), Some(bound)) if emitted <= bound => Ok(()),
+            (_, None) => err_at!(NotSecure, msg: format!("period {:?} out of range", info.period)),
+            _ => err_at!(NotSecure, msg: format!("future round {}", r)),
+        }
+    }
+
     fn boot_validate_info(this: Info, other: Info) -> Result<()> {
-        if this.public_key != other.public_key {
-            let x = hex::encode(&this.public_key);
-            let y = hex::encode(&other.public_key);
-            err_at!(NotSecure, msg: format!("public-key {} ! {}", x, y))
+        let mismatch = if this.public_key != other.public_key {
+            trace_event!(
+                warn,
+                this = %this.public_key_hex(),
+                other = %other.public_key_hex(),
+                "public-key mismatch"
+            );
+            Mismatch::PublicKey {
+                expected: this.public_key,
+                got: other.public_key,
+            }
         } else if this.hash != other.hash {
-            let x = hex::encode(&this.hash);
-            let y = hex::encode(&other.hash);
-            err_at!(NotSecure, msg: format!("hash {} != {}", x, y))
+            trace_event!(
+                warn,
+                this = %this.hash_hex(),
+                other = %other.hash_hex(),
+                "hash mismatch"
+            );
+            Mismatch::ChainHash {
+                expected: this.hash,
+                got: other.hash,
+            }
         } else {
-            Ok(())
-        }
+            return Ok(());
+        };
+
+        err_at!(NotSecure, source: Err(mismatch))
     }
 
     fn boot_validate_latest(this: Random, other: Random) -> Result<()> {
-        if this.round != other.round {
-            err_at!(
-                NotSecure,
-                msg: format!("round {} != {}", this.round, other.round)
-            )
+        let round = this.round;
+        let mismatch = if this.round != other.round {
+            trace_event!(warn, this = %this.round, other = %other.round, "round mismatch");
+            Mismatch::Round {
+                expected: this.round,
+                got: other.round,
+            }
         } else if this.randomness != other.randomness {
-            let x = hex::encode(&this.randomness);
-            let y = hex::encode(&other.randomness);
-            err_at!(NotSecure, msg: format!("randomness {} != {} ", x, y))
+            trace_event!(
+                warn,
+                round = %round,
+                this = %this.randomness_hex(),
+                other = %other.randomness_hex(),
+                "randomness mismatch"
+            );
+            Mismatch::Randomness {
+                round,
+                expected: this.randomness,
+                got: other.randomness,
+            }
         } else if this.signature != other.signature {
-            let x = hex::encode(&this.signature);
-            let y = hex::encode(&other.signature);
-            err_at!(NotSecure, msg: format!("signature {} != {}", x, y))
+            trace_event!(
+                warn,
+                round = %round,
+                this = %this.signature_hex(),
+                other = %other.signature_hex(),
+                "signature mismatch"
+            );
+            Mismatch::Signature {
+                round,
+                expected: this.signature,
+                got: other.signature,
+            }
         } else if this.previous_signature != other.previous_signature {
-            let x = hex::encode(&this.previous_signature);
-            let y = hex::encode(&other.previous_signature);
-            err_at!(NotSecure, msg: format!("previous_signature {} != {}", x, y))
+            trace_event!(
+                warn,
+                round = %round,
+                this = %this.previous_signature_hex(),
+                other = %other.previous_signature_hex(),
+                "previous_signature mismatch"
+            );
+            Mismatch::PreviousSignature {
+                round,
+                expected: this.previous_signature,
+                got: other.previous_signature,
+            }
         } else {
-            Ok(())
-        }
+            return Ok(());
+        };
+
+        err_at!(NotSecure, source: Err(mismatch))
     }
 
-    fn get_endpoint_pair(&self) -> (Option<Inner>, Option<Inner>) {
+    // pick two endpoints, along with their index, as per the configured
+    // selection strategy, skipping the endpoints in `skip`.
+    fn get_endpoint_pair(&mut self, skip: &[usize]) -> (Option<Pick>, Option<Pick>) {
+        let mut iter = self.get_endpoints(skip, 2).into_iter();
+        (iter.next(), iter.next())
+    }
+
+    // pick upto `n` endpoints, along with their index, as per the
+    // configured selection strategy, skipping the endpoints in `skip`.
+    fn get_endpoints(&mut self, skip: &[usize], n: usize) -> Vec<Pick> {
         use crate::http::MAX_ELAPSED;
 
+        // pinned endpoint is picked exclusively, irrespective of latency.
+        if let Some(preferred) = &self.preferred {
+            return self
+                .endpoints
+                .iter()
+                .enumerate()
+                .filter(|(i, e)| !skip.contains(i) && &e.to_base_url() == preferred)
+                .map(|(i, e)| (i, e.clone()))
+                .take(n)
+                .collect();
+        }
+
         let mut endpoints = vec![];
         for (i, endp) in self.endpoints.iter().enumerate() {
-            if endp.to_elapsed() < MAX_ELAPSED {
+            if endp.to_elapsed() < MAX_ELAPSED && !skip.contains(&i) {
                 endpoints.push((i, endp.to_elapsed()));
             }
         }
-        endpoints.sort_by(|x, y| x.1.cmp(&y.1));
-
-        let mut iter = endpoints.iter();
-        match (iter.next(), iter.next()) {
-            (Some((i, _)), Some((j, _))) => {
-                let x = Some(self.endpoints[*i].clone());
-                let y = Some(self.endpoints[*j].clone());
-                (x, y)
+        if endpoints.is_empty() && self.state.degraded_fallback {
+            let least = self
+                .endpoints
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !skip.contains(i))
+                .min_by_key(|(_, endp)| endp.to_elapsed());
+            trace_event!(debug, ?skip, picked = ?least.map(|(i, _)| i), "degraded pick");
+            return least
+                .map(|(i, endp)| (i, endp.clone()))
+                .into_iter()
+                .collect();
+        }
+        match self.state.selection {
+            SelectionStrategy::Fastest | SelectionStrategy::FirstOk => {
+                endpoints.sort_by(|x, y| x.1.cmp(&y.1))
             }
-            (Some((i, _)), None) => {
-                let x = Some(self.endpoints[*i].clone());
-                let y = None;
-                (x, y)
+            SelectionStrategy::RoundRobin if !endpoints.is_empty() => {
+                let n = self.picks % endpoints.len();
+                endpoints.rotate_left(n);
             }
-            (None, _) => (None, None),
+            SelectionStrategy::RoundRobin => (),
+            SelectionStrategy::Weighted => weighted_order(&mut endpoints, pseudo_random()),
+        }
+        self.picks = self.picks.wrapping_add(1);
+        endpoints.truncate(n);
+        trace_event!(
+            debug,
+            strategy = ?self.state.selection,
+            ?skip,
+            picked = ?endpoints,
+            "endpoints picked"
+        );
+
+        endpoints
+            .into_iter()
+            .map(|(i, _)| (i, self.endpoints[i].clone()))
+            .collect()
+    }
+
+    // error to return once every endpoint has failed, a lone failure is
+    // returned as is.
+    fn to_exhausted<T>(&self, mut failed: Vec<(usize, Error)>) -> Result<T> {
+        match failed.len() {
+            0 => err_at!(IOError, msg: format!("missing/exhausted endpoint")),
+            1 => Err(failed.remove(0).1),
+            _ => {
+                let prefix = format!("{}:{}", file!(), line!());
+                let errs = failed
+                    .into_iter()
+                    .map(|(i, err)| (self.endpoints[i].to_base_url(), err))
+                    .collect();
+                Err(Error::Exhausted(prefix, errs))
+            }
+        }
+    }
+
+    // whether `err` is, or includes, a failed verification.
+    fn is_not_secure(err: &Error) -> bool {
+        match err {
+            Error::NotSecure(_, _, _) => true,
+            Error::Exhausted(_, errs) => errs.iter().any(|(_, e)| Self::is_not_secure(e)),
+            _ => false,
         }
     }
 
@@ -251,47 +1407,435 @@ impl Endpoints {
     }
 }
 
+// Maximum number of rounds held in cache, beyond which the earliest
+// rounds are evicted.
+const CACHE_SIZE: usize = 1024;
+
+// Schedule to watch for a round. Wait till the round is due, then till
+// the round lands on the endpoints, RoundUnavailable, it is polled with
+// back-off. Other failed fetches are retried for about a period,
+// verification failures are not.
+pub(crate) struct WatchRetry {
+    clock: Arc<dyn Clock>,
+    due: time::SystemTime,
+    period: time::Duration,
+    // retries are bounded by a period from when the round is due.
+    deadline: Option<time::SystemTime>,
+    backoff: time::Duration,
+}
+
+impl WatchRetry {
+    // wait till the round is due.
+    pub(crate) async fn wait(&mut self) -> Result<()> {
+        if let Ok(wait) = self.due.duration_since(self.clock.now()) {
+            self.clock.delay(wait).await;
+        }
+        self.deadline = match self.clock.now().checked_add(self.period) {
+            Some(deadline) => Some(deadline),
+            None => err_at!(Invalid, msg: format!("period {:?} out of range", self.period))?,
+        };
+        Ok(())
+    }
+
+    // back-off after a fetch failed with `err`, return false to give up.
+    pub(crate) async fn retry(&mut self, err: &Error) -> bool {
+        match err {
+            err if Endpoints::is_not_secure(err) => return false,
+            Error::RoundUnavailable { .. } => (),
+            _ => match self.clock.now().checked_add(self.backoff) {
+                Some(retry) if Some(retry) <= self.deadline => (),
+                _ => return false,
+            },
+        }
+        self.clock.delay(self.backoff).await;
+        self.backoff = cmp::min(self.backoff.saturating_mul(2), self.period);
+        true
+    }
+}
+
+// Cache of fetched rounds, keyed by round, along with the latest round
+// which is valid only till the next round is due.
+#[derive(Default)]
+struct Cache {
+    rounds: BTreeMap<u128, Timed<Random>>,
+    latest: Option<Timed<Random>>,
+}
+
+impl Cache {
+    fn from_config(config: &Config) -> Option<Cache> {
+        match config.cache {
+            true => Some(Cache::default()),
+            false => None,
+        }
+    }
+
+    // cached value for `round`, None for the latest round, as of `now`.
+    // Cache hits take no time.
+    fn get(
+        &self,
+        state: &State,
+        round: Option<u128>,
+        now: time::SystemTime,
+    ) -> Option<Timed<Random>> {
+        let val = match round {
+            Some(round) => self.rounds.get(&round),
+            None => {
+                let latest = self.latest.as_ref()?;
+                let next = state.info.time_of_round(latest.value.round + 1).ok()?;
+                if now < next {
+                    Some(latest)
+                } else {
+                    None
+                }
+            }
+        };
+        val.map(|val| Timed {
+            elapsed: time::Duration::default(),
+            ..val.clone()
+        })
+    }
+
+    fn remove(&mut self, round: u128) {
+        self.rounds.remove(&round);
+        if self.latest.as_ref().map(|r| r.value.round) == Some(round) {
+            self.latest = None
+        }
+    }
+
+    fn insert(&mut self, round: Option<u128>, val: Timed<Random>) {
+        if round.is_none() {
+            self.latest = Some(val.clone());
+        }
+        self.rounds.insert(val.value.round, val);
+        while self.rounds.len() > CACHE_SIZE {
+            let first = *self.rounds.keys().next().unwrap();
+            self.rounds.remove(&first);
+        }
+    }
+}
+
+// endpoint picked for a call, along with its index.
+type Pick = (usize, Inner);
+
 #[derive(Clone)]
 enum Inner {
-    Http { name: String, endp: Http },
+    Http {
+        name: String,
+        endp: Http,
+        config: EndpointConfig,
+    },
+    #[cfg(feature = "grpc")]
+    Grpc {
+        name: String,
+        endp: Grpc,
+        config: EndpointConfig,
+    },
 }
 
+// Per-endpoint overrides apply only for the duration of a call, only the
+// check_point established by the endpoint flows back into client's state.
 impl Inner {
     async fn boot_phase1(
         &mut self,
+        state: &State,
         rot: Option<&[u8]>,
         agent: Option<reqwest::header::HeaderValue>,
     ) -> Result<(Info, Random)> {
-        match self {
-            Inner::Http { endp, .. } => endp.boot_phase1(rot, agent).await,
-        }
+        let (url, start) = (self.to_base_url(), time::Instant::now());
+        state.observer.on_request(&url);
+        let fut = async {
+            match self {
+                Inner::Http { endp, config, .. } => {
+                    let s = state.clone().with_overrides(config);
+                    endp.boot_phase1(&s, rot, agent).await
+                }
+                #[cfg(feature = "grpc")]
+                Inner::Grpc { endp, config, .. } => {
+                    let s = state.clone().with_overrides(config);
+                    endp.boot_phase1(&s, rot).await
+                }
+            }
+        };
+        let res = instrument!(fut, "boot_phase1", endpoint = %url).await;
+        Self::observe(state, &url, start, res, |(_, latest)| latest.round)
     }
 
     async fn boot_phase2(
         &mut self,
-        state: State,
+        mut state: State,
         latest: Random,
         agent: Option<reqwest::header::HeaderValue>,
     ) -> Result<State> {
-        match self {
-            Inner::Http { endp, .. } => endp.boot_phase2(state, latest, agent).await,
-        }
+        let (url, start) = (self.to_base_url(), time::Instant::now());
+        state.observer.on_request(&url);
+        let latest_round = latest.round;
+        let fut = async {
+            match self {
+                Inner::Http { endp, config, .. } => {
+                    let s = state.clone().with_overrides(config);
+                    endp.boot_phase2(s, latest, agent).await
+                }
+                #[cfg(feature = "grpc")]
+                Inner::Grpc { endp, config, .. } => {
+                    let s = state.clone().with_overrides(config);
+                    endp.boot_phase2(s, latest).await
+                }
+            }
+        };
+        let res = instrument!(fut, "boot_phase2", endpoint = %url, round = %latest_round).await;
+        let s = Self::observe(&state, &url, start, res, |s| match &s.check_point {
+            Some(r) => r.round,
+            None => latest_round,
+        })?;
+        state.check_point = s.check_point;
+        Ok(state)
     }
 
     async fn get(
         &mut self,
-        state: State,
+        mut state: State,
         round: Option<u128>,
         agent: Option<reqwest::header::HeaderValue>,
     ) -> Result<(State, Random)> {
-        match self {
-            Inner::Http { endp, .. } => endp.get(state, round, agent).await,
+        let s = state.clone().with_overrides(self.to_config());
+        let url = self.to_base_url();
+        let mut retries = 0;
+        let (s, r) = loop {
+            let start = time::Instant::now();
+            s.observer.on_request(&url);
+            let fut = async {
+                match self {
+                    Inner::Http { endp, .. } => endp.get(s.clone(), round, agent.clone()).await,
+                    #[cfg(feature = "grpc")]
+                    Inner::Grpc { endp, .. } => endp.get(s.clone(), round).await,
+                }
+            };
+            let res = instrument!(fut, "get", endpoint = %url, round = ?round, retries).await;
+            match Self::observe(&s, &url, start, res, |(_, r)| r.round) {
+                Ok(val) => break val,
+                Err(err) if retries < s.max_retries && Self::is_retryable(&s, &err) => {
+                    s.clock.delay(retry_backoff(s.retry_delay, retries)).await;
+                    retries += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        };
+        state.check_point = s.check_point;
+        Ok((state, r))
+    }
+
+    async fn get_range(
+        &mut self,
+        state: &State,
+        start: u128,
+        end: u128,
+        agent: Option<reqwest::header::HeaderValue>,
+    ) -> Result<Vec<Random>> {
+        let (url, began) = (self.to_base_url(), time::Instant::now());
+        state.observer.on_request(&url);
+        let fut = async {
+            match self {
+                Inner::Http { endp, config, .. } => {
+                    let s = state.clone().with_overrides(config);
+                    endp.get_range(&s, start, end, agent).await
+                }
+                #[cfg(feature = "grpc")]
+                Inner::Grpc { endp, config, .. } => {
+                    let s = state.clone().with_overrides(config);
+                    endp.get_range(&s, start, end).await
+                }
+            }
+        };
+        let res = instrument!(fut, "get_range", endpoint = %url, start = %start, end = %end).await;
+        Self::observe(state, &url, began, res, |items| match items.last() {
+            Some(r) => r.round,
+            None => start,
+        })
+    }
+
+    // report the outcome of a call to `url`, that started at `start`, to
+    // the observer. `round` picks the round served by a successful call.
+    fn observe<T, F>(
+        state: &State,
+        url: &str,
+        start: time::Instant,
+        res: Result<T>,
+        round: F,
+    ) -> Result<T>
+    where
+        F: FnOnce(&T) -> u128,
+    {
+        let elapsed = start.elapsed();
+        match &res {
+            Ok(val) => {
+                let round = round(val);
+                trace_event!(debug, endpoint = url, round = %round, ?elapsed, "served");
+                state.observer.on_success(url, round, elapsed)
+            }
+            Err(err @ Error::NotSecure(_, _, _)) => {
+                trace_event!(warn, endpoint = url, ?elapsed, error = %err, "not secure");
+                state.observer.on_error(url, err)
+            }
+            Err(err) => {
+                trace_event!(debug, endpoint = url, ?elapsed, error = %err, "failed");
+                state.observer.on_error(url, err)
+            }
+        }
+        res
+    }
+
+    // a reachable endpoint starts afresh with the ping's latency, an
+    // unreachable one is marked with MAX_ELAPSED so that it is not picked
+    // till a later health check finds it reachable.
+    async fn health_check(
+        &mut self,
+        state: &State,
+        agent: Option<reqwest::header::HeaderValue>,
+    ) -> bool {
+        use crate::http::MAX_ELAPSED;
+
+        let res = match self {
+            Inner::Http { endp, config, .. } => {
+                let s = state.clone().with_overrides(config);
+                endp.ping(&s, agent).await
+            }
+            #[cfg(feature = "grpc")]
+            Inner::Grpc { endp, config, .. } => {
+                let s = state.clone().with_overrides(config);
+                endp.ping(&s).await
+            }
+        };
+        match res {
+            Ok(elapsed) => {
+                self.set_elapsed_window(vec![elapsed]);
+                true
+            }
+            // says nothing about the endpoint.
+            Err(_) if state.budget.to_remaining() == Some(0) => false,
+            Err(_) => {
+                self.set_elapsed_window(vec![MAX_ELAPSED]);
+                false
+            }
+        }
+    }
+
+    // only transient failures, network errors, timeouts, server errors
+    // and throttling, are retried. A spent byte budget won't recover by
+    // retrying.
+    fn is_retryable(state: &State, err: &Error) -> bool {
+        match err {
+            Error::IOError(_, _, _) => state.budget.to_remaining() != Some(0),
+            Error::Http { status, .. } => *status >= 500 || *status == 429,
+            _ => false,
         }
     }
 
     fn to_elapsed(&self) -> time::Duration {
         match self {
             Inner::Http { endp, .. } => endp.to_elapsed(),
+            #[cfg(feature = "grpc")]
+            Inner::Grpc { endp, .. } => endp.to_elapsed(),
+        }
+    }
+
+    // tag boot errors with the phase and endpoint that failed, phase being
+    // one of `phase1`, `agreement`, `phase2`.
+    fn tag_boot(&self, err: Error, phase: &str) -> Error {
+        let tag = format!("boot-{} {}", phase, self.to_base_url());
+        err.tag_prefix(&tag)
+    }
+
+    fn to_last_elapsed(&self) -> time::Duration {
+        match self {
+            Inner::Http { endp, .. } => endp.to_last_elapsed(),
+            #[cfg(feature = "grpc")]
+            Inner::Grpc { endp, .. } => endp.to_last_elapsed(),
+        }
+    }
+
+    fn to_elapsed_window(&self) -> Vec<time::Duration> {
+        match self {
+            Inner::Http { endp, .. } => endp.to_elapsed_window(),
+            #[cfg(feature = "grpc")]
+            Inner::Grpc { endp, .. } => endp.to_elapsed_window(),
+        }
+    }
+
+    fn set_elapsed_window(&mut self, window: Vec<time::Duration>) {
+        match self {
+            Inner::Http { endp, .. } => endp.set_elapsed_window(window),
+            #[cfg(feature = "grpc")]
+            Inner::Grpc { endp, .. } => endp.set_elapsed_window(window),
+        }
+    }
+
+    fn to_config(&self) -> &EndpointConfig {
+        match self {
+            Inner::Http { config, .. } => config,
+            #[cfg(feature = "grpc")]
+            Inner::Grpc { config, .. } => config,
+        }
+    }
+
+    fn to_base_url(&self) -> String {
+        match self {
+            Inner::Http { endp, .. } => endp.to_base_url(),
+            #[cfg(feature = "grpc")]
+            Inner::Grpc { endp, .. } => endp.to_base_url(),
         }
     }
 }
+
+// delay before the n-th retry, zero based, doubles for every retry with a
+// jitter of up to half the base delay so that clients don't retry in lock
+// step.
+fn retry_backoff(base: time::Duration, n: usize) -> time::Duration {
+    let max = time::Duration::from_secs(u64::MAX);
+    let delay = base.checked_mul(1 << cmp::min(n, 16)).unwrap_or(max);
+    let jitter = (base / 1000) * ((pseudo_random() % 500) as u32);
+    delay.checked_add(jitter).unwrap_or(max)
+}
+
+// order `endpoints` by weighted random draws, without replacement, weight
+// being the inverse of endpoint's latency. `seed` is the source of
+// randomness.
+fn weighted_order(endpoints: &mut [(usize, time::Duration)], mut seed: u64) {
+    let weight = |elapsed: &time::Duration| {
+        let micros = cmp::max(elapsed.as_micros(), 1);
+        cmp::max(1_000_000_000 / micros, 1)
+    };
+
+    for k in 0..endpoints.len() {
+        let total: u128 = endpoints[k..].iter().map(|(_, e)| weight(e)).sum();
+        let mut draw = u128::from(seed) % total;
+        let mut j = k;
+        for (off, (_, elapsed)) in endpoints[k..].iter().enumerate() {
+            match draw.checked_sub(weight(elapsed)) {
+                Some(rest) => draw = rest,
+                None => {
+                    j = k + off;
+                    break;
+                }
+            }
+        }
+        endpoints.swap(k, j);
+        // xorshift, for the next draw.
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+    }
+}
+
+// cheap, non-cryptographic, randomness from the system clock, good enough
+// to spread load and jitter retries.
+fn pseudo_random() -> u64 {
+    let nanos = match time::SystemTime::now().duration_since(time::UNIX_EPOCH) {
+        Ok(d) => d.as_nanos() as u64,
+        Err(_) => 0,
+    };
+    nanos.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1
+}
+
+#[cfg(test)]
+#[path = "endpoints_test.rs"]
+pub(crate) mod endpoints_test;
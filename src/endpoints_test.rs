@@ -0,0 +1,2209 @@
+use sha2::{Digest, Sha256};
+
+use super::*;
+
+#[test]
+fn test_validate_latest_time() {
+    let info = Info {
+        period: time::Duration::from_secs(30),
+        genesis_time: time::UNIX_EPOCH + time::Duration::from_secs(1595431050),
+        ..Info::default()
+    };
+    let mut r = Random {
+        round: 1,
+        randomness: vec![],
+        signature: vec![],
+        previous_signature: vec![],
+    };
+
+    // round 101 is emitted 3000 seconds after genesis.
+    let now = info.genesis_time + time::Duration::from_secs(3000);
+    for round in [1, 100, 101, 102].iter() {
+        r.round = *round;
+        assert!(Endpoints::validate_latest_time(&info, &r, now).is_ok());
+    }
+    for round in [103, 1000, u128::MAX].iter() {
+        r.round = *round;
+        assert!(Endpoints::validate_latest_time(&info, &r, now).is_err());
+    }
+
+    // absurd period is refused, not a panic.
+    let info = Info {
+        period: time::Duration::MAX,
+        ..info
+    };
+    match Endpoints::validate_latest_time(&info, &r, now) {
+        Err(Error::NotSecure(_, msg, _)) => assert!(msg.contains("period"), "{}", msg),
+        res => panic!("unexpected {:?}", res),
+    }
+}
+
+#[test]
+fn test_merge_check_point() {
+    let r = |round: u128| Random {
+        round,
+        randomness: vec![],
+        signature: vec![],
+        previous_signature: vec![],
+    };
+
+    assert_eq!(Endpoints::merge_check_point(None, None), None);
+    assert_eq!(Endpoints::merge_check_point(None, Some(r(5))), Some(r(5)));
+    assert_eq!(Endpoints::merge_check_point(Some(r(5)), None), Some(r(5)));
+    assert_eq!(
+        Endpoints::merge_check_point(Some(r(5)), Some(r(5))),
+        Some(r(5))
+    );
+    assert_eq!(
+        Endpoints::merge_check_point(Some(r(5)), Some(r(9))),
+        Some(r(9))
+    );
+    assert_eq!(
+        Endpoints::merge_check_point(Some(r(5)), Some(r(2))),
+        Some(r(5))
+    );
+}
+
+#[test]
+fn test_report_failure() {
+    let mut endpoints = Endpoints::from_config("test", Config::default());
+    let endp = Inner::Http {
+        name: "test".to_string(),
+        endp: Http::new_drand_api(),
+        config: EndpointConfig::default(),
+    };
+
+    // no subscriber, failures are dropped.
+    let err = Error::NotSecure("p".to_string(), "bad signature".to_string(), None);
+    endpoints.report_failure(Some(10), &endp, &err);
+
+    let rx = endpoints.subscribe_failures();
+    endpoints.report_failure(Some(10), &endp, &err);
+    let err = Error::IOError("p".to_string(), "timeout".to_string(), None);
+    endpoints.report_failure(Some(11), &endp, &err);
+    let err = Error::NotSecure("p".to_string(), "mismatch chain".to_string(), None);
+    endpoints.report_failure(None, &endp, &err);
+
+    let failures: Vec<VerifyFailure> = rx.try_iter().collect();
+    assert_eq!(failures.len(), 2);
+    assert_eq!(failures[0].round, Some(10));
+    assert_eq!(failures[0].host, "https://api.drand.sh");
+    assert_eq!(failures[0].error.to_string(), "p NotSecure: bad signature");
+    assert_eq!(failures[1].round, None);
+}
+
+#[test]
+fn test_endpoint_max_conns() {
+    let mut config = Config::default();
+    config.set_max_conns(8);
+    let mut endpoints = Endpoints::from_config("test", config);
+    endpoints.add_endpoint(Endpoint::HttpDrandApi).unwrap();
+    let mut econfig = EndpointConfig::default();
+    econfig.set_max_conns(2);
+    endpoints
+        .add_endpoint_with(Endpoint::HttpCloudflare, econfig)
+        .unwrap();
+
+    assert_eq!(
+        endpoints.to_max_conns(),
+        vec![
+            ("https://api.drand.sh".to_string(), 8),
+            ("https://drand.cloudflare.com".to_string(), 2),
+        ]
+    );
+}
+
+#[test]
+fn test_https_only() {
+    let local = "http://127.0.0.1:8080";
+
+    // beacon verification doesn't require TLS.
+    let mut config = Config::default();
+    config.set_secure(true);
+    let mut endpoints = Endpoints::from_config("test", config);
+    let endp = Endpoint::HttpCustom {
+        base_url: local.to_string(),
+    };
+    endpoints.add_endpoint(endp).unwrap();
+    assert_eq!(endpoints.to_endpoints(), vec![local.to_string()]);
+
+    let mut config = Config::default();
+    config.set_https_only(true);
+    let mut endpoints = Endpoints::from_config("test", config);
+    let endp = Endpoint::HttpCustom {
+        base_url: local.to_string(),
+    };
+    assert!(matches!(
+        endpoints.add_endpoint(endp).map(|_| ()),
+        Err(Error::Invalid(_, _, _))
+    ));
+    endpoints.add_endpoint(Endpoint::HttpDrandApi).unwrap();
+    assert_eq!(endpoints.to_endpoints(), vec!["https://api.drand.sh"]);
+}
+
+#[test]
+fn test_endpoint_latencies() {
+    let mut endpoints = Endpoints::from_config("test", Config::default());
+    endpoints.add_endpoint(Endpoint::HttpDrandApi).unwrap();
+    endpoints.add_endpoint(Endpoint::HttpCloudflare).unwrap();
+
+    let ms = time::Duration::from_millis;
+    endpoints.endpoints[0].set_elapsed_window(vec![ms(10), ms(30)]);
+    assert_eq!(
+        endpoints.endpoint_latencies(),
+        vec![
+            ("https://api.drand.sh".to_string(), ms(20)),
+            (
+                "https://drand.cloudflare.com".to_string(),
+                time::Duration::from_secs(u64::MAX)
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_verify_chain() {
+    use crate::SignatureVerifier;
+    use futures::stream::StreamExt;
+
+    // rejects round-5.
+    struct Mock;
+
+    impl SignatureVerifier for Mock {
+        fn verify(&self, _: &[u8], round: u64, psign: &[u8], sign: &[u8]) -> Result<bool> {
+            let ok = sign == [round as u8] && psign == [round as u8 - 1];
+            Ok(ok && round != 5)
+        }
+    }
+
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let mut config = Config::default();
+    config.set_max_conns(1).set_verifier(Box::new(Mock));
+    let mut endpoints = Endpoints::from_config("test", config);
+    assert!(matches!(
+        endpoints.verify_chain(0, 10).map(|_| ()),
+        Err(Error::Invalid(_, _, _))
+    ));
+    assert!(matches!(
+        endpoints.verify_chain(1, 10).map(|_| ()),
+        Err(Error::Invalid(_, _, _))
+    ));
+
+    let (base_url, server) = mock_server(0, 6);
+    endpoints
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+    endpoints.endpoints[0].set_elapsed_window(vec![time::Duration::from_millis(10)]);
+
+    let rounds = endpoints.verify_chain(6, 9).unwrap();
+    let rounds: Vec<u128> = rt
+        .block_on(rounds.collect::<Vec<Result<Random>>>())
+        .into_iter()
+        .map(|r| r.unwrap().round)
+        .collect();
+    assert_eq!(rounds, vec![6, 7, 8]);
+
+    // stops at the first break.
+    let rounds = endpoints.verify_chain(3, 9).unwrap();
+    let rounds = rt.block_on(rounds.collect::<Vec<Result<Random>>>());
+    assert_eq!(rounds.len(), 3);
+    assert_eq!(rounds[1].as_ref().unwrap().round, 4);
+    assert!(matches!(rounds[2], Err(Error::NotSecure(_, _, _))));
+    server.join().unwrap();
+}
+
+#[test]
+fn test_for_each_round() {
+    use crate::SignatureVerifier;
+    use futures::stream::StreamExt;
+
+    // rejects round-5.
+    struct Mock;
+
+    impl SignatureVerifier for Mock {
+        fn verify(&self, _: &[u8], round: u64, psign: &[u8], sign: &[u8]) -> Result<bool> {
+            let ok = sign == [round as u8] && psign == [round as u8 - 1];
+            Ok(ok && round != 5)
+        }
+    }
+
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let mut config = Config::default();
+    config.set_max_conns(1).set_verifier(Box::new(Mock));
+    let mut endpoints = Endpoints::from_config("test", config);
+    assert!(matches!(
+        endpoints.for_each_round(5, 4).map(|_| ()),
+        Err(Error::Invalid(_, _, _))
+    ));
+
+    let (base_url, server) = mock_server(0, 9);
+    endpoints
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+    endpoints.endpoints[0].set_elapsed_window(vec![time::Duration::from_millis(10)]);
+
+    // rounds are taken as is, when not secure.
+    let rounds = endpoints.for_each_round(3, 9).unwrap();
+    let rounds: Vec<u128> = rt
+        .block_on(rounds.collect::<Vec<Result<Random>>>())
+        .into_iter()
+        .map(|r| r.unwrap().round)
+        .collect();
+    assert_eq!(rounds, vec![3, 4, 5, 6, 7, 8]);
+
+    endpoints.state.secure = true;
+    let rounds = endpoints.for_each_round(3, 9).unwrap();
+    let rounds = rt.block_on(rounds.collect::<Vec<Result<Random>>>());
+    assert_eq!(rounds.len(), 3);
+    assert!(matches!(rounds[2], Err(Error::NotSecure(_, _, _))));
+    server.join().unwrap();
+}
+
+#[test]
+fn test_get_range_max_conns() {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    // server holds on to requests till no new connection shows up for a
+    // while, and reports the largest batch of concurrent requests.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.set_nonblocking(true).unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let server = thread::spawn(move || {
+        let (mut served, mut peak) = (0, 0);
+        while served < 9 {
+            let mut conns = vec![];
+            let mut idle = time::Instant::now();
+            while conns.is_empty() || idle.elapsed() < time::Duration::from_millis(200) {
+                match listener.accept() {
+                    Ok((conn, _)) => {
+                        conns.push(conn);
+                        idle = time::Instant::now();
+                    }
+                    Err(_) => thread::sleep(time::Duration::from_millis(10)),
+                }
+            }
+            peak = std::cmp::max(peak, conns.len());
+            for mut conn in conns.into_iter() {
+                conn.set_nonblocking(false).unwrap();
+                let mut buf = [0; 1024];
+                let m = conn.read(&mut buf).unwrap();
+                let req = String::from_utf8_lossy(&buf[..m]).to_string();
+                let path = req.split_whitespace().nth(1).unwrap();
+                let round: u8 = path.trim_start_matches("/public/").parse().unwrap();
+                let body = format!(
+                    r#"{{"round":{},"randomness":"aa","signature":"{}","previous_signature":"{}"}}"#,
+                    round,
+                    hex::encode([round]),
+                    hex::encode([round - 1]),
+                );
+                let resp = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                conn.write_all(resp.as_bytes()).unwrap();
+                served += 1;
+            }
+        }
+        peak
+    });
+
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let mut config = Config::default();
+    config.set_secure(false).set_max_conns(3);
+    let mut endpoints = Endpoints::from_config("test", config);
+    endpoints
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+    endpoints.endpoints[0].set_elapsed_window(vec![time::Duration::from_millis(10)]);
+
+    let rounds = rt.block_on(endpoints.get_range(1, 10)).unwrap();
+    assert_eq!(rounds.len(), 9);
+    assert_eq!(server.join().unwrap(), 3);
+}
+
+#[test]
+fn test_endpoint_overrides() {
+    let secs = time::Duration::from_secs;
+
+    let state = State::default();
+    let s = state.clone().with_overrides(&EndpointConfig::default());
+    assert_eq!(s.request_timeout, None);
+    assert_eq!(s.connect_timeout, None);
+    assert_eq!(s.max_conns, MAX_CONNS);
+
+    let mut config = EndpointConfig::default();
+    config.set_request_timeout(secs(2)).set_max_conns(16);
+    let s = state.clone().with_overrides(&config);
+    assert_eq!(s.request_timeout, Some(secs(2)));
+    assert_eq!(s.connect_timeout, None);
+    assert_eq!(s.max_conns, 16);
+
+    // per-endpoint value overrides the client-wide value.
+    let state = State {
+        request_timeout: Some(secs(10)),
+        connect_timeout: Some(secs(3)),
+        ..State::default()
+    };
+    let s = state.with_overrides(&config);
+    assert_eq!(s.request_timeout, Some(secs(2)));
+    assert_eq!(s.connect_timeout, Some(secs(3)));
+}
+
+#[test]
+fn test_refresh_info() {
+    use crate::{Scheme, SignatureVerifier};
+
+    // signatures are [round] under key "aa", and [round ^ 0xf0] under
+    // the forged key "ee".
+    struct Verifier;
+
+    impl SignatureVerifier for Verifier {
+        fn verify(&self, pk: &[u8], round: u64, _: &[u8], sign: &[u8]) -> Result<bool> {
+            let ok = match pk {
+                [0xaa] => sign == [round as u8],
+                [0xee] => sign == [round as u8 ^ 0xf0],
+                _ => false,
+            };
+            Ok(ok)
+        }
+    }
+
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let info = Info {
+        public_key: vec![0xaa],
+        period: time::Duration::from_secs(30),
+        genesis_time: time::UNIX_EPOCH + time::Duration::from_secs(1595431050),
+        hash: vec![0xbb],
+        group_hash: vec![0xcc],
+        scheme: Scheme::Unchained,
+        ..Info::default()
+    };
+    let check_point = Random {
+        round: 5,
+        randomness: Sha256::digest(&[5]).to_vec(),
+        signature: vec![5],
+        previous_signature: vec![4],
+    };
+    let new_endpoints = |urls: Vec<String>| {
+        let mut config = Config::default();
+        config
+            .set_determinism(false)
+            .set_secure(true)
+            .set_cross_validate(false)
+            .set_verifier(Box::new(Verifier));
+        let mut endpoints = Endpoints::from_config("test", config);
+        for base_url in urls.into_iter() {
+            endpoints
+                .add_endpoint(Endpoint::HttpCustom { base_url })
+                .unwrap();
+        }
+        endpoints.endpoints[0].set_elapsed_window(vec![time::Duration::from_millis(10)]);
+        endpoints.state.info = info.clone();
+        endpoints.state.check_point = Some(check_point.clone());
+        endpoints
+    };
+
+    // stale hash-info is refreshed, and the round fetched again.
+    let (base_url, server) = mock_server(0, 4);
+    let mut endpoints = new_endpoints(vec![base_url]);
+    endpoints.state.info.public_key = vec![0xc0];
+    assert_eq!(rt.block_on(endpoints.get(Some(6))).unwrap().round, 6);
+    server.join().unwrap();
+    assert_eq!(endpoints.to_info().public_key, vec![0xaa]);
+
+    // endpoint forging a key under the chain-hash can't verify the
+    // check_point, hash-info is left as is.
+    let (base_url, server) = mock_server_with(Mock {
+        serves: 3,
+        salt: 0xf0,
+        public_key: "ee",
+        ..Mock::default()
+    });
+    let mut endpoints = new_endpoints(vec![base_url]);
+    match rt.block_on(endpoints.get(Some(6))) {
+        Err(Error::NotSecure(_, msg, _)) => assert!(msg.contains("check_point"), "{}", msg),
+        res => panic!("unexpected {:?}", res),
+    }
+    server.join().unwrap();
+    assert_eq!(endpoints.to_info().public_key, vec![0xaa]);
+
+    // refreshed hash-info is cross-validated, even when disabled.
+    let (url1, server1) = mock_server_with(Mock {
+        serves: 3,
+        salt: 0xf0,
+        public_key: "ee",
+        ..Mock::default()
+    });
+    let (url2, server2) = mock_server(0, 2);
+    let mut endpoints = new_endpoints(vec![url1, url2]);
+    match rt.block_on(endpoints.get(Some(6))) {
+        Err(Error::NotSecure(_, msg, _)) => assert!(msg.contains("public-key"), "{}", msg),
+        res => panic!("unexpected {:?}", res),
+    }
+    server1.join().unwrap();
+    server2.join().unwrap();
+    assert_eq!(endpoints.to_info().public_key, vec![0xaa]);
+}
+
+#[test]
+fn test_get_wrong_round() {
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let mut config = Config::default();
+    config.set_determinism(false).set_secure(false);
+    let mut endpoints = Endpoints::from_config("test", config);
+
+    // endpoint answers with the round after the one asked for.
+    let (base_url, server) = mock_server_with(Mock {
+        serves: 1,
+        ahead: 1,
+        ..Mock::default()
+    });
+    endpoints
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+    endpoints.endpoints[0].set_elapsed_window(vec![time::Duration::from_millis(10)]);
+
+    let err = rt.block_on(endpoints.get(Some(5))).unwrap_err();
+    assert!(Endpoints::is_not_secure(&err), "{}", err);
+    assert!(err.to_string().contains("asked round 5 got 6"), "{}", err);
+    server.join().unwrap();
+    assert!(endpoints.to_check_point().is_none());
+}
+
+#[test]
+fn test_get_unchained() {
+    use crate::{Scheme, SignatureVerifier};
+
+    struct Verifier;
+
+    impl SignatureVerifier for Verifier {
+        fn verify(&self, _: &[u8], round: u64, psign: &[u8], sign: &[u8]) -> Result<bool> {
+            Ok(psign.is_empty() && sign == [round as u8])
+        }
+    }
+
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let mut config = Config::default();
+    config
+        .set_determinism(false)
+        .set_secure(true)
+        .set_verifier(Box::new(Verifier));
+    let mut endpoints = Endpoints::from_config("test", config);
+
+    // only the round asked for is fetched, rounds since the check_point
+    // are not walked.
+    let (base_url, server) = mock_server(0, 1);
+    endpoints
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+    endpoints.endpoints[0].set_elapsed_window(vec![time::Duration::from_millis(10)]);
+    endpoints.state.info = Info {
+        public_key: vec![0xaa],
+        period: time::Duration::from_secs(30),
+        genesis_time: time::UNIX_EPOCH,
+        scheme: Scheme::Unchained,
+        ..Info::default()
+    };
+    endpoints.state.check_point = Some(Random {
+        round: 3,
+        randomness: Sha256::digest(&[3]).to_vec(),
+        signature: vec![3],
+        previous_signature: vec![],
+    });
+
+    let r = rt.block_on(endpoints.get(Some(9))).unwrap();
+    assert_eq!(r.round, 9);
+    assert_eq!(server.join().unwrap(), vec!["/public/9"]);
+    assert_eq!(endpoints.to_check_point().unwrap().round, 9);
+}
+
+#[test]
+fn test_get_over_budget() {
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+
+    // a single response larger than the budget is refused, whether or not
+    // it advertises its length upfront.
+    for (content_length, remaining) in [(true, 64), (false, 0)].iter() {
+        let mut config = Config::default();
+        config
+            .set_determinism(false)
+            .set_secure(false)
+            .set_byte_budget(64);
+        let mut endpoints = Endpoints::from_config("test", config);
+        let (base_url, server) = mock_server_with(Mock {
+            serves: 1,
+            content_length: *content_length,
+            ..Mock::default()
+        });
+        endpoints
+            .add_endpoint(Endpoint::HttpCustom { base_url })
+            .unwrap();
+        endpoints.endpoints[0].set_elapsed_window(vec![time::Duration::from_millis(10)]);
+
+        match rt.block_on(endpoints.get(Some(5))) {
+            Err(err) => assert!(
+                err.to_string().contains("byte budget exhausted by"),
+                "{}",
+                err
+            ),
+            Ok(r) => panic!("unexpected round {}", r.round),
+        }
+        server.join().unwrap();
+        assert_eq!(endpoints.state.budget.to_remaining(), Some(*remaining));
+    }
+}
+
+#[test]
+fn test_get_exhausted() {
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+
+    let mut config = Config::default();
+    config.set_byte_budget(0);
+    let mut endpoints = Endpoints::from_config("test", config);
+    for _ in 0..3 {
+        let mut endp = Http::new_drand_api();
+        endp.set_elapsed_window(vec![time::Duration::from_millis(10)]);
+        endpoints.endpoints.push(Inner::Http {
+            name: "test".to_string(),
+            endp,
+            config: EndpointConfig::default(),
+        });
+    }
+
+    match rt.block_on(endpoints.get(Some(1))) {
+        Err(Error::Exhausted(_, errs)) => {
+            assert_eq!(errs.len(), 3);
+            for (host, err) in errs.iter() {
+                assert_eq!(host, "https://api.drand.sh");
+                assert!(err.to_string().contains("byte budget exhausted"), "{}", err);
+            }
+        }
+        res => panic!("unexpected {:?}", res),
+    }
+    assert!(!Endpoints::is_not_secure(&Error::IOError(
+        "".to_string(),
+        "".to_string(),
+        None
+    )));
+
+    let err = Error::Exhausted(
+        "".to_string(),
+        vec![
+            (
+                "a".to_string(),
+                Error::IOError("".to_string(), "".to_string(), None),
+            ),
+            (
+                "b".to_string(),
+                Error::NotSecure("".to_string(), "".to_string(), None),
+            ),
+        ],
+    );
+    assert!(Endpoints::is_not_secure(&err));
+}
+
+#[test]
+fn test_current_round() {
+    let mut endpoints = Endpoints::from_config("test", Config::default());
+    assert!(endpoints.current_round().is_err());
+
+    let hour = time::Duration::from_secs(3600);
+    endpoints.state.info.period = time::Duration::from_secs(30);
+    endpoints.state.info.genesis_time = time::SystemTime::now() - hour;
+    let round = endpoints.current_round().unwrap();
+    assert!(round == 121 || round == 122, "{}", round);
+}
+
+#[test]
+fn test_watch_unbooted() {
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let mut endpoints = Endpoints::from_config("test", Config::default());
+
+    match rt.block_on(watch_round(&mut endpoints, 1)) {
+        Err(Error::Invalid(_, _, _)) => (),
+        res => panic!("unexpected {:?}", res),
+    }
+}
+
+#[test]
+fn test_boot_collect() {
+    let mut endpoints = Endpoints::from_config("test", Config::default());
+    endpoints.add_endpoint(Endpoint::HttpDrandApi).unwrap();
+    endpoints.add_endpoint(Endpoint::HttpDrandApi2).unwrap();
+    endpoints.add_endpoint(Endpoint::HttpDrandApi3).unwrap();
+    let tail = endpoints.endpoints[1..].to_vec();
+    let err = |e: fn(String, String, Option<crate::Source>) -> Error| {
+        e("test".to_string(), "test".to_string(), None)
+    };
+
+    // disagreeing endpoint fails the boot.
+    let results = vec![Ok(tail[0].clone()), Err(err(Error::NotSecure))];
+    assert!(matches!(
+        endpoints.boot_collect(0, results),
+        Err(Error::NotSecure(_, _, _))
+    ));
+    assert_eq!(endpoints.to_endpoints().len(), 3);
+
+    // unreachable endpoint is dropped, reference endpoint goes first.
+    let results = vec![Err(err(Error::IOError)), Ok(tail[1].clone())];
+    endpoints.boot_collect(1, results).unwrap();
+    assert_eq!(
+        endpoints.to_endpoints(),
+        vec!["https://api2.drand.sh", "https://api3.drand.sh"]
+    );
+}
+
+#[test]
+fn test_request_timeout() {
+    let secs = time::Duration::from_secs;
+
+    let state: State = Config::default().into();
+    assert_eq!(state.request_timeout, Some(secs(10)));
+
+    let mut config = Config::default();
+    config.set_timeout(secs(3));
+    let state: State = config.into();
+    assert_eq!(state.request_timeout, Some(secs(3)));
+
+    let mut endp_config = EndpointConfig::default();
+    let s = state.clone().with_overrides(&endp_config);
+    assert_eq!(s.request_timeout, Some(secs(3)));
+    endp_config.set_request_timeout(secs(1));
+    let s = state.with_overrides(&endp_config);
+    assert_eq!(s.request_timeout, Some(secs(1)));
+}
+
+// mock server, drops the first `drops` connections and then serves the
+// next `serves` requests for `/info` and `/public/<round>`. Round-n is
+// signed as [n], with randomness as SHA-256 of [n], and chains from
+// [n-1], rounds beyond 100 are not published yet. Server thread returns
+// the paths requested, in the order they were served.
+pub(crate) fn mock_server(
+    drops: usize,
+    serves: usize,
+) -> (String, std::thread::JoinHandle<Vec<String>>) {
+    mock_server_with(Mock {
+        drops,
+        serves,
+        ..Mock::default()
+    })
+}
+
+// same as mock_server, but signatures are xor-ed with `salt`, to mock a
+// mirror that disagrees with others.
+fn mock_server_salted(
+    drops: usize,
+    serves: usize,
+    salt: u8,
+) -> (String, std::thread::JoinHandle<Vec<String>>) {
+    mock_server_with(Mock {
+        drops,
+        serves,
+        salt,
+        ..Mock::default()
+    })
+}
+
+// behaviour of a mock server, refer mock_server_with.
+struct Mock {
+    drops: usize,
+    serves: usize,
+    salt: u8,
+    // round served for `/public/latest`.
+    latest: u8,
+    // (path, status) to respond with, in place of the usual response, the
+    // first time `path` is requested, an entry is consumed once matched.
+    statuses: Vec<(&'static str, &'static str)>,
+    // send Content-Length header, else the body is delimited by closing
+    // the connection.
+    content_length: bool,
+    // hash-info carries the chain-hash addressed in the path, to mock a
+    // node serving several chains, else hash is always "bb".
+    chains: bool,
+    // public key served in hash-info.
+    public_key: &'static str,
+    // rounds served this much ahead of the round asked for.
+    ahead: u8,
+}
+
+impl Default for Mock {
+    fn default() -> Self {
+        Mock {
+            drops: 0,
+            serves: 0,
+            salt: 0,
+            latest: 5,
+            statuses: vec![],
+            content_length: true,
+            chains: false,
+            public_key: "aa",
+            ahead: 0,
+        }
+    }
+}
+
+fn mock_server_with(mut mock: Mock) -> (String, std::thread::JoinHandle<Vec<String>>) {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let handle = thread::spawn(move || {
+        let mut paths = vec![];
+        for n in 0..(mock.drops + mock.serves) {
+            let (mut conn, _) = listener.accept().unwrap();
+            let mut buf = [0; 1024];
+            let m = conn.read(&mut buf).unwrap();
+            if n < mock.drops {
+                continue;
+            }
+            let req = String::from_utf8_lossy(&buf[..m]).to_string();
+            let path = req.split_whitespace().nth(1).unwrap();
+            // absolute-form, when the request comes through a proxy.
+            let path = match path.strip_prefix("http://") {
+                Some(path) => &path[path.find('/').unwrap()..],
+                None => path,
+            };
+            paths.push(path.to_string());
+            // chain addressed by its hash, `/{chain-hash}/info`.
+            let (chain, path) = match path[1..].find('/') {
+                Some(i) if !path.starts_with("/public/") => (&path[1..i + 1], &path[i + 1..]),
+                _ => ("bb", path),
+            };
+            let (status, body) = match path {
+                "/info" => {
+                    let hash = if mock.chains { chain } else { "bb" };
+                    let body = format!(
+                        r#"{{"public_key":"{}","period":30,"genesis_time":1595431050,"hash":"{}","groupHash":"cc"}}"#,
+                        mock.public_key, hash
+                    );
+                    ("200 OK", body)
+                }
+                path => {
+                    let round: u8 = match path.trim_start_matches("/public/") {
+                        "latest" => mock.latest,
+                        round => round.parse::<u8>().unwrap() + mock.ahead,
+                    };
+                    let salt = mock.salt;
+                    let body = format!(
+                        r#"{{"round":{},"randomness":"{}","signature":"{}","previous_signature":"{}"}}"#,
+                        round,
+                        hex::encode(Sha256::digest(&[round])),
+                        hex::encode([round ^ salt]),
+                        hex::encode([round.wrapping_sub(1) ^ salt]),
+                    );
+                    let status = if round > 100 {
+                        "404 Not Found"
+                    } else {
+                        "200 OK"
+                    };
+                    (status, body)
+                }
+            };
+            let status = match mock.statuses.iter().position(|(p, _)| *p == path) {
+                Some(i) => mock.statuses.remove(i).1,
+                None => status,
+            };
+            let length = match mock.content_length {
+                true => format!("Content-Length: {}\r\n", body.len()),
+                false => "".to_string(),
+            };
+            let resp = format!(
+                "HTTP/1.1 {}\r\n{}Connection: close\r\n\r\n{}",
+                status, length, body
+            );
+            conn.write_all(resp.as_bytes()).unwrap();
+        }
+        paths
+    });
+    (base_url, handle)
+}
+
+// wait for `round` and fetch it the way a client's watch does, refer
+// WatchRetry.
+async fn watch_round(endpoints: &mut Endpoints, round: u128) -> Result<Random> {
+    let mut retry = endpoints.to_watch_retry(round)?;
+    retry.wait().await?;
+    loop {
+        match endpoints.get(Some(round)).await {
+            Ok(r) => break Ok(r),
+            Err(err) => {
+                if !retry.retry(&err).await {
+                    break Err(err);
+                }
+            }
+        }
+    }
+}
+
+// url of an endpoint that is down, nothing listens on a just closed port.
+fn dead_url() -> String {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    format!("http://{}", listener.local_addr().unwrap())
+}
+
+// clock whose delays complete right away, advancing `now`. The next delay
+// is stepped back by `back`, as by an NTP correction, once.
+struct MockClock {
+    now: Arc<std::sync::Mutex<time::SystemTime>>,
+    back: Arc<std::sync::Mutex<time::Duration>>,
+}
+
+impl MockClock {
+    fn new(now: &Arc<std::sync::Mutex<time::SystemTime>>) -> MockClock {
+        MockClock {
+            now: Arc::clone(now),
+            back: Arc::new(std::sync::Mutex::new(time::Duration::default())),
+        }
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> time::SystemTime {
+        *self.now.lock().unwrap()
+    }
+
+    fn delay(
+        &self,
+        dur: time::Duration,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send>> {
+        let back = std::mem::take(&mut *self.back.lock().unwrap());
+        let mut now = self.now.lock().unwrap();
+        *now = *now + dur - back;
+        Box::pin(futures::future::ready(()))
+    }
+}
+
+// endpoint that accepts connections but never responds, for as long as
+// the returned listener is alive.
+fn stuck_server() -> (String, std::net::TcpListener) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    (
+        format!("http://{}", listener.local_addr().unwrap()),
+        listener,
+    )
+}
+
+#[test]
+fn test_get_retry() {
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let new_endpoint = |base_url: &str| Inner::Http {
+        name: "test".to_string(),
+        endp: Http::new_custom(base_url).unwrap(),
+        config: EndpointConfig::default(),
+    };
+
+    // fails twice and then succeeds.
+    let mut config = Config::default();
+    config.set_retry(2, time::Duration::from_millis(10));
+    let state: State = config.into();
+    let (base_url, server) = mock_server(2, 1);
+    let mut endp = new_endpoint(&base_url);
+    let (_, r) = rt.block_on(endp.get(state.clone(), Some(5), None)).unwrap();
+    assert_eq!(r.round, 5);
+    assert_eq!(r.signature, vec![5]);
+    server.join().unwrap();
+
+    // without retries the first failure is returned.
+    let (base_url, server) = mock_server(1, 1);
+    let mut endp = new_endpoint(&base_url);
+    let state = State {
+        max_retries: 0,
+        ..state
+    };
+    match rt.block_on(endp.get(state.clone(), Some(5), None)) {
+        Err(Error::IOError(_, _, _)) => (),
+        Err(err) => panic!("unexpected {}", err),
+        Ok((_, r)) => panic!("unexpected round {}", r.round),
+    }
+    // let the server finish.
+    rt.block_on(endp.get(state, Some(5), None)).unwrap();
+    server.join().unwrap();
+}
+
+#[test]
+fn test_retry_clock() {
+    use std::sync::Mutex;
+
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+
+    // back-off waits on the configured clock, not on tokio's timer.
+    let hour = time::Duration::from_secs(3600);
+    let now = Arc::new(Mutex::new(time::UNIX_EPOCH));
+    let mut config = Config::default();
+    config
+        .set_retry(2, hour)
+        .set_clock(Box::new(MockClock::new(&now)));
+    let (base_url, server) = mock_server(2, 1);
+    let mut endp = Inner::Http {
+        name: "test".to_string(),
+        endp: Http::new_custom(&base_url).unwrap(),
+        config: EndpointConfig::default(),
+    };
+    let (_, r) = rt.block_on(endp.get(config.into(), Some(5), None)).unwrap();
+    assert_eq!(r.round, 5);
+    server.join().unwrap();
+
+    let waited = now
+        .lock()
+        .unwrap()
+        .duration_since(time::UNIX_EPOCH)
+        .unwrap();
+    assert!(waited >= hour * 3, "{:?}", waited);
+}
+
+#[test]
+fn test_retry_backoff() {
+    let base = time::Duration::from_millis(100);
+    for n in 0..4 {
+        let delay = retry_backoff(base, n);
+        let min = base * (1 << n);
+        assert!(delay >= min && delay < min + base / 2, "{} {:?}", n, delay);
+    }
+    assert!(retry_backoff(base, usize::MAX) > base);
+
+    let state = State::default();
+    let err = |e: fn(String, String, Option<crate::Source>) -> Error| {
+        e("test".to_string(), "test".to_string(), None)
+    };
+    assert!(Inner::is_retryable(&state, &err(Error::IOError)));
+    assert!(!Inner::is_retryable(&state, &err(Error::NotSecure)));
+    assert!(!Inner::is_retryable(&state, &err(Error::JsonParse)));
+}
+
+#[test]
+fn test_get_range() {
+    use crate::SignatureVerifier;
+
+    struct Mock;
+
+    impl SignatureVerifier for Mock {
+        fn verify(&self, _: &[u8], round: u64, psign: &[u8], sign: &[u8]) -> Result<bool> {
+            Ok(sign == [round as u8] && psign == [round as u8 - 1])
+        }
+    }
+
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let mut config = Config::default();
+    config.set_secure(true).set_verifier(Box::new(Mock));
+    let mut endpoints = Endpoints::from_config("test", config);
+    assert!(matches!(
+        rt.block_on(endpoints.get_range(0, 10)),
+        Err(Error::Invalid(_, _, _))
+    ));
+    assert!(matches!(
+        rt.block_on(endpoints.get_range(10, 5)),
+        Err(Error::Invalid(_, _, _))
+    ));
+
+    let (base_url, server) = mock_server(0, 10);
+    endpoints
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+    endpoints.endpoints[0].set_elapsed_window(vec![time::Duration::from_millis(10)]);
+
+    let rounds = rt.block_on(endpoints.get_range(10, 20)).unwrap();
+    let rounds: Vec<u128> = rounds.into_iter().map(|r| r.round).collect();
+    assert_eq!(rounds, (10..20).collect::<Vec<u128>>());
+    assert!(rt.block_on(endpoints.get_range(7, 7)).unwrap().is_empty());
+    assert!(endpoints.state.check_point.is_none());
+    server.join().unwrap();
+}
+
+#[test]
+fn test_get_not_published() {
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+
+    let mut config = Config::default();
+    config.set_retry(2, time::Duration::from_millis(10));
+    let (base_url, server) = mock_server(0, 1);
+    let mut endp = Inner::Http {
+        name: "test".to_string(),
+        endp: Http::new_custom(&base_url).unwrap(),
+        config: EndpointConfig::default(),
+    };
+
+    // 404 is returned as is, without retrying.
+    match rt.block_on(endp.get(config.into(), Some(200), None)) {
+        Err(err @ Error::Http { .. }) if err.is_unpublished() => {
+            let url = format!("{}/public/200", base_url);
+            assert_eq!(err, Error::Http { status: 404, url });
+        }
+        Err(err) => panic!("unexpected {}", err),
+        Ok((_, r)) => panic!("unexpected round {}", r.round),
+    }
+    server.join().unwrap();
+
+    let state = State::default();
+    let err = |status| Error::Http {
+        status,
+        url: "test".to_string(),
+    };
+    assert!(Inner::is_retryable(&state, &err(503)));
+    assert!(Inner::is_retryable(&state, &err(429)));
+    assert!(!Inner::is_retryable(&state, &err(404)));
+}
+
+#[test]
+fn test_round_unavailable() {
+    // round-7 lands on the endpoint after two requests.
+    let (base_url, server) = mock_server_with(Mock {
+        serves: 3,
+        statuses: vec![
+            ("/public/7", "404 Not Found"),
+            ("/public/7", "404 Not Found"),
+        ],
+        ..Mock::default()
+    });
+
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let mut config = Config::default();
+    config.set_determinism(false).set_secure(false);
+    let mut endpoints = Endpoints::from_config("test", config);
+    endpoints
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+    endpoints.endpoints[0].set_elapsed_window(vec![time::Duration::from_millis(10)]);
+    endpoints.state.info = Info {
+        period: time::Duration::from_secs(30),
+        genesis_time: time::UNIX_EPOCH,
+        ..Info::default()
+    };
+
+    match rt.block_on(endpoints.get(Some(7))) {
+        Err(Error::RoundUnavailable { round: 7 }) => (),
+        Err(err) => panic!("unexpected {}", err),
+        Ok(r) => panic!("unexpected round {}", r.round),
+    }
+    // watch polls till the round appears.
+    let r = rt.block_on(watch_round(&mut endpoints, 7)).unwrap();
+    assert_eq!(r.round, 7);
+    server.join().unwrap();
+}
+
+#[test]
+fn test_health_check() {
+    use crate::http::MAX_ELAPSED;
+
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let mut endpoints = Endpoints::from_config("test", Config::default());
+
+    let (base_url, server) = mock_server(0, 1);
+    endpoints
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+    endpoints
+        .add_endpoint(Endpoint::HttpCustom {
+            base_url: dead_url(),
+        })
+        .unwrap();
+    for endp in endpoints.endpoints.iter_mut() {
+        endp.set_elapsed_window(vec![time::Duration::from_millis(10)]);
+    }
+
+    assert_eq!(rt.block_on(endpoints.health_check()), vec![true, false]);
+    server.join().unwrap();
+
+    assert!(endpoints.endpoints[0].to_elapsed() < MAX_ELAPSED);
+    assert_eq!(endpoints.endpoints[0].to_elapsed_window().len(), 1);
+    assert_eq!(endpoints.endpoints[1].to_elapsed(), MAX_ELAPSED);
+    match endpoints.get_endpoint_pair(&[]) {
+        (Some((0, _)), None) => (),
+        _ => panic!("dead endpoint picked"),
+    }
+}
+
+#[test]
+fn test_selection_strategy() {
+    let ms = time::Duration::from_millis;
+    let new_endpoints = |selection| {
+        let mut config = Config::default();
+        config.set_selection_strategy(selection);
+        let mut endpoints = Endpoints::from_config("test", config);
+        endpoints.add_endpoint(Endpoint::HttpDrandApi).unwrap();
+        endpoints.add_endpoint(Endpoint::HttpDrandApi2).unwrap();
+        endpoints.add_endpoint(Endpoint::HttpDrandApi3).unwrap();
+        for (endp, elapsed) in endpoints.endpoints.iter_mut().zip([30, 10, 20].iter()) {
+            endp.set_elapsed_window(vec![ms(*elapsed)]);
+        }
+        endpoints
+    };
+    let pick = |endpoints: &mut Endpoints| match endpoints.get_endpoint_pair(&[]) {
+        (Some((i, _)), Some((j, _))) => (i, j),
+        _ => panic!("missing endpoints"),
+    };
+
+    let mut endpoints = new_endpoints(SelectionStrategy::Fastest);
+    assert_eq!(pick(&mut endpoints), (1, 2));
+    assert_eq!(pick(&mut endpoints), (1, 2));
+
+    let mut endpoints = new_endpoints(SelectionStrategy::RoundRobin);
+    assert_eq!(pick(&mut endpoints), (0, 1));
+    assert_eq!(pick(&mut endpoints), (1, 2));
+    assert_eq!(pick(&mut endpoints), (2, 0));
+    assert_eq!(pick(&mut endpoints), (0, 1));
+
+    let mut endpoints = new_endpoints(SelectionStrategy::Weighted);
+    for _ in 0..10 {
+        let (i, j) = pick(&mut endpoints);
+        assert!(i != j && i < 3 && j < 3);
+    }
+
+    // every endpoint gets its share, faster ones more often.
+    let mut counts = [0; 3];
+    for seed in 1..3001_u64 {
+        let mut xs = vec![(0, ms(30)), (1, ms(10)), (2, ms(20))];
+        weighted_order(&mut xs, seed.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        counts[xs[0].0] += 1;
+    }
+    assert!(
+        counts[1] > counts[2] && counts[2] > counts[0],
+        "{:?}",
+        counts
+    );
+    assert!(counts[0] > 0, "{:?}", counts);
+}
+
+#[test]
+fn test_preferred_endpoint() {
+    let ms = time::Duration::from_millis;
+    let mut endpoints = Endpoints::from_config("test", Config::default());
+    endpoints.add_endpoint(Endpoint::HttpDrandApi).unwrap();
+    endpoints.add_endpoint(Endpoint::HttpDrandApi2).unwrap();
+    endpoints.add_endpoint(Endpoint::HttpDrandApi3).unwrap();
+    // slowest and unreachable endpoints can be pinned alike.
+    for (endp, elapsed) in endpoints.endpoints.iter_mut().zip([30, 10, 20].iter()) {
+        endp.set_elapsed_window(vec![ms(*elapsed)]);
+    }
+    let pick = |endpoints: &mut Endpoints, skip: &[usize]| match endpoints.get_endpoint_pair(skip) {
+        (Some((i, _)), None) => Some(i),
+        (None, None) => None,
+        _ => panic!("expected a lone pick"),
+    };
+
+    endpoints
+        .set_preferred(Some("https://api.drand.sh/"))
+        .unwrap();
+    assert_eq!(endpoints.to_preferred().unwrap(), "https://api.drand.sh");
+    assert_eq!(pick(&mut endpoints, &[]), Some(0));
+    assert_eq!(pick(&mut endpoints, &[0]), None);
+
+    endpoints.endpoints[2].set_elapsed_window(vec![]);
+    endpoints
+        .set_preferred(Some("https://api3.drand.sh"))
+        .unwrap();
+    assert_eq!(pick(&mut endpoints, &[]), Some(2));
+
+    match endpoints.set_preferred(Some("https://drand.cloudflare.com")) {
+        Err(Error::Invalid(_, _, _)) => (),
+        res => panic!("unexpected {:?}", res),
+    }
+    assert_eq!(endpoints.to_preferred().unwrap(), "https://api3.drand.sh");
+
+    endpoints.set_preferred(None).unwrap();
+    assert!(endpoints.to_preferred().is_none());
+    match endpoints.get_endpoint_pair(&[]) {
+        (Some((1, _)), Some((0, _))) => (),
+        _ => panic!("expected fastest pair"),
+    }
+}
+
+#[test]
+fn test_first_ok() {
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let mut config = Config::default();
+    config.set_selection_strategy(SelectionStrategy::FirstOk);
+    let mut endpoints = Endpoints::from_config("test", config);
+
+    // the two fastest endpoints accept connections but never respond.
+    let stuck: Vec<_> = (0..2).map(|_| stuck_server()).collect();
+    for (base_url, _) in stuck.iter() {
+        endpoints
+            .add_endpoint(Endpoint::HttpCustom {
+                base_url: base_url.clone(),
+            })
+            .unwrap();
+    }
+    let (base_url, server) = mock_server(0, 1);
+    endpoints
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+    let ms = time::Duration::from_millis;
+    for (endp, elapsed) in endpoints.endpoints.iter_mut().zip([10, 20, 30].iter()) {
+        endp.set_elapsed_window(vec![ms(*elapsed)]);
+    }
+
+    let start = time::Instant::now();
+    let r = rt.block_on(endpoints.get(Some(5))).unwrap();
+    assert_eq!(r.round, 5);
+    assert!(start.elapsed() < time::Duration::from_secs(5));
+    server.join().unwrap();
+}
+
+#[test]
+fn test_get_deadline() {
+    use std::{
+        future::{self, Future},
+        pin::Pin,
+    };
+
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let mut endpoints = Endpoints::from_config("test", Config::default());
+
+    // accepts connections but never responds.
+    let (base_url, _stuck) = stuck_server();
+    endpoints
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+    endpoints.endpoints[0].set_elapsed_window(vec![time::Duration::from_millis(10)]);
+
+    let start = time::Instant::now();
+    let deadline = start + time::Duration::from_millis(200);
+    match rt.block_on(endpoints.get_deadline(Some(5), deadline)) {
+        Err(Error::Timeout(_, msg, _)) => assert!(msg.contains("round 5"), "{}", msg),
+        res => panic!("unexpected {:?}", res.map(|r| r.value)),
+    }
+    assert!(start.elapsed() < time::Duration::from_secs(5));
+
+    let (base_url, server) = mock_server(0, 1);
+    let mut endpoints = Endpoints::from_config("test", Config::default());
+    endpoints
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+    endpoints.endpoints[0].set_elapsed_window(vec![time::Duration::from_millis(10)]);
+    let deadline = time::Instant::now() + time::Duration::from_secs(5);
+    let r = rt
+        .block_on(endpoints.get_deadline(Some(5), deadline))
+        .unwrap();
+    assert_eq!(r.value.round, 5);
+    server.join().unwrap();
+
+    // clock whose delays elapse at once, deadline is not measured on it.
+    struct MockClock;
+
+    impl Clock for MockClock {
+        fn now(&self) -> time::SystemTime {
+            time::SystemTime::now()
+        }
+
+        fn delay(&self, _: time::Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+            Box::pin(future::ready(()))
+        }
+    }
+
+    let (base_url, server) = mock_server(0, 1);
+    let mut config = Config::default();
+    config.set_clock(Box::new(MockClock));
+    let mut endpoints = Endpoints::from_config("test", config);
+    endpoints
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+    endpoints.endpoints[0].set_elapsed_window(vec![time::Duration::from_millis(10)]);
+    let deadline = time::Instant::now() + time::Duration::from_secs(5);
+    let r = rt
+        .block_on(endpoints.get_deadline(Some(5), deadline))
+        .unwrap();
+    assert_eq!(r.value.round, 5);
+    server.join().unwrap();
+}
+
+#[test]
+fn test_get_quorum() {
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let new_endpoints = |n: usize, k: usize, salts: &[u8]| {
+        let mut config = Config::default();
+        config.set_quorum(n, k);
+        let mut endpoints = Endpoints::from_config("test", config);
+        let mut servers = vec![];
+        for salt in salts.iter() {
+            let (base_url, server) = mock_server_salted(0, 1, *salt);
+            endpoints
+                .add_endpoint(Endpoint::HttpCustom { base_url })
+                .unwrap();
+            servers.push(server);
+        }
+        for endp in endpoints.endpoints.iter_mut() {
+            endp.set_elapsed_window(vec![time::Duration::from_millis(10)]);
+        }
+        (endpoints, servers)
+    };
+
+    // two of three agree.
+    let (mut endpoints, servers) = new_endpoints(3, 2, &[0, 0xff, 0]);
+    let r = rt.block_on(endpoints.get(Some(5))).unwrap();
+    assert_eq!(r.signature, vec![5]);
+    servers.into_iter().for_each(|s| {
+        s.join().unwrap();
+    });
+
+    // all three must agree.
+    let (mut endpoints, servers) = new_endpoints(3, 3, &[0, 0xff, 0]);
+    match rt.block_on(endpoints.get(Some(5))) {
+        Err(Error::NotSecure(_, msg, _)) => assert!(msg.contains("quorum"), "{}", msg),
+        res => panic!("unexpected {:?}", res),
+    }
+    servers.into_iter().for_each(|s| {
+        s.join().unwrap();
+    });
+
+    let mut endpoints = Endpoints::from_config("test", Config::default());
+    endpoints.state.quorum = Some((2, 3));
+    assert!(matches!(
+        rt.block_on(endpoints.get(Some(5))),
+        Err(Error::Invalid(_, _, _))
+    ));
+}
+
+#[test]
+fn test_require_agreement() {
+    use crate::SignatureVerifier;
+
+    struct Mock;
+
+    impl SignatureVerifier for Mock {
+        fn verify(&self, _: &[u8], round: u64, _: &[u8], sign: &[u8]) -> Result<bool> {
+            Ok(sign == [round as u8])
+        }
+    }
+
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let new_endpoints = |require_agreement: bool, dead: bool| {
+        let mut config = Config::default();
+        config
+            .set_secure(true)
+            .set_require_agreement(require_agreement)
+            .set_verifier(Box::new(Mock));
+        let mut endpoints = Endpoints::from_config("test", config);
+        let (base_url, server) = mock_server(0, 1);
+        endpoints
+            .add_endpoint(Endpoint::HttpCustom { base_url })
+            .unwrap();
+        let (base_url, other) = match dead {
+            true => (dead_url(), None),
+            false => {
+                let (base_url, server) = mock_server(0, 1);
+                (base_url, Some(server))
+            }
+        };
+        endpoints
+            .add_endpoint(Endpoint::HttpCustom { base_url })
+            .unwrap();
+        for endp in endpoints.endpoints.iter_mut() {
+            endp.set_elapsed_window(vec![time::Duration::from_millis(10)]);
+        }
+        let servers: Vec<_> = Some(server).into_iter().chain(other).collect();
+        (endpoints, servers)
+    };
+
+    // two endpoints agree.
+    let (mut endpoints, servers) = new_endpoints(true, false);
+    let r = rt.block_on(endpoints.get(Some(5))).unwrap();
+    assert_eq!(r.signature, vec![5]);
+    servers.into_iter().for_each(|s| {
+        s.join().unwrap();
+    });
+
+    // lone endpoint's round is accepted by default, not when agreement is
+    // required.
+    let (mut endpoints, servers) = new_endpoints(false, true);
+    rt.block_on(endpoints.get(Some(5))).unwrap();
+    servers.into_iter().for_each(|s| {
+        s.join().unwrap();
+    });
+
+    let (mut endpoints, servers) = new_endpoints(true, true);
+    assert!(rt.block_on(endpoints.get(Some(5))).is_err());
+    servers.into_iter().for_each(|s| {
+        s.join().unwrap();
+    });
+}
+
+#[test]
+fn test_get_timed_host() {
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+
+    // dissenting mirror is the fastest, the round is served by one of the
+    // agreeing mirrors.
+    let mut config = Config::default();
+    config.set_quorum(3, 2);
+    let mut endpoints = Endpoints::from_config("test", config);
+    let mut servers = vec![];
+    for (salt, elapsed) in [(0xff, 1), (0, 20), (0, 30)].iter() {
+        let (base_url, server) = mock_server_salted(0, 1, *salt);
+        endpoints
+            .add_endpoint(Endpoint::HttpCustom { base_url })
+            .unwrap();
+        let endp = endpoints.endpoints.last_mut().unwrap();
+        endp.set_elapsed_window(vec![time::Duration::from_millis(*elapsed)]);
+        servers.push(server);
+    }
+    let hosts = endpoints.to_endpoints();
+
+    let timed = rt.block_on(endpoints.get_timed(Some(5))).unwrap();
+    assert_eq!(timed.value.signature, vec![5]);
+    assert!(hosts[1..].contains(&timed.host), "{}", timed.host);
+    servers.into_iter().for_each(|s| {
+        s.join().unwrap();
+    });
+}
+
+#[test]
+fn test_degraded_fallback() {
+    use crate::http::MAX_ELAPSED;
+
+    let mut endpoints = Endpoints::from_config("test", Config::default());
+    endpoints.add_endpoint(Endpoint::HttpDrandApi).unwrap();
+    endpoints.add_endpoint(Endpoint::HttpDrandApi2).unwrap();
+    endpoints.endpoints[0].set_elapsed_window(vec![MAX_ELAPSED * 2]);
+    endpoints.endpoints[1].set_elapsed_window(vec![MAX_ELAPSED]);
+
+    assert!(endpoints.get_endpoints(&[], 2).is_empty());
+
+    endpoints.state.degraded_fallback = true;
+    let picks: Vec<usize> = endpoints
+        .get_endpoints(&[], 2)
+        .into_iter()
+        .map(|(i, _)| i)
+        .collect();
+    assert_eq!(picks, vec![1]);
+    let picks: Vec<usize> = endpoints
+        .get_endpoints(&[1], 2)
+        .into_iter()
+        .map(|(i, _)| i)
+        .collect();
+    assert_eq!(picks, vec![0]);
+    assert!(endpoints.get_endpoints(&[0, 1], 2).is_empty());
+
+    // healthy endpoints are preferred.
+    endpoints.endpoints[0].set_elapsed_window(vec![time::Duration::from_millis(10)]);
+    match endpoints.get_endpoint_pair(&[]) {
+        (Some((0, _)), None) => (),
+        _ => panic!("unexpected pick"),
+    }
+}
+
+#[test]
+fn test_reset_metrics() {
+    use crate::http::MAX_ELAPSED;
+
+    let mut endpoints = Endpoints::from_config("test", Config::default());
+    endpoints.add_endpoint(Endpoint::HttpDrandApi).unwrap();
+    endpoints.add_endpoint(Endpoint::HttpDrandApi2).unwrap();
+    endpoints.endpoints[0].set_elapsed_window(vec![MAX_ELAPSED]);
+    endpoints.endpoints[1].set_elapsed_window(vec![time::Duration::from_millis(10)]);
+    assert_eq!(endpoints.get_endpoints(&[], 2).len(), 1);
+
+    endpoints.reset_metrics();
+    for endp in endpoints.endpoints.iter() {
+        assert_eq!(endp.to_elapsed(), time::Duration::default());
+    }
+    assert_eq!(endpoints.get_endpoints(&[], 2).len(), 2);
+}
+
+#[test]
+fn test_boot_chain_hash_mismatch() {
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let mut endpoints = Endpoints::from_config("test", Config::default());
+
+    // mock serves hash-info with hash "bb".
+    let (base_url, server) = mock_server(0, 1);
+    endpoints
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+
+    match rt.block_on(endpoints.boot(Some(vec![0xdd]))) {
+        Err(Error::NotSecure(_, msg, _)) => assert!(msg.contains("drand-group"), "{}", msg),
+        res => panic!("unexpected {:?}", res),
+    }
+    server.join().unwrap();
+    assert_eq!(endpoints.to_info(), Info::default());
+
+    // chain-hash pinned via configuration.
+    let mut config = Config::default();
+    config.set_chain_hash(vec![0xdd]);
+    let mut endpoints = Endpoints::from_config("test", config);
+    let (base_url, server) = mock_server(0, 1);
+    endpoints
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+    match rt.block_on(endpoints.boot(None)) {
+        Err(Error::NotSecure(_, msg, _)) => assert!(msg.contains("drand-group"), "{}", msg),
+        res => panic!("unexpected {:?}", res),
+    }
+    server.join().unwrap();
+
+    // chain addressed by its pinned hash.
+    let mut config = Config::default();
+    config.set_chain_hash(vec![0xbb]);
+    let mut endpoints = Endpoints::from_config("test", config);
+    let (base_url, server) = mock_server(0, 2);
+    endpoints
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+    rt.block_on(endpoints.boot(None)).unwrap();
+    server.join().unwrap();
+    assert_eq!(endpoints.to_info().hash, vec![0xbb]);
+
+    // second mirror is a round ahead, its copy of the reference's latest
+    // round is refetched under the pinned chain as well.
+    let mut config = Config::default();
+    config.set_chain_hash(vec![0xbb]);
+    let mut endpoints = Endpoints::from_config("test", config);
+    let (url1, server1) = mock_server(0, 2);
+    let (url2, server2) = mock_server_with(Mock {
+        serves: 3,
+        latest: 6,
+        ..Mock::default()
+    });
+    for base_url in vec![url1, url2].into_iter() {
+        endpoints
+            .add_endpoint(Endpoint::HttpCustom { base_url })
+            .unwrap();
+    }
+    rt.block_on(endpoints.boot(None)).unwrap();
+    let paths = server1.join().unwrap();
+    assert_eq!(paths, vec!["/bb/info", "/bb/public/latest"]);
+    let paths = server2.join().unwrap();
+    assert_eq!(paths, vec!["/bb/info", "/bb/public/latest", "/bb/public/5"]);
+    assert_eq!(endpoints.to_endpoints().len(), 2);
+}
+
+#[test]
+fn test_boot_chains() {
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let mut config = Config::default();
+    config.set_check_point(Some(Random {
+        round: 3,
+        randomness: vec![],
+        signature: vec![],
+        previous_signature: vec![],
+    }));
+    let mut endpoints = Endpoints::from_config("test", config);
+    let (base_url, server) = mock_server_with(Mock {
+        serves: 4,
+        chains: true,
+        ..Mock::default()
+    });
+    endpoints
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+
+    let chains = vec![vec![0xbb], vec![0xdd]];
+    let booted = rt.block_on(endpoints.boot_chains(&chains)).unwrap();
+    let mut paths = server.join().unwrap();
+    paths.sort();
+    assert_eq!(
+        paths,
+        vec![
+            "/bb/info",
+            "/bb/public/latest",
+            "/dd/info",
+            "/dd/public/latest"
+        ]
+    );
+    for (chain_hash, endpoints) in chains.iter().zip(booted.iter()) {
+        assert_eq!(&endpoints.to_info().hash, chain_hash);
+        // check_point of this client belongs to its own chain.
+        assert!(endpoints.to_check_point().is_none());
+    }
+    // this client is left as is.
+    assert_eq!(endpoints.to_info(), Info::default());
+    assert_eq!(endpoints.to_check_point().unwrap().round, 3);
+
+    // a chain that can't be booted fails the lot, mock serves hash-info
+    // with hash "bb" for every chain.
+    let (base_url, server) = mock_server(0, 3);
+    let mut endpoints = Endpoints::from_config("test", Config::default());
+    endpoints
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+    match rt.block_on(endpoints.boot_chains(&chains)) {
+        Err(Error::NotSecure(_, msg, _)) => assert!(msg.contains("drand-group"), "{}", msg),
+        Err(err) => panic!("unexpected {}", err),
+        Ok(_) => panic!("unexpected boot"),
+    }
+    server.join().unwrap();
+}
+
+#[test]
+fn test_boot_dead_endpoint() {
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let mut config = Config::default();
+    config
+        .set_determinism(false)
+        .set_secure(false)
+        .set_cross_validate(true);
+    let mut endpoints = Endpoints::from_config("test", config);
+
+    // each mock serves hash-info and the latest round.
+    let (url1, server1) = mock_server(0, 2);
+    let (url2, server2) = mock_server(0, 2);
+    for base_url in vec![dead_url(), url1.clone(), url2.clone()].into_iter() {
+        endpoints
+            .add_endpoint(Endpoint::HttpCustom { base_url })
+            .unwrap();
+    }
+
+    rt.block_on(endpoints.boot(None)).unwrap();
+    server1.join().unwrap();
+    server2.join().unwrap();
+
+    assert_eq!(endpoints.to_info().hash, vec![0xbb]);
+    assert_eq!(endpoints.to_endpoints(), vec![url1, url2]);
+}
+
+#[test]
+fn test_boot_fallover() {
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let mut config = Config::default();
+    config
+        .set_determinism(false)
+        .set_secure(false)
+        .set_cross_validate(false);
+    let mut endpoints = Endpoints::from_config("test", config);
+
+    // first endpoint is dead, boot falls over to the next one.
+    let (url, server) = mock_server(0, 2);
+    for base_url in vec![dead_url(), url.clone()].into_iter() {
+        endpoints
+            .add_endpoint(Endpoint::HttpCustom { base_url })
+            .unwrap();
+    }
+
+    rt.block_on(endpoints.boot(None)).unwrap();
+    server.join().unwrap();
+
+    assert_eq!(endpoints.to_info().hash, vec![0xbb]);
+    assert_eq!(endpoints.to_endpoints(), vec![url]);
+
+    // with latency measured, fastest endpoint is booted irrespective of
+    // the order added.
+    let mut config = Config::default();
+    config
+        .set_determinism(false)
+        .set_secure(false)
+        .set_cross_validate(false);
+    let mut endpoints = Endpoints::from_config("test", config);
+    let (url1, _stuck) = stuck_server();
+    let (url2, server) = mock_server(0, 2);
+    for base_url in vec![url1.clone(), url2.clone()].into_iter() {
+        endpoints
+            .add_endpoint(Endpoint::HttpCustom { base_url })
+            .unwrap();
+    }
+    endpoints.endpoints[0].set_elapsed_window(vec![time::Duration::from_millis(100)]);
+    endpoints.endpoints[1].set_elapsed_window(vec![time::Duration::from_millis(10)]);
+
+    rt.block_on(endpoints.boot(None)).unwrap();
+    server.join().unwrap();
+    assert_eq!(endpoints.to_endpoints(), vec![url1, url2]);
+}
+
+#[test]
+fn test_boot_mismatch() {
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let mut config = Config::default();
+    config
+        .set_determinism(false)
+        .set_secure(false)
+        .set_cross_validate(true);
+    let mut endpoints = Endpoints::from_config("test", config);
+
+    // second mirror signs the latest round differently.
+    let (url1, server1) = mock_server(0, 2);
+    let (url2, server2) = mock_server_salted(0, 2, 0xf0);
+    for base_url in vec![url1, url2.clone()].into_iter() {
+        endpoints
+            .add_endpoint(Endpoint::HttpCustom { base_url })
+            .unwrap();
+    }
+
+    let err = rt.block_on(endpoints.boot(None)).unwrap_err();
+    server1.join().unwrap();
+    server2.join().unwrap();
+
+    assert!(err.to_string().contains(&url2), "{}", err);
+    assert_eq!(
+        err.to_mismatch(),
+        Some(&Mismatch::Signature {
+            round: 5,
+            expected: vec![5],
+            got: vec![5 ^ 0xf0],
+        })
+    );
+    assert_eq!(
+        Error::IOError("".to_string(), "".to_string(), None).to_mismatch(),
+        None
+    );
+}
+
+#[test]
+fn test_http_client() {
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+
+    // injected client routes every request through the mock, as proxy.
+    let (proxy_url, server) = mock_server(0, 2);
+    let http_client = reqwest::Client::builder()
+        .proxy(reqwest::Proxy::http(&proxy_url).unwrap())
+        .build()
+        .unwrap();
+    let mut config = Config::default();
+    config
+        .set_determinism(false)
+        .set_secure(false)
+        .set_http_client(http_client);
+    let mut endpoints = Endpoints::from_config("test", config);
+    let base_url = "http://drand.invalid".to_string();
+    endpoints
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+
+    rt.block_on(endpoints.boot(None)).unwrap();
+    server.join().unwrap();
+    assert_eq!(endpoints.to_info().hash, vec![0xbb]);
+}
+
+#[test]
+fn test_mock_clock() {
+    use std::sync::Mutex;
+
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+
+    let info = Info {
+        period: time::Duration::from_secs(30),
+        genesis_time: time::UNIX_EPOCH + time::Duration::from_secs(1595431050),
+        ..Info::default()
+    };
+    // in the middle of round-5.
+    let now = Arc::new(Mutex::new(info.time_of_round(5).unwrap() + info.period / 2));
+
+    let mut config = Config::default();
+    config.set_clock(Box::new(MockClock::new(&now)));
+    let mut endpoints = Endpoints::from_config("test", config);
+    endpoints.state.info = info.clone();
+    assert_eq!(endpoints.current_round().unwrap(), 5);
+
+    let (base_url, server) = mock_server(0, 1);
+    endpoints
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+    endpoints.endpoints[0].set_elapsed_window(vec![time::Duration::from_millis(10)]);
+
+    let r = rt.block_on(watch_round(&mut endpoints, 6)).unwrap();
+    server.join().unwrap();
+    assert_eq!(r.round, 6);
+    assert_eq!(*now.lock().unwrap(), info.time_of_round(6).unwrap());
+    assert_eq!(endpoints.current_round().unwrap(), 6);
+}
+
+#[test]
+fn test_clock_skew() {
+    use std::sync::Mutex;
+
+    let info = Info {
+        period: time::Duration::from_secs(30),
+        genesis_time: time::UNIX_EPOCH + time::Duration::from_secs(1595431050),
+        ..Info::default()
+    };
+    // in the middle of round-5.
+    let now = Arc::new(Mutex::new(info.time_of_round(5).unwrap() + info.period / 2));
+    let minute = time::Duration::from_secs(60);
+
+    for (skew, round) in [
+        (None, 5),
+        (Some(crate::ClockSkew::Ahead(minute)), 3),
+        (Some(crate::ClockSkew::Behind(minute)), 7),
+    ]
+    .iter()
+    {
+        let mut config = Config::default();
+        config
+            .set_clock(Box::new(MockClock::new(&now)))
+            .set_clock_skew(*skew);
+        let mut endpoints = Endpoints::from_config("test", config);
+        endpoints.state.info = info.clone();
+        assert_eq!(endpoints.current_round().unwrap(), *round, "{:?}", skew);
+    }
+
+    // a fetched round is published, whatever the clock says.
+    let mut config = Config::default();
+    config.set_clock(Box::new(MockClock::new(&now)));
+    let mut endpoints = Endpoints::from_config("test", config);
+    endpoints.state.info = info.clone();
+
+    endpoints.observed = Some(2);
+    assert_eq!(endpoints.current_round().unwrap(), 5);
+    endpoints.observed = Some(8);
+    assert_eq!(endpoints.current_round().unwrap(), 8);
+    endpoints.observed = None;
+    assert_eq!(endpoints.current_round().unwrap(), 5);
+}
+
+#[test]
+fn test_backward_clock() {
+    use std::sync::Mutex;
+
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let info = Info {
+        period: time::Duration::from_secs(30),
+        genesis_time: time::UNIX_EPOCH + time::Duration::from_secs(1595431050),
+        ..Info::default()
+    };
+    let minute = time::Duration::from_secs(60);
+    // in the middle of round-5.
+    let mid5 = info.time_of_round(5).unwrap() + info.period / 2;
+    let now = Arc::new(Mutex::new(mid5));
+    let clock = MockClock::new(&now);
+    let back = Arc::clone(&clock.back);
+
+    let (base_url, server) = mock_server(0, 1);
+    let mut config = Config::default();
+    config
+        .set_determinism(false)
+        .set_secure(false)
+        .set_clock(Box::new(clock));
+    let mut endpoints = Endpoints::from_config("test", config);
+    endpoints
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+    endpoints.endpoints[0].set_elapsed_window(vec![time::Duration::from_millis(10)]);
+    endpoints.state.info = info.clone();
+    assert_eq!(endpoints.current_round().unwrap(), 5);
+
+    // a round is not taken back once fetched.
+    *now.lock().unwrap() = mid5 - minute;
+    assert_eq!(endpoints.current_round().unwrap(), 3);
+    endpoints.observed = Some(5);
+    assert_eq!(endpoints.current_round().unwrap(), 5);
+
+    // stepped back before genesis.
+    *now.lock().unwrap() = info.genesis_time - minute;
+    assert_eq!(endpoints.current_round().unwrap(), 5);
+    endpoints.observed = None;
+    match endpoints.current_round() {
+        Err(Error::Invalid(_, msg, _)) => assert!(msg.contains("genesis"), "{}", msg),
+        res => panic!("unexpected {:?}", res),
+    }
+
+    // latest round looks to be from the future, refused without panic.
+    let r = Random {
+        round: 5,
+        randomness: vec![],
+        signature: vec![],
+        previous_signature: vec![],
+    };
+    for now in [mid5, mid5 - minute, info.genesis_time - minute].iter() {
+        let res = Endpoints::validate_latest_time(&info, &r, *now);
+        assert_eq!(res.is_ok(), *now == mid5, "{:?}", now);
+    }
+
+    // clock stepped back while waiting for round-7, it is still fetched.
+    *now.lock().unwrap() = mid5;
+    *back.lock().unwrap() = minute;
+    let r = rt.block_on(watch_round(&mut endpoints, 7)).unwrap();
+    assert_eq!(r.round, 7);
+    assert!(*now.lock().unwrap() < info.time_of_round(7).unwrap());
+    server.join().unwrap();
+}
+
+#[test]
+fn test_checkpoint_store() {
+    use std::sync::Mutex;
+
+    struct MockStore(Arc<Mutex<Vec<Random>>>);
+
+    impl CheckpointStore for MockStore {
+        fn load(&self) -> Option<Random> {
+            self.0.lock().unwrap().last().cloned()
+        }
+
+        fn save(&self, check_point: &Random) {
+            self.0.lock().unwrap().push(check_point.clone())
+        }
+    }
+
+    struct Mock;
+
+    impl SignatureVerifier for Mock {
+        fn verify(&self, _: &[u8], _: u64, _: &[u8], _: &[u8]) -> Result<bool> {
+            Ok(true)
+        }
+    }
+
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let saved = Arc::new(Mutex::new(vec![]));
+
+    let mut config = Config::default();
+    config
+        .set_secure(true)
+        .set_verifier(Box::new(Mock))
+        .set_checkpoint_store(Box::new(MockStore(Arc::clone(&saved))));
+    let mut endpoints = Endpoints::from_config("test", config.clone());
+    assert_eq!(endpoints.to_check_point(), None);
+
+    let (base_url, server) = mock_server(0, 2);
+    endpoints
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+    endpoints.endpoints[0].set_elapsed_window(vec![time::Duration::from_millis(10)]);
+
+    // check_point advances to round-5 and is saved, an earlier round
+    // leaves it as is.
+    rt.block_on(endpoints.get(Some(5))).unwrap();
+    rt.block_on(endpoints.get(Some(3))).unwrap();
+    server.join().unwrap();
+    let rounds: Vec<u128> = saved.lock().unwrap().iter().map(|r| r.round).collect();
+    assert_eq!(rounds, vec![5]);
+
+    // restarted client resumes from the saved check_point.
+    let endpoints = Endpoints::from_config("test", config.clone());
+    assert_eq!(endpoints.to_check_point().unwrap().round, 5);
+
+    // explicitly configured check_point takes precedence.
+    let r = Random {
+        round: 2,
+        ..endpoints.to_check_point().unwrap()
+    };
+    config.set_check_point(Some(r.clone()));
+    let endpoints = Endpoints::from_config("test", config);
+    assert_eq!(endpoints.to_check_point(), Some(r));
+}
+
+#[test]
+fn test_observer() {
+    use std::sync::Mutex;
+
+    struct MockObserver(Arc<Mutex<Vec<String>>>);
+
+    impl Observer for MockObserver {
+        fn on_request(&self, _endpoint: &str) {
+            self.0.lock().unwrap().push("request".to_string())
+        }
+
+        fn on_success(&self, _endpoint: &str, round: u128, _latency: time::Duration) {
+            self.0.lock().unwrap().push(format!("success {}", round))
+        }
+
+        fn on_error(&self, _endpoint: &str, _err: &Error) {
+            self.0.lock().unwrap().push("error".to_string())
+        }
+    }
+
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let events = Arc::new(Mutex::new(vec![]));
+
+    let mut config = Config::default();
+    config
+        .set_retry(1, time::Duration::from_millis(1))
+        .set_observer(Box::new(MockObserver(Arc::clone(&events))));
+    let mut endpoints = Endpoints::from_config("test", config);
+
+    // drops the first connection, the retry succeeds.
+    let (base_url, server) = mock_server(1, 1);
+    endpoints
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+    endpoints.endpoints[0].set_elapsed_window(vec![time::Duration::from_millis(10)]);
+
+    assert_eq!(rt.block_on(endpoints.get(Some(5))).unwrap().round, 5);
+    server.join().unwrap();
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec!["request", "error", "request", "success 5"]
+    );
+}
+
+#[test]
+fn test_stale_endpoint() {
+    use std::sync::Mutex;
+
+    struct MockObserver(Arc<Mutex<Vec<(u128, u128)>>>);
+
+    impl Observer for MockObserver {
+        fn on_stale(&self, _endpoint: &str, round: u128, expected: u128) {
+            self.0.lock().unwrap().push((round, expected))
+        }
+    }
+
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let stale = Arc::new(Mutex::new(vec![]));
+
+    let mut config = Config::default();
+    config.set_observer(Box::new(MockObserver(Arc::clone(&stale))));
+    let mut endpoints = Endpoints::from_config("test", config);
+
+    // mock serves round 5 as the latest, the clock says round 11.
+    let (base_url, server) = mock_server(0, 2);
+    endpoints
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+    let period = time::Duration::from_secs(30);
+    endpoints.state.info.period = period;
+    endpoints.state.info.genesis_time = time::SystemTime::now() - (period * 10);
+    let elapsed = time::Duration::from_millis(10);
+    endpoints.endpoints[0].set_elapsed_window(vec![elapsed]);
+
+    // only the latest round is checked.
+    assert_eq!(rt.block_on(endpoints.get(Some(5))).unwrap().round, 5);
+    assert!(stale.lock().unwrap().is_empty());
+
+    assert_eq!(rt.block_on(endpoints.get(None)).unwrap().round, 5);
+    server.join().unwrap();
+    assert_eq!(*stale.lock().unwrap(), vec![(5, 11)]);
+    assert!(endpoints.endpoints[0].to_elapsed() > elapsed);
+}
+
+#[test]
+fn test_get_verified() {
+    use crate::SignatureVerifier;
+
+    // only even rounds verify.
+    struct Mock;
+
+    impl SignatureVerifier for Mock {
+        fn verify(&self, _: &[u8], round: u64, _: &[u8], _: &[u8]) -> Result<bool> {
+            Ok(round & 1 == 0)
+        }
+    }
+
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let mut config = Config::default();
+    config
+        .set_determinism(false)
+        .set_secure(true)
+        .set_cache(true)
+        .set_verifier(Box::new(Mock));
+    let mut endpoints = Endpoints::from_config("test", config);
+    // two watches, then a secure get refreshing hash-info on failure.
+    let (base_url, server) = mock_server(0, 6);
+    endpoints
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+    endpoints.endpoints[0].set_elapsed_window(vec![time::Duration::from_millis(10)]);
+    endpoints.state.info = Info {
+        period: time::Duration::from_secs(30),
+        genesis_time: time::UNIX_EPOCH,
+        ..Info::default()
+    };
+
+    assert!(endpoints.set_secure(false));
+    let val = rt.block_on(endpoints.get_verified(6)).unwrap();
+    assert!(val.verified && val.verify_error.is_none());
+    assert_eq!(val.random.round, 6);
+    assert_eq!(endpoints.to_check_point().unwrap().round, 6);
+
+    // failed round is yielded, check_point stays put.
+    let val = rt.block_on(endpoints.get_verified(7)).unwrap();
+    assert!(!val.verified);
+    assert_eq!(val.random.round, 7);
+    match val.verify_error {
+        Some(Error::NotSecure(_, msg, _)) => assert!(msg.contains("fail verify"), "{}", msg),
+        err => panic!("unexpected {:?}", err),
+    }
+    assert_eq!(endpoints.to_check_point().unwrap().round, 6);
+    assert_eq!(endpoints.observed, Some(6));
+
+    // failed round is not cached, nor served in secure mode.
+    assert!(!endpoints.set_secure(true));
+    let now = endpoints.state.clock.now();
+    let cache = endpoints.cache.as_ref().unwrap();
+    assert!(cache.get(&endpoints.state, Some(7), now).is_none());
+    assert!(cache.get(&endpoints.state, Some(6), now).is_some());
+    match rt.block_on(endpoints.get(Some(7))) {
+        Err(Error::NotSecure(_, msg, _)) => assert!(msg.contains("fail verify"), "{}", msg),
+        res => panic!("unexpected {:?}", res),
+    }
+    server.join().unwrap();
+}
+
+#[test]
+fn test_warm() {
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+
+    let mut endpoints = Endpoints::from_config("test", Config::default());
+    match rt.block_on(endpoints.warm(1, 10)) {
+        Err(Error::Invalid(_, msg, _)) => assert!(msg.contains("cache"), "{}", msg),
+        res => panic!("unexpected {:?}", res),
+    }
+
+    let mut config = Config::default();
+    config.set_cache(true);
+    let mut endpoints = Endpoints::from_config("test", config);
+    // rounds beyond 100 are not published, they are skipped.
+    let (base_url, server) = mock_server(0, 5);
+    endpoints
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+    endpoints.endpoints[0].set_elapsed_window(vec![time::Duration::from_millis(10)]);
+
+    assert_eq!(rt.block_on(endpoints.warm(98, 103)).unwrap(), 3);
+    server.join().unwrap();
+    // served from cache, the server is gone.
+    for round in 98..=100 {
+        let r = rt.block_on(endpoints.get_timed(Some(round))).unwrap();
+        assert_eq!(r.value.signature, vec![round as u8]);
+        assert_eq!(r.elapsed, time::Duration::default());
+    }
+    assert!(rt.block_on(endpoints.get(Some(101))).is_err());
+}
+
+#[test]
+fn test_cache() {
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+
+    let mut config = Config::default();
+    config.set_cache(true);
+    let mut endpoints = Endpoints::from_config("test", config);
+
+    // serves a single request, repeated gets are served from cache.
+    let (base_url, server) = mock_server(0, 1);
+    endpoints
+        .add_endpoint(Endpoint::HttpCustom { base_url })
+        .unwrap();
+    endpoints.endpoints[0].set_elapsed_window(vec![time::Duration::from_millis(10)]);
+
+    let r = rt.block_on(endpoints.get_timed(Some(5))).unwrap();
+    server.join().unwrap();
+    let cached = rt.block_on(endpoints.get_timed(Some(5))).unwrap();
+    assert_eq!(cached.value, r.value);
+    assert_eq!(cached.host, r.host);
+    assert_eq!(cached.elapsed, time::Duration::default());
+    assert!(rt.block_on(endpoints.get(Some(6))).is_err());
+
+    // latest round is served from cache till the next round is due.
+    let state = State {
+        info: Info {
+            period: time::Duration::from_secs(30),
+            genesis_time: time::UNIX_EPOCH + time::Duration::from_secs(1595431050),
+            ..Info::default()
+        },
+        ..State::default()
+    };
+    let mut cache = Cache::default();
+    cache.insert(None, r.clone());
+    let due = state.info.time_of_round(6).unwrap();
+    let val = cache.get(&state, None, due - time::Duration::from_secs(1));
+    assert_eq!(val.unwrap().value, r.value);
+    assert!(cache.get(&state, None, due).is_none());
+    assert!(cache.get(&state, Some(5), due).is_some());
+
+    // earliest rounds are evicted.
+    for round in 6..(CACHE_SIZE as u128 + 6) {
+        let value = Random {
+            round,
+            ..r.value.clone()
+        };
+        cache.insert(Some(round), Timed { value, ..r.clone() });
+    }
+    assert_eq!(cache.rounds.len(), CACHE_SIZE);
+    assert!(cache.get(&state, Some(5), due).is_none());
+    assert!(cache.get(&state, Some(6), due).is_some());
+}
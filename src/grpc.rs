@@ -0,0 +1,456 @@
+use futures::stream::{self, Stream, StreamExt};
+use tonic::{
+    codec::ProstCodec,
+    codegen::http::uri::PathAndQuery,
+    transport::{Channel, ClientTlsConfig},
+    Code, Status,
+};
+
+use std::{
+    cmp,
+    convert::{TryFrom, TryInto},
+    ops, time,
+};
+
+use crate::{
+    core::Scheme,
+    endpoints::State,
+    http::{verify_genesis, MAX_ELAPSED, MAX_ELAPSED_WINDOW},
+    verify, Error, Info, Random, Result,
+};
+
+// rpc methods of drand's `Public` service, refer drand/protobuf/drand/api.proto
+const PUBLIC_RAND: &str = "/drand.Public/PublicRand";
+const CHAIN_INFO: &str = "/drand.Public/ChainInfo";
+
+macro_rules! async_call {
+    ($channel:expr, $req:expr, $path:expr) => {{
+        let start = time::Instant::now();
+        let res = unary($channel, $req, $path).await;
+        res.map(|val| (val, start.elapsed()))
+    }};
+}
+
+/// Endpoint talking to a drand node over its gRPC api.
+#[derive(Clone)]
+pub(crate) struct Grpc {
+    addr: String,
+    elapsed: Vec<time::Duration>,
+}
+
+impl Grpc {
+    pub(crate) fn new(addr: &str) -> Result<Grpc> {
+        let url = err_at!(Invalid, source: reqwest::Url::parse(addr), addr)?;
+        match url.scheme() {
+            "http" | "https" if url.has_host() => (),
+            _ => err_at!(Invalid, msg: format!("not a grpc url {:?}", addr))?,
+        }
+        match url.path() {
+            "" | "/" if url.query().is_none() && url.fragment().is_none() => (),
+            _ => err_at!(Invalid, msg: format!("path/query/fragment in {:?}", addr))?,
+        }
+        let addr = url.as_str().trim_end_matches('/').to_string();
+        Ok(Grpc {
+            addr,
+            elapsed: Vec::default(),
+        })
+    }
+
+    pub(crate) fn to_elapsed(&self) -> time::Duration {
+        match self.elapsed.len() {
+            0 => time::Duration::from_secs(u64::MAX),
+            n => {
+                let sum: time::Duration = self.elapsed.iter().sum();
+                sum / (n as u32)
+            }
+        }
+    }
+
+    pub(crate) fn to_base_url(&self) -> String {
+        self.addr.clone()
+    }
+
+    pub(crate) fn to_last_elapsed(&self) -> time::Duration {
+        self.elapsed.last().cloned().unwrap_or_default()
+    }
+
+    pub(crate) fn to_elapsed_window(&self) -> Vec<time::Duration> {
+        self.elapsed.clone()
+    }
+
+    pub(crate) fn set_elapsed_window(&mut self, window: Vec<time::Duration>) {
+        let n = window.len().saturating_sub(MAX_ELAPSED_WINDOW);
+        self.elapsed = window[n..].to_vec();
+    }
+
+    fn add_elapsed(&mut self, elapsed: time::Duration) {
+        if self.elapsed.len() >= MAX_ELAPSED_WINDOW {
+            self.elapsed.remove(0);
+        }
+        self.elapsed.push(elapsed);
+    }
+
+    // account the latency of a call, a failed call is penalised with
+    // twice the current latency.
+    fn track_elapsed<T>(&mut self, item: Result<(T, time::Duration)>) -> Result<T> {
+        match item {
+            Ok((val, elapsed)) => {
+                self.add_elapsed(elapsed);
+                Ok(val)
+            }
+            Err(err) => {
+                let elapsed = cmp::min(
+                    self.to_elapsed().checked_mul(2).unwrap_or(MAX_ELAPSED),
+                    MAX_ELAPSED,
+                );
+                self.add_elapsed(elapsed);
+                Err(err)
+            }
+        }
+    }
+
+    async fn connect(&self, state: &State) -> Result<Channel> {
+        let endpoint = err_at!(Invalid, source: Channel::from_shared(self.addr.clone()))?;
+        let endpoint = match state.request_timeout {
+            Some(timeout) => endpoint.timeout(timeout),
+            None => endpoint,
+        };
+        let endpoint = match self.addr.starts_with("https") {
+            true => err_at!(Invalid, source: endpoint.tls_config(ClientTlsConfig::new()))?,
+            false => endpoint,
+        };
+        match state.connect_timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, endpoint.connect()).await {
+                Ok(res) => err_at!(IOError, source: res, format!("connect {}", self.addr)),
+                Err(_) => err_at!(IOError, msg: format!("connect {} timeout", self.addr)),
+            },
+            None => err_at!(IOError, source: endpoint.connect().await, self.addr.clone()),
+        }
+    }
+
+    pub(crate) async fn boot_phase1(
+        &mut self,
+        state: &State,
+        rot: Option<&[u8]>,
+    ) -> Result<(Info, Random)> {
+        let channel = self.connect(state).await?;
+
+        // get info
+        let info: Info = {
+            state.budget.check()?;
+            state.rate_limit.acquire(state.clock.as_ref()).await;
+            let res = async_call!(&channel, ChainInfoRequest {}, CHAIN_INFO);
+            let packet: ChainInfoPacket = self.track_elapsed(res)?;
+            state.budget.spend(prost::Message::encoded_len(&packet));
+            packet.try_into()?
+        };
+
+        // confirm whether root-of-trust is as expected.
+        match rot {
+            Some(rot) if rot != info.hash => err_at!(NotSecure, msg: "not expected drand-group")?,
+            _ => (),
+        }
+
+        // get latest round
+        let latest = self.do_get(state, &channel, None).await?;
+
+        Ok((info, latest))
+    }
+
+    // fetch chain-info to check whether the endpoint is reachable and
+    // serving, return the round-trip latency.
+    pub(crate) async fn ping(&self, state: &State) -> Result<time::Duration> {
+        let channel = self.connect(state).await?;
+
+        state.budget.check()?;
+        state.rate_limit.acquire(state.clock.as_ref()).await;
+        let (_packet, elapsed): (ChainInfoPacket, _) =
+            async_call!(&channel, ChainInfoRequest {}, CHAIN_INFO)?;
+
+        Ok(elapsed)
+    }
+
+    pub(crate) async fn boot_phase2(&mut self, mut state: State, latest: Random) -> Result<State> {
+        let channel = self.connect(&state).await?;
+
+        // get check_point
+        state.check_point = match (state.determinism, state.check_point.take()) {
+            // reestablish-determinism
+            (true, None) => {
+                let r = self.do_get(&state, &channel, Some(1)).await?;
+                verify_genesis(&state, &r)?;
+                Some(self.verify(&state, &channel, r, latest).await?)
+            }
+            // continued-determinism
+            (true, Some(check_point)) => {
+                Some(self.verify(&state, &channel, check_point, latest).await?)
+            }
+            // assumed-determinism
+            (false, _) if state.secure => Some(latest),
+            // no-determinism
+            (false, _) => None,
+        };
+
+        Ok(state)
+    }
+
+    pub(crate) async fn get(
+        &mut self,
+        mut state: State,
+        round: Option<u128>,
+    ) -> Result<(State, Random)> {
+        let channel = self.connect(&state).await?;
+
+        let r = self.do_get(&state, &channel, round).await?;
+
+        let (check_point, r) = match (state.check_point.take(), round) {
+            // return a verified genesis randomness, chains from group_hash.
+            (check_point, Some(1)) if state.secure => {
+                verify_genesis(&state, &r)?;
+                (check_point.unwrap_or_else(|| r.clone()), r)
+            }
+            // return a verified earlier random-ness.
+            (Some(check_point), Some(round)) if round <= check_point.round && state.secure => {
+                r.verify_with(&state.info, state.verifier.as_ref())?;
+                (check_point, r)
+            }
+            // just return an earlier random-ness.
+            (Some(check_point), Some(round)) if round <= check_point.round => (check_point, r),
+            // return a verified randomness, requested or latest round.
+            (Some(check_point), _) if state.secure => {
+                let r = self.verify(&state, &channel, check_point, r).await?;
+                (r.clone(), r)
+            }
+            // return insecure randomness, requested or latest round.
+            (Some(_), _) => (r.clone(), r),
+            // return a verified randomness, without a check_point to chain
+            // from, only its signature can be verified.
+            (None, _) if state.secure => {
+                r.verify_with(&state.info, state.verifier.as_ref())?;
+                (r.clone(), r)
+            }
+            // return unverified and insecure randomness
+            (None, _) => (r.clone(), r),
+        };
+        state.check_point = Some(check_point);
+
+        Ok((state, r))
+    }
+
+    pub(crate) async fn get_range(
+        &mut self,
+        state: &State,
+        start: u128,
+        end: u128,
+    ) -> Result<Vec<Random>> {
+        let channel = self.connect(state).await?;
+        let (info, verifier) = (&state.info, state.verifier.as_ref());
+
+        let mut rounds = fetch_rounds(state, &channel, start..end);
+
+        let mut items: Vec<Random> = vec![];
+        while let Some(item) = rounds.next().await {
+            let random = self.track_elapsed(item)?;
+            if state.secure {
+                match items.last() {
+                    // round-1 chains from group_hash.
+                    None if random.round == 1 => verify_genesis(state, &random)?,
+                    // nothing to chain the first round from, verify signature.
+                    None => random.verify_with(info, verifier)?,
+                    Some(prev) => {
+                        if !verify::verify_chain(verifier, info, &prev.signature, &random)? {
+                            err_at!(NotSecure, msg: format!("fail verify {}", random))?;
+                        }
+                    }
+                }
+            }
+            items.push(random);
+        }
+
+        Ok(items)
+    }
+
+    async fn verify(
+        &mut self,
+        state: &State,
+        channel: &Channel,
+        mut prev: Random,
+        till: Random,
+    ) -> Result<Random> {
+        let info = &state.info;
+
+        if prev.round >= till.round {
+            return Ok(till);
+        }
+        // rounds of an unchained scheme don't link to one another, there
+        // is no chain to walk.
+        if info.scheme != Scheme::Chained {
+            till.verify_with(info, state.verifier.as_ref())?;
+            return Ok(till);
+        }
+
+        let verifier = state.verifier.as_ref();
+        let mut rounds = fetch_rounds(state, channel, (prev.round + 1)..till.round);
+        while let Some(item) = rounds.next().await {
+            let random = self.track_elapsed(item)?;
+            if !verify::verify_chain(verifier, info, &prev.signature, &random)? {
+                err_at!(NotSecure, msg: format!("fail verify {}", random))?;
+            }
+            prev = random;
+        }
+
+        if !verify::verify_chain(verifier, info, &prev.signature, &till)? {
+            err_at!(NotSecure, msg: format!("fail verify {}", till))?;
+        }
+
+        Ok(till)
+    }
+
+    async fn do_get(
+        &mut self,
+        state: &State,
+        channel: &Channel,
+        round: Option<u128>,
+    ) -> Result<Random> {
+        state.budget.check()?;
+        state.rate_limit.acquire(state.clock.as_ref()).await;
+
+        // round 0 asks for the latest round.
+        let req = PublicRandRequest {
+            round: to_wire_round(round.unwrap_or(0))?,
+        };
+        let res = async_call!(channel, req, PUBLIC_RAND);
+        let resp: PublicRandResponse = self.track_elapsed(res)?;
+        state.budget.spend(prost::Message::encoded_len(&resp));
+
+        Ok(resp.into())
+    }
+}
+
+// fetch `rounds` from the endpoint, at most max_conns at a time, yielded
+// in round order.
+fn fetch_rounds<'a>(
+    state: &'a State,
+    channel: &'a Channel,
+    rounds: ops::Range<u128>,
+) -> impl Stream<Item = Result<(Random, time::Duration)>> + 'a {
+    let rounds = rounds.map(move |round| async move {
+        state.budget.check()?;
+        state.rate_limit.acquire(state.clock.as_ref()).await;
+        let req = PublicRandRequest {
+            round: to_wire_round(round)?,
+        };
+        let (resp, elapsed): (PublicRandResponse, _) = async_call!(channel, req, PUBLIC_RAND)?;
+        state.budget.spend(prost::Message::encoded_len(&resp));
+        let r: Random = resp.into();
+        if r.round != round {
+            err_at!(Invalid, msg: format!("asked round {} got {}", round, r.round))?;
+        }
+        Ok::<(Random, time::Duration), Error>((r, elapsed))
+    });
+    stream::iter(rounds).buffered(cmp::max(state.max_conns, 1))
+}
+
+async fn unary<Q, R>(channel: &Channel, req: Q, path: &'static str) -> Result<R>
+where
+    Q: prost::Message + Send + Sync + 'static,
+    R: prost::Message + Default + Send + Sync + 'static,
+{
+    let mut client = tonic::client::Grpc::new(channel.clone());
+    err_at!(IOError, source: client.ready().await, path)?;
+
+    let uri = PathAndQuery::from_static(path);
+    let codec: ProstCodec<Q, R> = ProstCodec::default();
+    match client.unary(tonic::Request::new(req), uri, codec).await {
+        Ok(resp) => Ok(resp.into_inner()),
+        // transient failures are IOError, so that they are retried.
+        Err(status) if is_transient(&status) => err_at!(IOError, source: Err(status), path),
+        Err(status) => err_at!(Invalid, source: Err(status), path),
+    }
+}
+
+fn is_transient(status: &Status) -> bool {
+    matches!(
+        status.code(),
+        Code::Unavailable | Code::DeadlineExceeded | Code::ResourceExhausted
+    )
+}
+
+fn to_wire_round(round: u128) -> Result<u64> {
+    err_at!(Invalid, source: u64::try_from(round), format!("round {}", round))
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct PublicRandRequest {
+    #[prost(uint64, tag = "1")]
+    round: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct PublicRandResponse {
+    #[prost(uint64, tag = "1")]
+    round: u64,
+    #[prost(bytes, tag = "2")]
+    signature: Vec<u8>,
+    // empty for unchained scheme.
+    #[prost(bytes, tag = "3")]
+    previous_signature: Vec<u8>,
+    #[prost(bytes, tag = "4")]
+    randomness: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct ChainInfoRequest {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct ChainInfoPacket {
+    #[prost(bytes, tag = "1")]
+    public_key: Vec<u8>,
+    #[prost(uint32, tag = "2")]
+    period: u32,
+    #[prost(int64, tag = "3")]
+    genesis_time: i64,
+    #[prost(bytes, tag = "4")]
+    hash: Vec<u8>,
+    #[prost(bytes, tag = "5")]
+    group_hash: Vec<u8>,
+    // empty for nodes predating schemes.
+    #[prost(string, tag = "6")]
+    scheme_id: String,
+}
+
+impl From<PublicRandResponse> for Random {
+    fn from(val: PublicRandResponse) -> Self {
+        Random {
+            round: val.round.into(),
+            randomness: val.randomness,
+            signature: val.signature,
+            previous_signature: val.previous_signature,
+        }
+    }
+}
+
+impl TryFrom<ChainInfoPacket> for Info {
+    type Error = Error;
+
+    fn try_from(val: ChainInfoPacket) -> Result<Self> {
+        let genesis_time = err_at!(Invalid, source: u64::try_from(val.genesis_time), "genesis")?;
+        let val = Info {
+            scheme: match val.scheme_id.as_str() {
+                "" => Scheme::from_public_key(&val.public_key),
+                scheme_id => scheme_id.parse()?,
+            },
+            public_key: val.public_key,
+            hash: val.hash,
+            group_hash: val.group_hash,
+            group: None,
+            ..Info::from_unix(genesis_time, val.period.into())?
+        };
+
+        Ok(val)
+    }
+}
+
+#[cfg(test)]
+#[path = "grpc_test.rs"]
+mod grpc_test;
@@ -0,0 +1,94 @@
+use prost::Message;
+
+use super::*;
+
+#[test]
+fn test_grpc_addr() {
+    let endp = Grpc::new("https://drand.example.com:4444/").unwrap();
+    assert_eq!(endp.to_base_url(), "https://drand.example.com:4444");
+    let endp = Grpc::new("http://127.0.0.1:4444").unwrap();
+    assert_eq!(endp.to_base_url(), "http://127.0.0.1:4444");
+
+    for addr in [
+        "",
+        "127.0.0.1:4444",
+        "ftp://drand.example.com",
+        "https://drand.example.com/public",
+        "https://drand.example.com?round=1",
+    ]
+    .iter()
+    {
+        match Grpc::new(addr) {
+            Err(Error::Invalid(_, _, _)) => (),
+            Err(err) => panic!("{:?} {}", addr, err),
+            Ok(_) => panic!("{:?} accepted", addr),
+        }
+    }
+}
+
+#[test]
+fn test_public_rand_wire() {
+    // round = 1, signature = [0xaa], previous_signature = [0xbb],
+    // randomness = [0xcc], and an unknown metadata field.
+    let wire = [
+        0x08, 0x01, 0x12, 0x01, 0xaa, 0x1a, 0x01, 0xbb, 0x22, 0x01, 0xcc, 0x2a, 0x00,
+    ];
+    let resp = PublicRandResponse::decode(&wire[..]).unwrap();
+    let r: Random = resp.into();
+    assert_eq!(r.round, 1);
+    assert_eq!(r.signature, vec![0xaa]);
+    assert_eq!(r.previous_signature, vec![0xbb]);
+    assert_eq!(r.randomness, vec![0xcc]);
+
+    let mut buf = vec![];
+    PublicRandRequest { round: 2 }.encode(&mut buf).unwrap();
+    assert_eq!(buf, vec![0x08, 0x02]);
+
+    // latest round.
+    let mut buf = vec![];
+    PublicRandRequest { round: 0 }.encode(&mut buf).unwrap();
+    assert!(buf.is_empty());
+
+    assert!(to_wire_round(u128::from(u64::MAX) + 1).is_err());
+}
+
+#[test]
+fn test_chain_info_packet() {
+    let packet = ChainInfoPacket {
+        public_key: vec![1, 2],
+        period: 30,
+        genesis_time: 1595431050,
+        hash: vec![3, 4],
+        group_hash: vec![5, 6],
+        scheme_id: String::default(),
+    };
+    let info: Info = packet.clone().try_into().unwrap();
+    assert_eq!(info.public_key, vec![1, 2]);
+    assert_eq!(info.period, time::Duration::from_secs(30));
+    assert_eq!(
+        info.genesis_time,
+        time::UNIX_EPOCH + time::Duration::from_secs(1595431050)
+    );
+    assert_eq!(info.hash, vec![3, 4]);
+    assert_eq!(info.group_hash, vec![5, 6]);
+    assert_eq!(info.scheme, Scheme::default());
+
+    let mut buf = vec![];
+    packet.encode(&mut buf).unwrap();
+    assert_eq!(ChainInfoPacket::decode(&buf[..]).unwrap(), packet);
+
+    // nodes predating schemes, public-key on G2.
+    let g2 = ChainInfoPacket {
+        public_key: vec![0; 96],
+        ..packet.clone()
+    };
+    let info: Info = g2.try_into().unwrap();
+    assert_eq!(info.scheme, Scheme::UnchainedG1);
+
+    let packet = ChainInfoPacket {
+        genesis_time: -1,
+        ..packet
+    };
+    let res: Result<Info> = packet.try_into();
+    assert!(res.is_err());
+}
@@ -1,12 +1,23 @@
-use serde::Deserialize;
+use futures::stream::{self, Stream, StreamExt};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use std::{
     cmp,
+    collections::HashMap,
     convert::{TryFrom, TryInto},
+    ops,
+    sync::{
+        atomic::{AtomicUsize, Ordering::SeqCst},
+        Arc, Mutex,
+    },
     time,
 };
 
-use crate::{core::MAX_CONNS, endpoints::State, verify, Error, Info, Random, Result};
+use crate::{
+    core::{GroupInfo, Scheme},
+    endpoints::State,
+    verify, Clock, Error, Info, Random, Result,
+};
 
 pub(crate) const MAX_ELAPSED_WINDOW: usize = 32;
 
@@ -40,7 +51,10 @@ macro_rules! add_elapsed {
                 Ok(val)
             }
             err @ Err(_) => {
-                let elapsed = cmp::min($this.to_elapsed() * 2, MAX_ELAPSED);
+                let elapsed = cmp::min(
+                    $this.to_elapsed().checked_mul(2).unwrap_or(MAX_ELAPSED),
+                    MAX_ELAPSED,
+                );
                 $this.add_elapsed(elapsed);
                 err
             }
@@ -51,6 +65,10 @@ macro_rules! add_elapsed {
 #[derive(Clone)]
 pub(crate) enum Http {
     DrandApi(Vec<time::Duration>),
+    DrandApi2(Vec<time::Duration>),
+    DrandApi3(Vec<time::Duration>),
+    Cloudflare(Vec<time::Duration>),
+    Custom(String, Vec<time::Duration>),
 }
 
 impl Http {
@@ -58,10 +76,46 @@ impl Http {
         Http::DrandApi(Vec::default())
     }
 
+    pub(crate) fn new_drand_api2() -> Http {
+        Http::DrandApi2(Vec::default())
+    }
+
+    pub(crate) fn new_drand_api3() -> Http {
+        Http::DrandApi3(Vec::default())
+    }
+
+    pub(crate) fn new_cloudflare() -> Http {
+        Http::Cloudflare(Vec::default())
+    }
+
+    pub(crate) fn new_custom(base_url: &str) -> Result<Http> {
+        let url = err_at!(Invalid, source: reqwest::Url::parse(base_url), base_url)?;
+        match url.scheme() {
+            "http" | "https" if url.has_host() => (),
+            _ => err_at!(Invalid, msg: format!("not a http url {:?}", base_url))?,
+        }
+        if url.query().is_some() || url.fragment().is_some() {
+            err_at!(Invalid, msg: format!("query/fragment in {:?}", base_url))?
+        }
+        // urls are composed by appending to the base-url. Use the parsed
+        // url, with scheme and host lower-cased and default port elided,
+        // so that the same host spelled differently is deduplicated.
+        let base_url = url.as_str().trim_end_matches('/').to_string();
+        Ok(Http::Custom(base_url, Vec::default()))
+    }
+
+    // base-url of the chain, `/{chain-hash}` appended when one is pinned,
+    // to address the chain on nodes serving several of them.
+    pub(crate) fn to_chain_url(&self, state: &State) -> String {
+        let endpoint = self.to_base_url();
+        match &state.chain_hash {
+            Some(hash) => format!("{}/{}", endpoint, hex::encode(hash)),
+            None => endpoint,
+        }
+    }
+
     pub(crate) fn to_elapsed(&self) -> time::Duration {
-        let es = match self {
-            Http::DrandApi(es) => es,
-        };
+        let es = self.as_elapsed_window();
         match es.len() {
             0 => time::Duration::from_secs(u64::MAX),
             n => {
@@ -71,16 +125,78 @@ impl Http {
         }
     }
 
-    fn to_base_url(&self) -> String {
+    // fetch `rounds` from this endpoint, at most max_conns at a time,
+    // yielded in round order.
+    fn fetch_rounds<'a>(
+        &self,
+        state: &'a State,
+        client: &'a reqwest::Client,
+        rounds: ops::Range<u128>,
+    ) -> impl Stream<Item = Result<(Random, time::Duration)>> + 'a {
+        let endpoint = self.to_chain_url(state);
+        let rounds = rounds.map(move |round| {
+            let url = make_url!("public", endpoint, round);
+            async move {
+                state.budget.check()?;
+                state.rate_limit.acquire(state.clock.as_ref()).await;
+                let (res, elapsed) = { async_get!(client, url) };
+                let resp = res?;
+                let r: RandomJson = read_json(state, resp).await?;
+                let r: Random = r.try_into()?;
+                if r.round != round {
+                    err_at!(Invalid, msg: format!("asked round {} got {}", round, r.round))?;
+                }
+                Ok::<(Random, time::Duration), Error>((r, elapsed))
+            }
+        });
+        stream::iter(rounds).buffered(cmp::max(state.max_conns, 1))
+    }
+
+    // account the latency of a fetched round, a failed fetch is penalised
+    // with twice the current latency.
+    fn track_elapsed(&mut self, item: Result<(Random, time::Duration)>) -> Result<Random> {
+        match item {
+            Ok((r, elapsed)) => {
+                self.add_elapsed(elapsed);
+                Ok(r)
+            }
+            Err(err) => {
+                let elapsed = cmp::min(
+                    self.to_elapsed().checked_mul(2).unwrap_or(MAX_ELAPSED),
+                    MAX_ELAPSED,
+                );
+                self.add_elapsed(elapsed);
+                Err(err)
+            }
+        }
+    }
+
+    pub(crate) fn to_base_url(&self) -> String {
         match self {
             Http::DrandApi(_) => "https://api.drand.sh".to_string(),
+            Http::DrandApi2(_) => "https://api2.drand.sh".to_string(),
+            Http::DrandApi3(_) => "https://api3.drand.sh".to_string(),
+            Http::Cloudflare(_) => "https://drand.cloudflare.com".to_string(),
+            Http::Custom(base_url, _) => base_url.clone(),
         }
     }
 
+    pub(crate) fn to_last_elapsed(&self) -> time::Duration {
+        self.as_elapsed_window().last().cloned().unwrap_or_default()
+    }
+
+    pub(crate) fn to_elapsed_window(&self) -> Vec<time::Duration> {
+        self.as_elapsed_window().clone()
+    }
+
+    pub(crate) fn set_elapsed_window(&mut self, window: Vec<time::Duration>) {
+        let es = self.as_mut_elapsed_window();
+        let n = window.len().saturating_sub(MAX_ELAPSED_WINDOW);
+        *es = window[n..].to_vec();
+    }
+
     fn add_elapsed(&mut self, elapsed: time::Duration) {
-        let es = match self {
-            Http::DrandApi(es) => es,
-        };
+        let es = self.as_mut_elapsed_window();
 
         match es.len() {
             n if n >= MAX_ELAPSED_WINDOW => {
@@ -91,22 +207,45 @@ impl Http {
         es.push(elapsed);
     }
 
+    fn as_elapsed_window(&self) -> &Vec<time::Duration> {
+        match self {
+            Http::DrandApi(es) => es,
+            Http::DrandApi2(es) => es,
+            Http::DrandApi3(es) => es,
+            Http::Cloudflare(es) => es,
+            Http::Custom(_, es) => es,
+        }
+    }
+
+    fn as_mut_elapsed_window(&mut self) -> &mut Vec<time::Duration> {
+        match self {
+            Http::DrandApi(es) => es,
+            Http::DrandApi2(es) => es,
+            Http::DrandApi3(es) => es,
+            Http::Cloudflare(es) => es,
+            Http::Custom(_, es) => es,
+        }
+    }
+
     pub(crate) async fn boot_phase1(
         &mut self,
+        state: &State,
         rot: Option<&[u8]>,
         agent: Option<reqwest::header::HeaderValue>,
     ) -> Result<(Info, Random)> {
-        let endpoint = self.to_base_url();
-        let client = new_http_client(MAX_CONNS, agent.clone())?;
+        let endpoint = self.to_chain_url(state);
+        let client = new_http_client(state, state.max_conns, agent.clone())?;
 
         // get info
         let info: Info = {
+            state.budget.check()?;
+            state.rate_limit.acquire(state.clock.as_ref()).await;
             let (res, elapsed) = {
                 let url = make_url!("info", endpoint);
                 async_get!(client, url)
             };
-            let resp = err_at!(IOError, add_elapsed!(self, res, elapsed))?;
-            let info: InfoJson = err_at!(JsonParse, resp.json().await)?;
+            let resp = err_at!(IOError, source: add_elapsed!(self, res, elapsed))?;
+            let info: InfoJson = read_json(state, resp).await?;
             info.try_into()?
         };
 
@@ -120,24 +259,47 @@ impl Http {
         }
 
         // get latest round
-        let latest = self.do_get(&client, None).await?;
+        let latest = self.do_get(state, &client, None).await?;
 
         Ok((info, latest))
     }
 
+    // fetch `/info` to check whether the endpoint is reachable and
+    // serving, return the round-trip latency.
+    pub(crate) async fn ping(
+        &self,
+        state: &State,
+        agent: Option<reqwest::header::HeaderValue>,
+    ) -> Result<time::Duration> {
+        let endpoint = self.to_chain_url(state);
+        let client = new_http_client(state, state.max_conns, agent)?;
+
+        state.budget.check()?;
+        state.rate_limit.acquire(state.clock.as_ref()).await;
+        let (res, elapsed) = {
+            let url = make_url!("info", endpoint);
+            async_get!(client, url)
+        };
+        let resp = res?;
+        let _info: InfoJson = read_json(state, resp).await?;
+
+        Ok(elapsed)
+    }
+
     pub(crate) async fn boot_phase2(
         &mut self,
         mut state: State,
         latest: Random,
         agent: Option<reqwest::header::HeaderValue>,
     ) -> Result<State> {
-        let client = new_http_client(MAX_CONNS, agent.clone())?;
+        let client = new_http_client(&state, state.max_conns, agent.clone())?;
 
         // get check_point
         state.check_point = match (state.determinism, state.check_point.take()) {
             // reestablish-determinism
             (true, None) => {
-                let r = self.do_get(&client, Some(1)).await?;
+                let r = self.do_get(&state, &client, Some(1)).await?;
+                verify_genesis(&state, &r)?;
                 Some(self.verify(&state, r, latest, agent.clone()).await?)
             }
             // continued-determinism
@@ -163,11 +325,21 @@ impl Http {
         round: Option<u128>,
         agent: Option<reqwest::header::HeaderValue>,
     ) -> Result<(State, Random)> {
-        let client = new_http_client(MAX_CONNS, agent.clone())?;
+        let client = new_http_client(&state, state.max_conns, agent.clone())?;
 
-        let r = self.do_get(&client, round).await?;
+        let r = self.do_get(&state, &client, round).await?;
 
         let (check_point, r) = match (state.check_point.take(), round) {
+            // return a verified genesis randomness, chains from group_hash.
+            (check_point, Some(1)) if state.secure => {
+                verify_genesis(&state, &r)?;
+                (check_point.unwrap_or_else(|| r.clone()), r)
+            }
+            // return a verified earlier random-ness.
+            (Some(check_point), Some(round)) if round <= check_point.round && state.secure => {
+                r.verify_with(&state.info, state.verifier.as_ref())?;
+                (check_point, r)
+            }
             // just return an earlier random-ness.
             (Some(check_point), Some(round)) if round <= check_point.round => {
                 // TODO: with cache we can optimize this call
@@ -187,6 +359,12 @@ impl Http {
             }
             // return insecure randomness, latest round
             (Some(_), None) => (r.clone(), r),
+            // return a verified randomness, without a check_point to chain
+            // from, only its signature can be verified.
+            (None, _) if state.secure => {
+                r.verify_with(&state.info, state.verifier.as_ref())?;
+                (r.clone(), r)
+            }
             // return unverified and insecure randomness
             (None, _) => (r.clone(), r),
         };
@@ -195,6 +373,40 @@ impl Http {
         Ok((state, r))
     }
 
+    pub(crate) async fn get_range(
+        &mut self,
+        state: &State,
+        start: u128,
+        end: u128,
+        agent: Option<reqwest::header::HeaderValue>,
+    ) -> Result<Vec<Random>> {
+        let client = new_http_client(state, state.max_conns, agent)?;
+        let (info, verifier) = (&state.info, state.verifier.as_ref());
+
+        let mut rounds = self.fetch_rounds(state, &client, start..end);
+
+        let mut items: Vec<Random> = vec![];
+        while let Some(item) = rounds.next().await {
+            let random = self.track_elapsed(item)?;
+            if state.secure {
+                match items.last() {
+                    // round-1 chains from group_hash.
+                    None if random.round == 1 => verify_genesis(state, &random)?,
+                    // nothing to chain the first round from, verify signature.
+                    None => random.verify_with(info, verifier)?,
+                    Some(prev) => {
+                        if !verify::verify_chain(verifier, info, &prev.signature, &random)? {
+                            err_at!(NotSecure, msg: format!("fail verify {}", random))?;
+                        }
+                    }
+                }
+            }
+            items.push(random);
+        }
+
+        Ok(items)
+    }
+
     pub(crate) async fn verify(
         &mut self,
         state: &State,
@@ -202,49 +414,34 @@ impl Http {
         till: Random,
         agent: Option<reqwest::header::HeaderValue>,
     ) -> Result<Random> {
-        let endpoint = self.to_base_url();
-        let client = new_http_client(state.max_conns, agent.clone())?;
-        let pk = state.info.public_key.as_slice();
-
-        while prev.round < till.round {
-            let till_round = cmp::min(prev.round + 1000, till.round);
-
-            let mut rounds = vec![];
-            for round in (prev.round + 1)..till_round {
-                let url = make_url!("public", endpoint, round);
-                let client = &client;
-                rounds.push(async move {
-                    let (res, elapsed) = { async_get!(client, url) };
-                    let resp = err_at!(IOError, res)?;
-                    let r: RandomJson = err_at!(JsonParse, resp.json().await)?;
-                    let r: Random = r.try_into()?;
-                    Ok::<(Random, time::Duration), Error>((r, elapsed))
-                });
-            }
+        let client = new_http_client(state, state.max_conns, agent.clone())?;
+        let info = &state.info;
 
-            let mut err = false;
-            for item in futures::future::join_all(rounds).await {
-                let random = match item {
-                    Ok((_, elapsed)) if err => {
-                        self.add_elapsed(elapsed);
-                        continue;
-                    }
-                    Ok((r, elapsed)) => {
-                        self.add_elapsed(elapsed);
-                        r
-                    }
-                    Err(_) => {
-                        let elapsed = cmp::min(self.to_elapsed() * 2, MAX_ELAPSED);
-                        self.add_elapsed(elapsed);
-                        err = true;
-                        continue;
-                    }
-                };
-                if !verify::verify_chain(&pk, &prev.signature, &random)? {
-                    err_at!(NotSecure, msg: format!("fail verify {}", random))?;
-                }
-                prev = random;
+        if prev.round >= till.round {
+            return Ok(till);
+        }
+        // rounds of an unchained scheme don't link to one another, there
+        // is no chain to walk.
+        if info.scheme != Scheme::Chained {
+            till.verify_with(info, state.verifier.as_ref())?;
+            return Ok(till);
+        }
+
+        // fetch the rounds in between, at most max_conns at a time, and
+        // walk the chain in round order, holding only the previous round.
+        let mut rounds = self.fetch_rounds(state, &client, (prev.round + 1)..till.round);
+
+        let verifier = state.verifier.as_ref();
+        while let Some(item) = rounds.next().await {
+            let random = self.track_elapsed(item)?;
+            if !verify::verify_chain(verifier, info, &prev.signature, &random)? {
+                err_at!(NotSecure, msg: format!("fail verify {}", random))?;
             }
+            prev = random;
+        }
+
+        if !verify::verify_chain(verifier, info, &prev.signature, &till)? {
+            err_at!(NotSecure, msg: format!("fail verify {}", till))?;
         }
 
         Ok(till)
@@ -252,10 +449,14 @@ impl Http {
 
     pub(crate) async fn do_get(
         &mut self,
+        state: &State,
         client: &reqwest::Client,
         round: Option<u128>,
     ) -> Result<Random> {
-        let endpoint = self.to_base_url();
+        let endpoint = self.to_chain_url(state);
+
+        state.budget.check()?;
+        state.rate_limit.acquire(state.clock.as_ref()).await;
 
         let r = match round {
             Some(round) => {
@@ -263,17 +464,23 @@ impl Http {
                     let url = make_url!("public", endpoint, round);
                     async_get!(client, url)
                 };
-                let resp = err_at!(IOError, add_elapsed!(self, res, elapsed))?;
-                let r: RandomJson = err_at!(JsonParse, resp.json().await)?;
-                r.try_into()?
+                let resp = err_at!(IOError, source: add_elapsed!(self, res, elapsed))?;
+                let r: RandomJson = read_json(state, resp).await?;
+                let r: Random = r.try_into()?;
+                // a valid round other than the one asked for is no answer.
+                if r.round != round {
+                    let msg = format!("asked round {} got {}", round, r.round);
+                    err_at!(NotSecure, msg: msg)?
+                }
+                r
             }
             None => {
                 let (res, elapsed) = {
                     let url = make_url!("public", endpoint);
                     async_get!(client, url)
                 };
-                let resp = err_at!(IOError, add_elapsed!(self, res, elapsed))?;
-                let r: RandomJson = err_at!(JsonParse, resp.json().await)?;
+                let resp = err_at!(IOError, source: add_elapsed!(self, res, elapsed))?;
+                let r: RandomJson = read_json(state, resp).await?;
                 r.try_into()?
             }
         };
@@ -282,38 +489,89 @@ impl Http {
     }
 }
 
-#[derive(Deserialize)]
-struct InfoJson {
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct InfoJson {
     public_key: String,
     period: u64,
     genesis_time: u64,
     hash: String,
     #[serde(alias = "groupHash")]
     group_hash: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    threshold: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    nodes: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dist_key: Option<Vec<String>>,
+    #[serde(rename = "schemeID", default, skip_serializing_if = "Option::is_none")]
+    scheme_id: Option<String>,
 }
 
 impl TryFrom<InfoJson> for Info {
     type Error = Error;
 
     fn try_from(val: InfoJson) -> Result<Self> {
-        let genesis_time = time::Duration::from_secs(val.genesis_time);
+        let group = match (val.threshold, val.nodes, &val.dist_key) {
+            (None, None, None) => None,
+            (threshold, nodes, dist_key) => {
+                let mut coeffs = vec![];
+                for coeff in dist_key.iter().flatten() {
+                    coeffs.push(hex::decode(coeff)?);
+                }
+                Some(GroupInfo {
+                    threshold,
+                    nodes,
+                    dist_key: coeffs,
+                })
+            }
+        };
+        let public_key = hex::decode(&val.public_key)?;
         let val = Info {
-            public_key: err_at!(HexParse, hex::decode(&val.public_key))?,
-            period: time::Duration::from_secs(val.period),
-            genesis_time: time::UNIX_EPOCH + genesis_time,
-            hash: err_at!(HexParse, hex::decode(&val.hash))?,
-            group_hash: err_at!(HexParse, hex::decode(&val.group_hash))?,
+            scheme: match &val.scheme_id {
+                Some(scheme_id) => scheme_id.parse()?,
+                None => Scheme::from_public_key(&public_key),
+            },
+            public_key,
+            hash: hex::decode(&val.hash)?,
+            group_hash: hex::decode(&val.group_hash)?,
+            group,
+            ..Info::from_unix(val.genesis_time, val.period)?
         };
 
         Ok(val)
     }
 }
 
-#[derive(Deserialize)]
-struct RandomJson {
+impl From<Info> for InfoJson {
+    fn from(val: Info) -> Self {
+        let (genesis_time, period) = (val.genesis_unix(), val.period_secs());
+        let group = val.group.unwrap_or_default();
+        let dist_key: Vec<String> = group.dist_key.iter().map(hex::encode).collect();
+        InfoJson {
+            public_key: hex::encode(&val.public_key),
+            period,
+            genesis_time,
+            hash: hex::encode(&val.hash),
+            group_hash: hex::encode(&val.group_hash),
+            threshold: group.threshold,
+            nodes: group.nodes,
+            dist_key: if dist_key.is_empty() {
+                None
+            } else {
+                Some(dist_key)
+            },
+            scheme_id: Some(val.scheme.to_string()),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct RandomJson {
     round: u128,
     randomness: String,
     signature: String,
+    // absent for unchained scheme.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     previous_signature: String,
 }
 
@@ -321,28 +579,197 @@ impl TryFrom<RandomJson> for Random {
     type Error = Error;
 
     fn try_from(val: RandomJson) -> Result<Self> {
-        let psign = err_at!(HexParse, hex::decode(&val.previous_signature))?;
-        let val = Random {
+        let (randomness, psign) = (&val.randomness, &val.previous_signature);
+        Random::from_hex_parts(val.round, randomness, &val.signature, psign)
+    }
+}
+
+impl From<Random> for RandomJson {
+    fn from(val: Random) -> Self {
+        RandomJson {
             round: val.round,
-            randomness: err_at!(HexParse, hex::decode(&val.randomness))?,
-            signature: err_at!(HexParse, hex::decode(&val.signature))?,
-            previous_signature: psign,
+            randomness: val.randomness_hex(),
+            signature: val.signature_hex(),
+            previous_signature: val.previous_signature_hex(),
+        }
+    }
+}
+
+/// Budget on cumulative bytes downloaded by a client. It is shared by all
+/// the endpoints of a client and every copy of its state.
+#[derive(Clone, Default)]
+pub(crate) struct Budget {
+    limit: Option<usize>,
+    used: Arc<AtomicUsize>,
+}
+
+impl Budget {
+    pub(crate) fn new(limit: Option<usize>) -> Budget {
+        Budget {
+            limit,
+            used: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    pub(crate) fn to_remaining(&self) -> Option<usize> {
+        let used = self.used.load(SeqCst);
+        self.limit.map(|limit| limit.saturating_sub(used))
+    }
+
+    pub(crate) fn reset(&self) {
+        self.used.store(0, SeqCst)
+    }
+
+    pub(crate) fn check(&self) -> Result<()> {
+        match self.to_remaining() {
+            Some(0) => err_at!(IOError, msg: format!("byte budget exhausted")),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn spend(&self, n: usize) {
+        self.used.fetch_add(n, SeqCst);
+    }
+}
+
+type HttpClientKey = (
+    usize,
+    Option<time::Duration>,
+    Option<time::Duration>,
+    Option<reqwest::header::HeaderValue>,
+);
+
+/// http clients built for a client's endpoints, keyed by their pool size,
+/// timeouts and user-agent. Shared by every copy of a client's state, so
+/// that connection pools are reused across requests.
+#[derive(Clone, Default)]
+pub(crate) struct HttpClients(Arc<Mutex<HashMap<HttpClientKey, reqwest::Client>>>);
+
+/// Token-bucket limiting the rate of requests made by a client. It is
+/// shared by all the endpoints of a client and every copy of its state.
+#[derive(Clone, Default)]
+pub(crate) struct RateLimit {
+    per_second: Option<u32>,
+    // available tokens, negative when requests are queued up, and when
+    // they were last refilled.
+    bucket: Arc<Mutex<(f64, Option<time::SystemTime>)>>,
+}
+
+impl RateLimit {
+    pub(crate) fn new(per_second: Option<u32>) -> RateLimit {
+        let tokens = per_second.map(f64::from).unwrap_or_default();
+        RateLimit {
+            per_second,
+            bucket: Arc::new(Mutex::new((tokens, None))),
+        }
+    }
+
+    // take a token, waiting for it if the bucket is empty. Token is
+    // reserved before waiting, so that waiters are served in order.
+    pub(crate) async fn acquire(&self, clock: &dyn Clock) {
+        let rate = match self.per_second {
+            Some(n) if n > 0 => f64::from(n),
+            _ => return,
         };
+        let wait = {
+            let now = clock.now();
+            let mut bucket = match self.bucket.lock() {
+                Ok(bucket) => bucket,
+                Err(err) => err.into_inner(),
+            };
+            let (tokens, last) = &mut *bucket;
+            let refill = match last {
+                Some(last) => now.duration_since(*last).unwrap_or_default(),
+                None => time::Duration::default(),
+            };
+            match last {
+                Some(last) if now <= *last => (),
+                _ => *last = Some(now),
+            }
+            *tokens = (*tokens + refill.as_secs_f64() * rate).min(rate) - 1.0;
+            match *tokens {
+                tokens if tokens >= 0.0 => None,
+                tokens => Some(time::Duration::from_secs_f64(-tokens / rate)),
+            }
+        };
+        if let Some(wait) = wait {
+            clock.delay(wait).await
+        }
+    }
+}
 
-        Ok(val)
+// round-1 has no previous round, it is chained to the group_hash.
+pub(crate) fn verify_genesis(state: &State, r: &Random) -> Result<()> {
+    state.info.verify_genesis_with(r, state.verifier.as_ref())
+}
+
+// body is read in chunks, and refused as soon as it is known to be over
+// the byte budget, so a single response can't overrun the budget.
+async fn read_json<T>(state: &State, mut resp: reqwest::Response) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let status = resp.status();
+    if !status.is_success() {
+        let (status, url) = (status.as_u16(), resp.url().to_string());
+        return Err(Error::Http { status, url });
+    }
+
+    let over = |n: u64| match state.budget.to_remaining() {
+        Some(remaining) => n > (remaining as u64),
+        None => false,
+    };
+    if let Some(n) = resp.content_length().filter(|n| over(*n)) {
+        err_at!(IOError, msg: format!("byte budget exhausted by {} byte response", n))?
+    }
+
+    let mut body = vec![];
+    while let Some(chunk) = resp.chunk().await? {
+        body.extend_from_slice(&chunk);
+        if over(body.len() as u64) {
+            state.budget.spend(body.len());
+            err_at!(IOError, msg: format!("byte budget exhausted by response"))?
+        }
     }
+    state.budget.spend(body.len());
+    Ok(serde_json::from_slice(&body)?)
 }
 
+// use the configured http client if any, which shares its connection
+// pool across clones, else the one built for these settings, keeping up
+// to `max` idle connections per host. Clients are built once and reused
+// across requests, refer HttpClients.
 fn new_http_client(
+    state: &State,
     max: usize,
     agent: Option<reqwest::header::HeaderValue>,
 ) -> Result<reqwest::Client> {
+    if let Some(client) = &state.http_client {
+        return Ok(client.clone());
+    }
+
+    let key = (max, state.request_timeout, state.connect_timeout, agent);
+    let mut clients = err_at!(PoisonedLock, state.http_clients.0.lock())?;
+    if let Some(client) = clients.get(&key) {
+        return Ok(client.clone());
+    }
+
     let b = reqwest::Client::builder().pool_max_idle_per_host(max);
-    let b = match agent {
+    let b = match key.3.clone() {
         Some(agent) => b.user_agent(agent),
         None => b,
     };
-    err_at!(Invalid, b.build(), format!("http builder"))
+    let b = match state.request_timeout {
+        Some(timeout) => b.timeout(timeout),
+        None => b,
+    };
+    let b = match state.connect_timeout {
+        Some(timeout) => b.connect_timeout(timeout),
+        None => b,
+    };
+    let client = err_at!(Invalid, source: b.build(), format!("http builder"))?;
+    clients.insert(key, client.clone());
+    Ok(client)
 }
 
 #[cfg(test)]
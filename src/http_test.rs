@@ -1,11 +1,63 @@
 // TODO: Is it okay to use http calls to the league network in
 // unit-test case ? Or should we use a mock server ?
 
+use sha2::{Digest, Sha256};
+
 use super::*;
 
 #[test]
 fn test_base_url() {
     assert_eq!(Http::new_drand_api().to_base_url(), "https://api.drand.sh");
+    assert_eq!(
+        Http::new_drand_api2().to_base_url(),
+        "https://api2.drand.sh"
+    );
+    assert_eq!(
+        Http::new_drand_api3().to_base_url(),
+        "https://api3.drand.sh"
+    );
+    assert_eq!(
+        Http::new_cloudflare().to_base_url(),
+        "https://drand.cloudflare.com"
+    );
+}
+
+#[test]
+fn test_custom_url() {
+    let endp = Http::new_custom("https://drand.example.com/").unwrap();
+    assert_eq!(endp.to_base_url(), "https://drand.example.com");
+    let endp = Http::new_custom("http://127.0.0.1:8080/relay").unwrap();
+    assert_eq!(endp.to_base_url(), "http://127.0.0.1:8080/relay");
+
+    for url in [
+        "",
+        "drand.example.com",
+        "ftp://drand.example.com",
+        "https://",
+    ]
+    .iter()
+    {
+        match Http::new_custom(url) {
+            Err(Error::Invalid(_, _, _)) => (),
+            Err(err) => panic!("{:?} {}", url, err),
+            Ok(_) => panic!("{:?}", url),
+        }
+    }
+    assert!(Http::new_custom("https://drand.example.com/?x=1").is_err());
+}
+
+#[test]
+fn test_chain_url() {
+    let endp = Http::new_custom("https://drand.example.com/").unwrap();
+    let mut state = State::default();
+    assert_eq!(endp.to_chain_url(&state), "https://drand.example.com");
+
+    state.chain_hash = Some(vec![0xab, 0xcd]);
+    assert_eq!(endp.to_chain_url(&state), "https://drand.example.com/abcd");
+    assert_eq!(
+        make_url!("public", endp.to_chain_url(&state), 7),
+        "https://drand.example.com/abcd/public/7"
+    );
 }
 
 #[test]
@@ -66,7 +118,9 @@ fn test_do_get() {
     let mut endp = Http::new_drand_api();
     let client = reqwest::Client::new();
 
-    let r = rt.block_on(endp.do_get(&client, Some(1))).unwrap();
+    let r = rt
+        .block_on(endp.do_get(&State::default(), &client, Some(1)))
+        .unwrap();
 
     assert_eq!(r.round, 1);
     assert_eq!(
@@ -88,7 +142,9 @@ fn test_boot_phase1() {
     let mut rt = tokio::runtime::Runtime::new().unwrap();
     let mut endp = Http::new_drand_api();
 
-    let (info, _) = rt.block_on(endp.boot_phase1(None, None)).unwrap();
+    let (info, _) = rt
+        .block_on(endp.boot_phase1(&State::default(), None, None))
+        .unwrap();
     assert_eq!(
         hex::encode(info.hash),
         "8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce"
@@ -101,7 +157,9 @@ fn test_boot_phase1() {
     // root-of-trust
     let rot =
         hex::decode("8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce").unwrap();
-    let (info, _) = rt.block_on(endp.boot_phase1(Some(&rot), None)).unwrap();
+    let (info, _) = rt
+        .block_on(endp.boot_phase1(&State::default(), Some(&rot), None))
+        .unwrap();
     assert_eq!(
         hex::encode(info.hash.clone()),
         "8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce"
@@ -113,5 +171,246 @@ fn test_boot_phase1() {
 
     // invlaid root-of-trust
     let rot = &info.hash[1..];
-    assert!(rt.block_on(endp.boot_phase1(Some(rot), None)).is_err());
+    assert!(rt
+        .block_on(endp.boot_phase1(&State::default(), Some(rot), None))
+        .is_err());
+}
+
+#[test]
+fn test_info_group() {
+    let info = InfoJson {
+        public_key: "868f005eb8e6e4ca0a47c8a77ceaa5309a47978a7c71bc5cce96366b5d7a569937c529eeda66c7293784a9402801af31".to_string(),
+        period: 30,
+        genesis_time: 1595431050,
+        hash: "8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce".to_string(),
+        group_hash: "176f93498eac9ca337150b46d21dd58673ea4e3581185f869672e59fa4cb390a".to_string(),
+        threshold: None,
+        nodes: None,
+        dist_key: None,
+        scheme_id: None,
+    };
+    let val: Info = info.clone().try_into().unwrap();
+    assert_eq!(val.group, None);
+
+    let info = InfoJson {
+        threshold: Some(12),
+        nodes: Some(23),
+        dist_key: Some(vec![info.public_key.clone()]),
+        ..info
+    };
+    let val: Info = info.clone().try_into().unwrap();
+    let group = val.group.clone().unwrap();
+    assert_eq!(group.threshold, Some(12));
+    assert_eq!(group.nodes, Some(23));
+    assert_eq!(group.dist_key, vec![val.public_key.clone()]);
+
+    let back: Info = InfoJson::from(val.clone()).try_into().unwrap();
+    assert_eq!(back, val);
+
+    let info = InfoJson {
+        dist_key: Some(vec!["xyz".to_string()]),
+        ..info
+    };
+    assert!(Info::try_from(info).is_err());
+}
+
+#[test]
+fn test_budget() {
+    let budget = Budget::default();
+    assert_eq!(budget.to_remaining(), None);
+    budget.spend(1_000_000);
+    assert!(budget.check().is_ok());
+
+    let budget = Budget::new(Some(100));
+    let other = budget.clone();
+    budget.spend(60);
+    assert_eq!(other.to_remaining(), Some(40));
+    assert!(other.check().is_ok());
+
+    other.spend(60);
+    assert_eq!(budget.to_remaining(), Some(0));
+    assert!(budget.check().is_err());
+
+    budget.reset();
+    assert_eq!(other.to_remaining(), Some(100));
+    assert!(other.check().is_ok());
+}
+
+#[test]
+fn test_rate_limit() {
+    use std::{
+        future::{self, Future},
+        pin::Pin,
+        sync::Mutex,
+    };
+
+    // delays complete right away, advancing the clock, and are recorded.
+    struct MockClock(Mutex<(time::SystemTime, Vec<time::Duration>)>);
+
+    impl Clock for MockClock {
+        fn now(&self) -> time::SystemTime {
+            self.0.lock().unwrap().0
+        }
+
+        fn delay(&self, dur: time::Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+            let mut val = self.0.lock().unwrap();
+            val.0 += dur;
+            val.1.push(dur);
+            Box::pin(future::ready(()))
+        }
+    }
+
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let clock = MockClock(Mutex::new((time::UNIX_EPOCH, vec![])));
+
+    // no limit.
+    let limit = RateLimit::default();
+    rt.block_on(async {
+        for _ in 0..10 {
+            limit.acquire(&clock).await
+        }
+    });
+    assert!(clock.0.lock().unwrap().1.is_empty());
+
+    // burst of 2, later requests wait for their turn.
+    let limit = RateLimit::new(Some(2));
+    let other = limit.clone();
+    rt.block_on(async {
+        limit.acquire(&clock).await;
+        other.acquire(&clock).await;
+        limit.acquire(&clock).await;
+        other.acquire(&clock).await;
+    });
+    let half = time::Duration::from_millis(500);
+    assert_eq!(clock.0.lock().unwrap().1, vec![half, half]);
+
+    // bucket refills, up to the burst.
+    clock.0.lock().unwrap().0 += time::Duration::from_secs(10);
+    rt.block_on(async {
+        for _ in 0..3 {
+            limit.acquire(&clock).await
+        }
+    });
+    assert_eq!(clock.0.lock().unwrap().1, vec![half, half, half]);
+}
+
+#[test]
+fn test_http_clients() {
+    let count = |state: &State| state.http_clients.0.lock().unwrap().len();
+
+    // built once per settings, and shared by copies of the state.
+    let state = State::default();
+    new_http_client(&state, 4, None).unwrap();
+    new_http_client(&state.clone(), 4, None).unwrap();
+    assert_eq!(count(&state), 1);
+    new_http_client(&state, 2, None).unwrap();
+    let s = State {
+        request_timeout: Some(time::Duration::from_secs(1)),
+        ..state.clone()
+    };
+    new_http_client(&s, 4, None).unwrap();
+    assert_eq!(count(&state), 3);
+
+    // configured client is used as is.
+    let state = State {
+        http_client: Some(reqwest::Client::new()),
+        ..State::default()
+    };
+    new_http_client(&state, 4, None).unwrap();
+    assert_eq!(count(&state), 0);
+}
+
+#[test]
+fn test_verify_genesis() {
+    use crate::SignatureVerifier;
+
+    struct Mock;
+
+    impl SignatureVerifier for Mock {
+        fn verify(&self, _: &[u8], round: u64, psign: &[u8], _: &[u8]) -> Result<bool> {
+            Ok(round == 1 && psign == [0xaa; 32])
+        }
+    }
+
+    let state = State {
+        verifier: Arc::new(Mock),
+        info: Info {
+            group_hash: vec![0xaa; 32],
+            ..Info::default()
+        },
+        ..State::default()
+    };
+
+    let mut r = Random {
+        round: 1,
+        randomness: Sha256::digest(&[]).to_vec(),
+        signature: vec![],
+        previous_signature: vec![0xaa; 32],
+    };
+    verify_genesis(&state, &r).unwrap();
+
+    r.previous_signature = vec![0; 96];
+    match verify_genesis(&state, &r) {
+        Err(Error::NotSecure(_, _, _)) => (),
+        res => panic!("unexpected {:?}", res),
+    }
+}
+
+#[test]
+fn test_info_scheme() {
+    let data = r#"{
+        "public_key": "8200fc249deb0148eb918d6e213980c5d01acd7fc251900d9260136da3b54836ce125172399ddc69c4e3e11429b62c11",
+        "period": 3,
+        "genesis_time": 1651677099,
+        "hash": "7672797f548f3f4748ac4bf3352fc6c6b6468c9ad40ad456a397545c6e2df5bf",
+        "groupHash": "65083634d852ae169e21b6ce5f0410be9ed4cc679b9970236f7875cff667e13d",
+        "schemeID": "pedersen-bls-unchained",
+        "metadata": {"beaconID": "testnet-unchained-3s"}
+    }"#;
+    let info: Info = serde_json::from_str::<InfoJson>(data)
+        .unwrap()
+        .try_into()
+        .unwrap();
+    assert_eq!(info.scheme, Scheme::Unchained);
+    let back: Info = InfoJson::from(info.clone()).try_into().unwrap();
+    assert_eq!(back, info);
+
+    // quicknet, signatures on G1.
+    let quicknet = r#"{
+        "public_key": "83cf0f2896adee7eb8b5f01fcad3912212c437e0073e911fb90022d3e760183c8c4b450b6a0a6c3ac6a5776a2d1064510d1fec758c921cc22b0e17e63aaf4bcb5ed66304de9cf809bd274ca73bab4af5a6e9c76a4bc09e76eae8991ef5ece45a",
+        "period": 3,
+        "genesis_time": 1692803367,
+        "hash": "52db9ba70e0cc0f6eaf7803dd07447a1f5477735fd3f661792ba94600c84e971",
+        "groupHash": "f477d5c89f21a17c863a7f937c6a6d15859414d2be09cd448d4279af331c5d3e",
+        "schemeID": "bls-unchained-g1-rfc9380",
+        "metadata": {"beaconID": "quicknet"}
+    }"#;
+    let info: Info = serde_json::from_str::<InfoJson>(quicknet)
+        .unwrap()
+        .try_into()
+        .unwrap();
+    assert_eq!(info.scheme, Scheme::UnchainedG1);
+    assert_eq!(info.public_key.len(), 96);
+    let back: Info = InfoJson::from(info.clone()).try_into().unwrap();
+    assert_eq!(back, info);
+
+    // lacking schemeID, inferred from the public-key's size.
+    let legacy = quicknet.replace(r#""schemeID": "bls-unchained-g1-rfc9380","#, "");
+    let info: Info = serde_json::from_str::<InfoJson>(&legacy)
+        .unwrap()
+        .try_into()
+        .unwrap();
+    assert_eq!(info.scheme, Scheme::UnchainedG1);
+
+    let data = data.replace("pedersen-bls-unchained", "no-such-scheme");
+    let info: InfoJson = serde_json::from_str(&data).unwrap();
+    assert!(Info::try_from(info).is_err());
+
+    // unchained rounds carry no previous_signature.
+    let data = r#"{"round": 1, "randomness": "00", "signature": "01"}"#;
+    let r: Random = serde_json::from_str::<RandomJson>(data)
+        .unwrap()
+        .try_into()
+        .unwrap();
+    assert!(r.previous_signature.is_empty());
 }
@@ -5,14 +5,26 @@ mod util;
 mod client;
 mod core;
 mod endpoints;
+#[cfg(feature = "grpc")]
+mod grpc;
 mod http;
+mod store;
 mod verify;
 
-pub use crate::client::Client;
-pub use crate::core::{Config, Error, Info, Random, Result};
+pub use crate::client::{Client, ClientBuilder, Watch, WatchHandle, WatchVerified};
+pub use crate::core::{
+    find_gaps, ChainBreak, Clock, ClockSkew, Config, EndpointConfig, Error, GroupInfo, Info,
+    Mismatch, NoopObserver, Observer, OnchainProof, Random, Result, Scheme, SelectionStrategy,
+    Source, SystemClock, Timed, VerifiedRandom, VerifyFailure,
+};
+pub use crate::endpoints::ClientState;
+pub use crate::store::{CheckpointStore, FileCheckpointStore};
+pub use crate::verify::{verify_offline, verify_stream, DrandVerifier, SignatureVerifier};
 
 const MAINNET_CHAIN_HASH: &'static str =
     "8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce";
+const QUICKNET_CHAIN_HASH: &str =
+    "52db9ba70e0cc0f6eaf7803dd07447a1f5477735fd3f661792ba94600c84e971";
 
 // Trait for DrandClient, must eventually move to Client type.
 trait DrandClient {
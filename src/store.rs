@@ -0,0 +1,73 @@
+use std::{fmt, fs, path};
+
+use crate::Random;
+
+/// Trait to persist the client's check_point across restarts, refer
+/// [Config::set_checkpoint_store].
+///
+/// A `secure` client saves its check_point every time it advances. On
+/// restart, the loaded check_point serves as the trusted anchor, the
+/// same as [Config::check_point], so that with `determinism` the client
+/// resumes by chain-verifying from its last trusted round rather than
+/// from round-1 or by trusting the latest round. Store the check_point
+/// where it can't be tampered with.
+///
+/// [Config::set_checkpoint_store]: crate::Config::set_checkpoint_store
+/// [Config::check_point]: crate::Config::check_point
+pub trait CheckpointStore: Send + Sync {
+    /// Return the last saved check_point, None if there is none.
+    fn load(&self) -> Option<Random>;
+
+    /// Save `check_point`, replacing the previously saved one.
+    fn save(&self, check_point: &Random);
+}
+
+impl fmt::Debug for dyn CheckpointStore {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CheckpointStore")
+    }
+}
+
+/// Store the check_point as a file, in drand's JSON format, refer
+/// [Random::to_json]. Saving is best effort, a failed save leaves the
+/// previously saved check_point in place.
+#[derive(Clone, Debug)]
+pub struct FileCheckpointStore {
+    path: path::PathBuf,
+}
+
+impl FileCheckpointStore {
+    /// Create a store backed by the file at `path`, the file is created
+    /// on the first save.
+    pub fn new<P: AsRef<path::Path>>(path: P) -> FileCheckpointStore {
+        FileCheckpointStore {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    fn to_temp_path(&self) -> path::PathBuf {
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(".tmp");
+        self.path.with_file_name(name)
+    }
+}
+
+impl CheckpointStore for FileCheckpointStore {
+    fn load(&self) -> Option<Random> {
+        let data = fs::read_to_string(&self.path).ok()?;
+        Random::from_json(&data).ok()
+    }
+
+    // write to a temporary file and rename it over, so that a crash midway
+    // doesn't leave behind a torn file.
+    fn save(&self, check_point: &Random) {
+        let temp = self.to_temp_path();
+        if fs::write(&temp, check_point.to_json()).is_ok() {
+            fs::rename(&temp, &self.path).ok();
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "store_test.rs"]
+mod store_test;
@@ -0,0 +1,29 @@
+use super::*;
+
+#[test]
+fn test_file_checkpoint_store() {
+    let path = std::env::temp_dir().join(format!("drand-checkpoint-{}", std::process::id()));
+    let store = FileCheckpointStore::new(&path);
+    assert_eq!(store.load(), None);
+
+    let mut r = Random {
+        round: 10,
+        randomness: vec![1; 32],
+        signature: vec![2; 96],
+        previous_signature: vec![3; 96],
+    };
+    store.save(&r);
+    assert_eq!(store.load(), Some(r.clone()));
+    assert_eq!(fs::read_to_string(&path).unwrap(), r.to_json());
+
+    r.round = 11;
+    store.save(&r);
+    assert_eq!(store.load(), Some(r));
+    assert!(!store.to_temp_path().exists());
+
+    // a torn or garbled file is as good as none.
+    fs::write(&path, "{\"round\":").unwrap();
+    assert_eq!(store.load(), None);
+
+    fs::remove_file(&path).unwrap();
+}
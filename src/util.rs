@@ -22,6 +22,21 @@
 /// err_at!(Invalid, std::fs::read(file_path), format!("read failed"));
 /// ```
 ///
+/// To retain the originating error as the [source] of Error, it must be
+/// `Send + Sync + 'static`:
+///
+/// ```ignore
+/// use crate::Error;
+/// err_at!(IOError, source: std::fs::read(file_path));
+/// err_at!(IOError, source: std::fs::read(file_path), format!("read failed"));
+/// ```
+///
+/// Errors from `reqwest`, `serde_json` and `hex` convert via `?` into
+/// `IOError`, `JsonParse` and `HexParse` respectively, retaining the
+/// source and prefixed with the `?`'s call site, same as the above form.
+///
+/// [source]: std::error::Error::source
+///
 #[macro_export]
 macro_rules! err_at {
     ($e:expr) => {{
@@ -38,14 +53,34 @@ macro_rules! err_at {
     }};
     ($v:ident, msg:$m:expr) => {{
         let prefix = format!("{}:{}", file!(), line!());
-        Err(Error::$v(prefix, format!("{}", $m)))
+        Err(Error::$v(prefix, format!("{}", $m), None))
     }};
+    ($v:ident, source:$e:expr) => {
+        match $e {
+            Ok(val) => Ok(val),
+            Err(err) => {
+                let prefix = format!("{}:{}", file!(), line!());
+                let msg = format!("{}", err);
+                Err(Error::$v(prefix, msg, Some(std::sync::Arc::new(err))))
+            }
+        }
+    };
+    ($v:ident, source:$e:expr, $m:expr) => {
+        match $e {
+            Ok(val) => Ok(val),
+            Err(err) => {
+                let prefix = format!("{}:{}", file!(), line!());
+                let msg = format!("{} {}", $m, err);
+                Err(Error::$v(prefix, msg, Some(std::sync::Arc::new(err))))
+            }
+        }
+    };
     ($v:ident, $e:expr) => {
         match $e {
             Ok(val) => Ok(val),
             Err(err) => {
                 let prefix = format!("{}:{}", file!(), line!());
-                Err(Error::$v(prefix, format!("{}", err)))
+                Err(Error::$v(prefix, format!("{}", err), None))
             }
         }
     };
@@ -54,8 +89,31 @@ macro_rules! err_at {
             Ok(val) => Ok(val),
             Err(err) => {
                 let prefix = format!("{}:{}", file!(), line!());
-                Err(Error::$v(prefix, format!("{} {}", $m, err)))
+                Err(Error::$v(prefix, format!("{} {}", $m, err), None))
             }
         }
     };
 }
+
+/// Emit a tracing event at `level`, same arguments as tracing's event
+/// macros, like `trace_event!(debug, round = 1, "fetched")`. Compiles
+/// to nothing without the `tracing` feature, arguments are not evaluated.
+macro_rules! trace_event {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        tracing::$level!($($arg)+);
+    };
+}
+
+/// Instrument future `fut` with an info-level span, same arguments as
+/// tracing's span macros, like `instrument!(fut, "get", round = 1)`.
+/// Return `fut` as is without the `tracing` feature.
+macro_rules! instrument {
+    ($fut:expr, $($span:tt)+) => {{
+        #[cfg(feature = "tracing")]
+        let fut = tracing::Instrument::instrument($fut, tracing::info_span!($($span)+));
+        #[cfg(not(feature = "tracing"))]
+        let fut = $fut;
+        fut
+    }};
+}
@@ -1,27 +1,300 @@
-use crate::{Error, Random, Result};
-
-pub(crate) fn verify_chain(pk: &[u8], previous_signature: &[u8], curr: &Random) -> Result<bool> {
-    if previous_signature != curr.previous_signature.as_slice() {
-        let s = hex::encode(previous_signature);
-        let p = hex::encode(&curr.previous_signature);
-        err_at!(NotSecure, msg: format!("mismatch chain {:?} != {:?}", s, p))?
-    }
-
-    let pk = {
-        let mut bytes: [u8; 48] = [0_u8; 48];
-        bytes[..].clone_from_slice(&pk);
-        err_at!(NotSecure, drand_verify::g1_from_fixed(bytes))?
-    };
-
-    Ok(err_at!(
-        NotSecure,
-        drand_verify::verify(
-            &pk,
-            curr.round as u64,
-            &curr.previous_signature,
-            &curr.signature
-        )
-    )?)
+use futures::stream::{Stream, StreamExt};
+
+use std::{convert::TryFrom, fmt, sync::Arc};
+
+use crate::{ChainBreak, Error, Info, Random, Result, Scheme};
+
+/// Trait to plug in an alternate BLS verification backend, like `blst`
+/// for speed or a hardware-backed verifier. Refer [Config::set_verifier].
+///
+/// drand's chained scheme signs SHA-256 of `previous_signature || round`,
+/// with the signature on G2 and the public-key on G1. Unchained scheme
+/// signs SHA-256 of `round` alone, for which `previous_signature` is
+/// handed over empty. Implementations are handed the round and its
+/// previous_signature rather than the hashed message, so that they can
+/// map the message to the curve as their library requires.
+///
+/// [Config::set_verifier]: crate::Config::set_verifier
+pub trait SignatureVerifier: Send + Sync {
+    /// Verify `signature` for `round` chained to `previous_signature`
+    /// against the group's `public_key`. Return false if the signature
+    /// doesn't verify, and an error if the inputs are malformed.
+    fn verify(
+        &self,
+        public_key: &[u8],
+        round: u64,
+        previous_signature: &[u8],
+        signature: &[u8],
+    ) -> Result<bool>;
+
+    /// Verify `signature`, on G1, for `round` against the group's
+    /// `public_key`, on G2, as per `bls-unchained-g1-rfc9380` scheme. The
+    /// message, SHA-256 of `round`, is hashed to G1 with the domain
+    /// separation tag `BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_NUL_`.
+    /// Default implementation fails with `Error::Invalid`, for backends
+    /// that don't support the scheme.
+    fn verify_g1(&self, _public_key: &[u8], round: u64, _signature: &[u8]) -> Result<bool> {
+        err_at!(Invalid, msg: format!("round {}, signature on G1 unsupported", round))
+    }
+
+    /// Verify `signature` for an arbitrary `message` against `public_key`,
+    /// bypassing the message construction of drand's schemes. `message`
+    /// is hashed to the signature's group as is, callers are responsible
+    /// for constructing it, like SHA-256 of `previous_signature || round`
+    /// for the chained scheme. Meant for experimenting with schemes that
+    /// this crate doesn't model. Default implementation fails with
+    /// `Error::Invalid`, for backends that don't support it.
+    fn verify_message(
+        &self,
+        _public_key: &[u8],
+        _message: &[u8],
+        _signature: &[u8],
+    ) -> Result<bool> {
+        err_at!(Invalid, msg: format!("verify_message unsupported"))
+    }
+}
+
+impl fmt::Debug for dyn SignatureVerifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SignatureVerifier")
+    }
+}
+
+/// Default verification backend, using the pure-rust `drand-verify` for
+/// `pedersen-bls-chained` and `pedersen-bls-unchained` schemes, and the
+/// pure-rust `bls12_381` for [Scheme::UnchainedG1].
+#[derive(Clone, Debug, Default)]
+pub struct DrandVerifier;
+
+impl SignatureVerifier for DrandVerifier {
+    fn verify(
+        &self,
+        public_key: &[u8],
+        round: u64,
+        previous_signature: &[u8],
+        signature: &[u8],
+    ) -> Result<bool> {
+        let pk = {
+            let mut bytes: [u8; 48] = [0_u8; 48];
+            if public_key.len() != bytes.len() {
+                let n = public_key.len();
+                err_at!(NotSecure, msg: format!("public-key size {}", n))?
+            }
+            bytes[..].clone_from_slice(public_key);
+            err_at!(NotSecure, drand_verify::g1_from_fixed(bytes))?
+        };
+
+        Ok(err_at!(
+            NotSecure,
+            drand_verify::verify(&pk, round, previous_signature, signature)
+        )?)
+    }
+
+    fn verify_g1(&self, public_key: &[u8], round: u64, signature: &[u8]) -> Result<bool> {
+        use sha2::{Digest, Sha256};
+
+        let msg = Sha256::digest(&round.to_be_bytes());
+        verify_on_g1(public_key, &msg, signature)
+    }
+
+    fn verify_message(&self, public_key: &[u8], message: &[u8], signature: &[u8]) -> Result<bool> {
+        match public_key.len() {
+            96 => verify_on_g1(public_key, message, signature),
+            _ => verify_on_g2(public_key, message, signature),
+        }
+    }
+}
+// domain separation tag for hashing messages to G1, as per drand's
+// `bls-unchained-g1-rfc9380` scheme.
+const DST_G1: &[u8] = b"BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_NUL_";
+// domain separation tag for hashing messages to G2, as per drand's
+// pedersen schemes.
+const DST_G2: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+
+// verify `signature`, on G1, for `msg` hashed to G1 against `public_key`,
+// on G2.
+fn verify_on_g1(public_key: &[u8], msg: &[u8], signature: &[u8]) -> Result<bool> {
+    use bls12_381::{
+        hash_to_curve::{ExpandMsgXmd, HashToCurve},
+        pairing, G1Affine, G1Projective, G2Affine,
+    };
+    use sha2::Sha256;
+
+    let pk: Option<G2Affine> = match <[u8; 96]>::try_from(public_key) {
+        Ok(bytes) => G2Affine::from_compressed(&bytes).into(),
+        Err(_) => None,
+    };
+    let pk = match pk {
+        Some(pk) => pk,
+        None => err_at!(NotSecure, msg: format!("public-key not on G2"))?,
+    };
+    let sign: Option<G1Affine> = match <[u8; 48]>::try_from(signature) {
+        Ok(bytes) => G1Affine::from_compressed(&bytes).into(),
+        Err(_) => None,
+    };
+    let sign = match sign {
+        Some(sign) => sign,
+        None => err_at!(NotSecure, msg: format!("signature not on G1"))?,
+    };
+
+    let point = <G1Projective as HashToCurve<ExpandMsgXmd<Sha256>>>::hash_to_curve(msg, DST_G1);
+    Ok(pairing(&sign, &G2Affine::generator()) == pairing(&G1Affine::from(point), &pk))
+}
+
+// verify `signature`, on G2, for `msg` hashed to G2 against `public_key`,
+// on G1.
+fn verify_on_g2(public_key: &[u8], msg: &[u8], signature: &[u8]) -> Result<bool> {
+    use bls12_381::{
+        hash_to_curve::{ExpandMsgXmd, HashToCurve},
+        pairing, G1Affine, G2Affine, G2Projective,
+    };
+    use sha2::Sha256;
+
+    let pk: Option<G1Affine> = match <[u8; 48]>::try_from(public_key) {
+        Ok(bytes) => G1Affine::from_compressed(&bytes).into(),
+        Err(_) => None,
+    };
+    let pk = match pk {
+        Some(pk) => pk,
+        None => err_at!(NotSecure, msg: format!("public-key not on G1"))?,
+    };
+    let sign: Option<G2Affine> = match <[u8; 96]>::try_from(signature) {
+        Ok(bytes) => G2Affine::from_compressed(&bytes).into(),
+        Err(_) => None,
+    };
+    let sign = match sign {
+        Some(sign) => sign,
+        None => err_at!(NotSecure, msg: format!("signature not on G2"))?,
+    };
+
+    let point = <G2Projective as HashToCurve<ExpandMsgXmd<Sha256>>>::hash_to_curve(msg, DST_G2);
+    Ok(pairing(&G1Affine::generator(), &sign) == pairing(&pk, &G2Affine::from(point)))
+}
+
+pub(crate) fn default_verifier() -> Arc<dyn SignatureVerifier> {
+    Arc::new(DrandVerifier)
+}
+
+// verify `curr`'s randomness, its link to `previous_signature` and its
+// signature. Rounds of unchained scheme don't link, only their signature
+// is verified.
+pub(crate) fn verify_chain(
+    verifier: &dyn SignatureVerifier,
+    info: &Info,
+    previous_signature: &[u8],
+    curr: &Random,
+) -> Result<bool> {
+    curr.check_randomness()?;
+    let psign: &[u8] = match info.scheme {
+        Scheme::Chained if previous_signature != curr.previous_signature.as_slice() => {
+            let brk = ChainBreak {
+                round: curr.round,
+                expected: previous_signature.to_vec(),
+                got: curr.previous_signature.clone(),
+            };
+            err_at!(NotSecure, source: Err(brk))?
+        }
+        Scheme::Chained => &curr.previous_signature,
+        Scheme::Unchained | Scheme::UnchainedG1 => &[],
+    };
+
+    verify_signature(verifier, info, curr.round as u64, psign, &curr.signature)
+}
+
+// public-key and signature are on opposite groups, compressed points being
+// 48 bytes on G1 and 96 bytes on G2. Catches a scheme inferred wrongly, for
+// hash-info lacking `schemeID`, before the costlier pairing. Keys of other
+// sizes are left to the verifier.
+fn check_groups(public_key: &[u8], signature: &[u8]) -> Result<()> {
+    match (public_key.len(), signature.len()) {
+        (48, 96) | (96, 48) => Ok(()),
+        (48, n) | (96, n) => {
+            let m = public_key.len();
+            err_at!(NotSecure, msg: format!("signature size {} for public-key size {}", n, m))
+        }
+        _ => Ok(()),
+    }
+}
+
+// verify `signature` for `round` in the pairing group of `info`'s scheme,
+// signatures on G2 for pedersen schemes and on G1 for rfc9380 scheme.
+pub(crate) fn verify_signature(
+    verifier: &dyn SignatureVerifier,
+    info: &Info,
+    round: u64,
+    previous_signature: &[u8],
+    signature: &[u8],
+) -> Result<bool> {
+    let pk = &info.public_key;
+    check_groups(pk, signature)?;
+    match info.scheme {
+        Scheme::Chained | Scheme::Unchained => {
+            verifier.verify(pk, round, previous_signature, signature)
+        }
+        Scheme::UnchainedG1 => verifier.verify_g1(pk, round, signature),
+    }
+}
+
+/// Verify `round`, obtained out-of-band, against trusted hash-info
+/// `info` without any network call. Randomness is checked to be SHA-256
+/// of the signature, previous_signature to be well formed, round-1 being
+/// linked to `info`'s group_hash, and the signature to verify against
+/// `info`'s public key using [DrandVerifier]. Later rounds can't be
+/// linked without their previous round, but their previous_signature is
+/// covered by the signature. Return `Error::NotSecure` if a check fails.
+pub fn verify_offline(info: &Info, round: &Random) -> Result<()> {
+    round.check_randomness()?;
+    round.check_previous_signature_wellformed(info)?;
+    round.verify_with(info, &DrandVerifier)
+}
+
+/// Verify a chain of rounds as they flow through `stream`, without
+/// holding on to them. Rounds must be consecutive, each is checked for
+/// its randomness, its link with the previous round and its signature
+/// against `info`'s public key, unchained rounds aren't linked. Only the
+/// previous round is retained, so arbitrarily long chains can be
+/// verified in constant memory.
+///
+/// If the first round is round-1 it is linked to `info`'s group_hash,
+/// otherwise its previous_signature is taken on trust. Return the count
+/// of verified rounds, stop at the first error.
+pub async fn verify_stream<S>(
+    verifier: &dyn SignatureVerifier,
+    info: &Info,
+    stream: S,
+) -> Result<u128>
+where
+    S: Stream<Item = Result<Random>>,
+{
+    futures::pin_mut!(stream);
+
+    let mut prev: Option<Random> = None;
+    let mut n = 0;
+    while let Some(curr) = stream.next().await {
+        let curr = curr?;
+        let previous_signature = match &prev {
+            Some(prev) if curr.round != prev.round + 1 => {
+                err_at!(Invalid, msg: format!("{} does not follow {}", curr, prev))?
+            }
+            Some(prev) => prev.signature.as_slice(),
+            None if curr.round == 1 => info.group_hash.as_slice(),
+            None => curr.previous_signature.as_slice(),
+        };
+        if !verify_chain(verifier, info, previous_signature, &curr)? {
+            err_at!(NotSecure, msg: format!("fail verify {}", curr))?
+        }
+
+        n += 1;
+        prev = Some(curr);
+    }
+
+    Ok(n)
+}
+
+// check whether `data` is a compressed point on G2, which is where chained
+// drand-group signatures live.
+pub(crate) fn is_g2_point(data: &[u8]) -> bool {
+    drand_verify::g2_from_variable(data).is_ok()
 }
 
 #[cfg(test)]
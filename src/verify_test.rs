@@ -1,29 +1,36 @@
 // TODO: Is it okay to use http calls to the league network in
 // unit-test case ? Or should we use a mock server ?
 
+use sha2::{Digest, Sha256};
+
 use super::*;
 
 #[test]
 fn test_verify() {
-    use crate::http::Http;
+    use crate::{endpoints::State, http::Http};
 
     let mut rt = tokio::runtime::Runtime::new().unwrap();
 
     let mut endp = Http::new_drand_api();
     let client = reqwest::Client::new();
 
-    let (info, _) = rt.block_on(endp.boot_phase1(None, None)).unwrap();
-    let r1 = rt.block_on(endp.do_get(&client, Some(1))).unwrap();
-    let r2 = rt.block_on(endp.do_get(&client, Some(2))).unwrap();
+    let (info, _) = rt
+        .block_on(endp.boot_phase1(&State::default(), None, None))
+        .unwrap();
+    let r1 = rt
+        .block_on(endp.do_get(&State::default(), &client, Some(1)))
+        .unwrap();
+    let r2 = rt
+        .block_on(endp.do_get(&State::default(), &client, Some(2)))
+        .unwrap();
 
-    assert!(verify_chain(&info.public_key, &info.group_hash, &r1).unwrap());
-    assert!(verify_chain(&info.public_key, &r1.signature, &r2).unwrap());
+    let verifier = DrandVerifier;
+    assert!(verify_chain(&verifier, &info, &info.group_hash, &r1).unwrap());
+    assert!(verify_chain(&verifier, &info, &r1.signature, &r2).unwrap());
 }
 
 #[test]
 fn test_randomness() {
-    use sha2::{Digest, Sha256};
-
     let mut hasher = Sha256::default();
 
     let signature = hex::decode("8d61d9100567de44682506aea1a7a6fa6e5491cd27a0a0ed349ef6910ac5ac20ff7bc3e09d7c046566c9f7f3c6f3b10104990e7cb424998203d8f7de586fb7fa5f60045417a432684f85093b06ca91c769f0e7ca19268375e659c2a2352b4655").unwrap();
@@ -34,3 +41,259 @@ fn test_randomness() {
         hex::encode(hasher.finalize().to_vec())
     );
 }
+
+#[test]
+fn test_custom_verifier() {
+    use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+
+    struct Mock(AtomicUsize);
+
+    impl SignatureVerifier for Mock {
+        fn verify(&self, _: &[u8], round: u64, _: &[u8], _: &[u8]) -> Result<bool> {
+            self.0.fetch_add(1, SeqCst);
+            Ok(round == 2)
+        }
+    }
+
+    let verifier = Mock(AtomicUsize::new(0));
+    let mut r = Random {
+        round: 2,
+        randomness: Sha256::digest(&[]).to_vec(),
+        signature: vec![],
+        previous_signature: vec![1, 2, 3],
+    };
+    assert!(verify_chain(&verifier, &Info::default(), &[1, 2, 3], &r).unwrap());
+    r.round = 3;
+    assert!(!verify_chain(&verifier, &Info::default(), &[1, 2, 3], &r).unwrap());
+    // chain mismatch fails before calling the backend.
+    assert!(verify_chain(&verifier, &Info::default(), &[3, 2, 1], &r).is_err());
+    assert_eq!(verifier.0.load(SeqCst), 2);
+
+    let mut config = crate::Config::default();
+    config.set_verifier(Box::new(Mock(AtomicUsize::new(0))));
+    let state: crate::endpoints::State = config.into();
+    assert!(!verify_chain(state.verifier.as_ref(), &Info::default(), &[1, 2, 3], &r).unwrap());
+
+    // public-key and signature shall be on opposite groups.
+    let info = Info {
+        public_key: vec![0; 48],
+        ..Info::default()
+    };
+    for (size, ok) in [(96, true), (48, false)].iter() {
+        let res = verify_signature(&verifier, &info, 2, &[], &vec![0; *size]);
+        assert_eq!(res.is_ok(), *ok, "{}", size);
+    }
+    let info = Info {
+        public_key: vec![0; 96],
+        scheme: Scheme::UnchainedG1,
+        ..Info::default()
+    };
+    let res = verify_signature(&verifier, &info, 2, &[], &[0; 96]);
+    assert!(matches!(res, Err(Error::NotSecure(_, _, _))));
+}
+
+#[test]
+fn test_verify_g1() {
+    // quicknet's public-key and the signature of `/public/1000`.
+    let public_key = Info::quicknet_default().public_key;
+    let signature = hex::decode("b44679b9a59af2ec876b1a6b1ad52ea9b1615fc3982b19576350f93447cb1125e342b73a8dd2bacbe47e4b6b63ed5e39").unwrap();
+
+    let verifier = DrandVerifier;
+    assert!(verifier.verify_g1(&public_key, 1000, &signature).unwrap());
+    assert!(!verifier.verify_g1(&public_key, 1001, &signature).unwrap());
+
+    // malformed inputs are errors, not a failed verification.
+    let mut bad = signature.clone();
+    bad[0] ^= 0x80;
+    for (pk, sign) in [
+        (&public_key[..48], &signature[..]),
+        (&public_key[..], &bad[..]),
+    ]
+    .iter()
+    {
+        match verifier.verify_g1(pk, 1000, sign) {
+            Err(Error::NotSecure(_, _, _)) => (),
+            res => panic!("unexpected {:?}", res),
+        }
+    }
+}
+
+#[test]
+fn test_verify_message() {
+    let verifier = DrandVerifier;
+
+    // mainnet's `/public/1`, signing SHA-256 of group_hash || round.
+    let public_key = hex::decode("868f005eb8e6e4ca0a47c8a77ceaa5309a47978a7c71bc5cce96366b5d7a569937c529eeda66c7293784a9402801af31").unwrap();
+    let group_hash =
+        hex::decode("176f93498eac9ca337150b46d21dd58673ea4e3581185f869672e59fa4cb390a").unwrap();
+    let signature = hex::decode("8d61d9100567de44682506aea1a7a6fa6e5491cd27a0a0ed349ef6910ac5ac20ff7bc3e09d7c046566c9f7f3c6f3b10104990e7cb424998203d8f7de586fb7fa5f60045417a432684f85093b06ca91c769f0e7ca19268375e659c2a2352b4655").unwrap();
+    let mut hasher = Sha256::default();
+    hasher.update(&group_hash);
+    hasher.update(1_u64.to_be_bytes());
+    let message = hasher.finalize().to_vec();
+    assert!(verifier
+        .verify_message(&public_key, &message, &signature)
+        .unwrap());
+    // round's digest is the message, not the round itself.
+    assert!(!verifier
+        .verify_message(&public_key, &1_u64.to_be_bytes(), &signature)
+        .unwrap());
+
+    // quicknet's `/public/1000`, signing SHA-256 of round.
+    let public_key = Info::quicknet_default().public_key;
+    let signature = hex::decode("b44679b9a59af2ec876b1a6b1ad52ea9b1615fc3982b19576350f93447cb1125e342b73a8dd2bacbe47e4b6b63ed5e39").unwrap();
+    let message = Sha256::digest(&1000_u64.to_be_bytes());
+    assert!(verifier
+        .verify_message(&public_key, &message, &signature)
+        .unwrap());
+    let message = Sha256::digest(&1001_u64.to_be_bytes());
+    assert!(!verifier
+        .verify_message(&public_key, &message, &signature)
+        .unwrap());
+
+    // signature on the key's group is malformed.
+    match verifier.verify_message(&public_key, &message, &public_key) {
+        Err(Error::NotSecure(_, _, _)) => (),
+        res => panic!("unexpected {:?}", res),
+    }
+}
+
+#[test]
+fn test_verify_stream() {
+    use futures::{executor::block_on, stream};
+
+    struct Mock;
+
+    impl SignatureVerifier for Mock {
+        fn verify(&self, _: &[u8], round: u64, _: &[u8], _: &[u8]) -> Result<bool> {
+            Ok(round != 13)
+        }
+    }
+
+    let info = Info {
+        group_hash: vec![0xaa; 32],
+        ..Info::default()
+    };
+    let chain = |from: u128, till: u128| -> Vec<Result<Random>> {
+        let mut previous_signature = if from == 1 {
+            info.group_hash.clone()
+        } else {
+            vec![0xbb; 96]
+        };
+        let mut rounds = vec![];
+        for round in from..till {
+            let signature = vec![round as u8; 96];
+            rounds.push(Ok(Random {
+                round,
+                randomness: Sha256::digest(&signature).to_vec(),
+                signature: signature.clone(),
+                previous_signature,
+            }));
+            previous_signature = signature;
+        }
+        rounds
+    };
+
+    let n = block_on(verify_stream(&Mock, &info, stream::iter(chain(1, 11)))).unwrap();
+    assert_eq!(n, 10);
+    let n = block_on(verify_stream(&Mock, &info, stream::iter(chain(5, 10)))).unwrap();
+    assert_eq!(n, 5);
+    let n = block_on(verify_stream(&Mock, &info, stream::iter(vec![]))).unwrap();
+    assert_eq!(n, 0);
+
+    // bad signature
+    let res = block_on(verify_stream(&Mock, &info, stream::iter(chain(10, 15))));
+    assert!(matches!(res, Err(Error::NotSecure(_, _, _))));
+
+    // broken link
+    let mut rounds = chain(1, 5);
+    if let Ok(r) = &mut rounds[2] {
+        r.previous_signature = vec![0; 96];
+    }
+    let err = block_on(verify_stream(&Mock, &info, stream::iter(rounds))).unwrap_err();
+    let brk = ChainBreak {
+        round: 3,
+        expected: vec![2; 96],
+        got: vec![0; 96],
+    };
+    assert_eq!(err.to_chain_break(), Some(&brk));
+    assert!(err.to_string().ends_with(
+        "chain broken at round 3, previous_signature 0202020202020202.. != 0000000000000000.."
+    ));
+
+    // broken link to group_hash
+    let mut rounds = chain(1, 5);
+    if let Ok(r) = &mut rounds[0] {
+        r.previous_signature = vec![0; 32];
+    }
+    let err = block_on(verify_stream(&Mock, &info, stream::iter(rounds))).unwrap_err();
+    assert_eq!(err.to_chain_break().map(|b| b.round), Some(1));
+    assert_eq!(
+        err.to_chain_break().map(|b| &b.expected),
+        Some(&info.group_hash)
+    );
+
+    // gap
+    let mut rounds = chain(1, 5);
+    let _gap = rounds.remove(1);
+    let res = block_on(verify_stream(&Mock, &info, stream::iter(rounds)));
+    assert!(matches!(res, Err(Error::Invalid(_, _, _))));
+
+    // stream error
+    let mut rounds = chain(1, 5);
+    let err = Error::IOError("test".to_string(), "fetch failed".to_string(), None);
+    rounds.insert(2, Err(err));
+    let res = block_on(verify_stream(&Mock, &info, stream::iter(rounds)));
+    assert!(matches!(res, Err(Error::IOError(_, _, _))));
+}
+
+#[test]
+fn test_verify_offline() {
+    // mainnet's hash-info and `/public/1`.
+    let info = Info {
+        public_key: hex::decode("868f005eb8e6e4ca0a47c8a77ceaa5309a47978a7c71bc5cce96366b5d7a569937c529eeda66c7293784a9402801af31").unwrap(),
+        group_hash: hex::decode("176f93498eac9ca337150b46d21dd58673ea4e3581185f869672e59fa4cb390a").unwrap(),
+        ..Info::default()
+    };
+    let r = Random {
+        round: 1,
+        randomness: hex::decode("101297f1ca7dc44ef6088d94ad5fb7ba03455dc33d53ddb412bbc4564ed986ec").unwrap(),
+        signature: hex::decode("8d61d9100567de44682506aea1a7a6fa6e5491cd27a0a0ed349ef6910ac5ac20ff7bc3e09d7c046566c9f7f3c6f3b10104990e7cb424998203d8f7de586fb7fa5f60045417a432684f85093b06ca91c769f0e7ca19268375e659c2a2352b4655").unwrap(),
+        previous_signature: info.group_hash.clone(),
+    };
+    verify_offline(&info, &r).unwrap();
+
+    let mut bad = r.clone();
+    bad.randomness[0] ^= 0xff;
+    assert!(matches!(
+        verify_offline(&info, &bad),
+        Err(Error::NotSecure(_, _, _))
+    ));
+
+    // round-1 must link to the group_hash.
+    let mut bad = r;
+    bad.previous_signature = vec![0; 32];
+    assert!(matches!(
+        verify_offline(&info, &bad),
+        Err(Error::NotSecure(_, _, _))
+    ));
+
+    // quicknet's `/public/1000`, signed on G1, against the bundled
+    // hash-info.
+    let signature = hex::decode("b44679b9a59af2ec876b1a6b1ad52ea9b1615fc3982b19576350f93447cb1125e342b73a8dd2bacbe47e4b6b63ed5e39").unwrap();
+    let r = Random {
+        round: 1000,
+        randomness: Sha256::digest(&signature).to_vec(),
+        signature,
+        previous_signature: vec![],
+    };
+    let info = Info::quicknet_default();
+    verify_offline(&info, &r).unwrap();
+
+    let mut bad = r;
+    bad.round = 1001;
+    assert!(matches!(
+        verify_offline(&info, &bad),
+        Err(Error::NotSecure(_, _, _))
+    ));
+}